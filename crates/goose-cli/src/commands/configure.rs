@@ -1,9 +1,9 @@
 use cliclack::spinner;
 use console::style;
 use goose::agents::{extension::Envs, ExtensionConfig};
-use goose::config::{Config, ConfigError, ExtensionEntry, ExtensionManager};
+use goose::config::{Config, ConfigError, ExtensionEntry, ExtensionManager, PROFILE_KEY};
 use goose::message::Message;
-use goose::providers::{create, providers};
+use goose::providers::{create, providers, supported_models};
 use mcp_core::Tool;
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -134,6 +134,13 @@ pub async fn handle_configure() -> Result<(), Box<dyn Error>> {
             style("  if you prefer, you can edit it directly at").dim(),
             config.path()
         );
+        if let Ok(Some(profile)) = config.active_profile() {
+            println!(
+                "{} {}",
+                style("  changes are saved to the profile").dim(),
+                style(profile).cyan()
+            );
+        }
         println!();
 
         cliclack::intro(style(" goose-configure ").on_cyan().black())?;
@@ -150,10 +157,22 @@ pub async fn handle_configure() -> Result<(), Box<dyn Error>> {
                 "Enable or disable connected extensions",
             )
             .item("remove", "Remove Extension", "Remove an extension")
+            .item(
+                "sampling",
+                "Configure Sampling",
+                "Set the temperature and other sampling parameters",
+            )
+            .item(
+                "profiles",
+                "Manage Profiles",
+                "Create, copy or delete named profiles",
+            )
             .interact()?;
 
         match action {
             "toggle" => toggle_extensions_dialog(),
+            "sampling" => configure_sampling_dialog(),
+            "profiles" => configure_profiles_dialog().await,
             "add" => configure_extensions_dialog(),
             "remove" => remove_extension_dialog(),
             "providers" => configure_provider_dialog().await.and(Ok(())),
@@ -281,9 +300,35 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
 
     // Select model, defaulting to the provider's recommended model UNLESS there is an env override
     let default_model = std::env::var("GOOSE_MODEL").unwrap_or(provider_meta.default_model.clone());
-    let model: String = cliclack::input("Enter a model from that provider:")
-        .default_input(&default_model)
-        .interact()?;
+    let available_models = discover_models(provider_name, &default_model).await;
+    let model: String = if available_models.is_empty() {
+        cliclack::input("Enter a model from that provider:")
+            .default_input(&default_model)
+            .interact()?
+    } else {
+        let mut items: Vec<(String, String, &str)> = available_models
+            .iter()
+            .map(|m| (m.clone(), m.clone(), ""))
+            .collect();
+        items.push((String::new(), "Other".to_string(), "Enter a model name"));
+        let initial = available_models
+            .iter()
+            .find(|m| **m == default_model || m.strip_suffix(":latest") == Some(&default_model))
+            .cloned()
+            .unwrap_or_default();
+        let selected = cliclack::select("Which model should we use?")
+            .initial_value(initial)
+            .items(&items)
+            .filter_mode()
+            .interact()?;
+        if selected.is_empty() {
+            cliclack::input("Enter a model from that provider:")
+                .default_input(&default_model)
+                .interact()?
+        } else {
+            selected
+        }
+    };
 
     // Test the configuration
     let spin = spinner();
@@ -331,10 +376,253 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
     }
 }
 
+/// Models to choose from for the provider, fetched from its API when possible
+async fn discover_models(provider_name: &str, default_model: &str) -> Vec<String> {
+    let Ok(provider) = create(
+        provider_name,
+        goose::model::ModelConfig::new(default_model.to_string()),
+    ) else {
+        return vec![];
+    };
+
+    let spin = spinner();
+    spin.start("Fetching available models...");
+    let models = supported_models(provider_name, provider.as_ref()).await;
+    spin.stop("Fetched available models");
+
+    if models.is_empty() && provider_name == "ollama" {
+        let _ = cliclack::log::warning(
+            "Ollama has no models yet, download one with `ollama pull <model>`",
+        );
+    }
+    models
+}
+
+/// Sampling parameters that can be set in the configure dialog, with whether they are integers
+const SAMPLING_PARAMETERS: &[(&str, &str, bool)] = &[
+    ("GOOSE_TEMPERATURE", "Temperature", false),
+    ("GOOSE_MAX_TOKENS", "Max output tokens", true),
+    ("GOOSE_TOP_P", "Top p", false),
+    ("GOOSE_TOP_K", "Top k", true),
+    ("GOOSE_SEED", "Seed", true),
+    ("GOOSE_FREQUENCY_PENALTY", "Frequency penalty", false),
+    ("GOOSE_PRESENCE_PENALTY", "Presence penalty", false),
+    ("GOOSE_STOP_SEQUENCES", "Stop sequences", false),
+];
+
+/// Dialog for setting the sampling parameters of the model
+pub fn configure_sampling_dialog() -> Result<(), Box<dyn Error>> {
+    let config = Config::global();
+
+    let items: Vec<(&str, &str, String)> = SAMPLING_PARAMETERS
+        .iter()
+        .map(|(key, label, _)| {
+            let current = config
+                .get::<Value>(key)
+                .map(|value| value.to_string())
+                .unwrap_or_else(|_| "provider default".to_string());
+            (*key, *label, current)
+        })
+        .collect();
+    let key = cliclack::select("Which parameter would you like to set?")
+        .items(&items)
+        .interact()?;
+    let (_, label, integer) = SAMPLING_PARAMETERS
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .expect("selected parameter is listed");
+
+    let value: String = if key == "GOOSE_STOP_SEQUENCES" {
+        cliclack::input(format!(
+            "{label}, separated by commas (leave empty to use none)"
+        ))
+        .required(false)
+        .interact()?
+    } else {
+        let integer = *integer;
+        cliclack::input(format!(
+            "{label} (leave empty to use the provider's default)"
+        ))
+        .required(false)
+        .validate(move |input: &String| {
+            let input = input.trim();
+            let valid = input.is_empty()
+                || if integer {
+                    input.parse::<i64>().is_ok()
+                } else {
+                    input.parse::<f64>().is_ok()
+                };
+            if valid {
+                Ok(())
+            } else if integer {
+                Err("Please enter a whole number")
+            } else {
+                Err("Please enter a number")
+            }
+        })
+        .interact()?
+    };
+
+    let value = value.trim();
+    if value.is_empty() {
+        if config.get::<Value>(key).is_ok() {
+            config.delete(key)?;
+        }
+    } else if key == "GOOSE_STOP_SEQUENCES" {
+        let sequences: Vec<&str> = value.split(',').map(str::trim).collect();
+        config.set(key, json!(sequences))?;
+    } else {
+        config.set(key, serde_json::from_str(value)?)?;
+    }
+
+    cliclack::outro(format!("{label} updated"))?;
+    Ok(())
+}
+
+/// Dialog for creating, copying and deleting profiles, and choosing the default one
+pub async fn configure_profiles_dialog() -> Result<(), Box<dyn Error>> {
+    let config = Config::global();
+    let profiles = config.profiles()?;
+
+    let mut select = cliclack::select("What would you like to do?").item(
+        "create",
+        "Create Profile",
+        "Start a profile from your base provider, model and extensions",
+    );
+    if !profiles.is_empty() {
+        select = select
+            .item("copy", "Copy Profile", "Create a profile from another one")
+            .item("delete", "Delete Profile", "Remove a profile")
+            .item(
+                "default",
+                "Set Default Profile",
+                "Choose the profile used without --profile",
+            );
+    }
+    let action = select.interact()?;
+
+    match action {
+        "create" | "copy" => {
+            let from = if action == "copy" {
+                Some(select_profile(
+                    "Which profile would you like to copy?",
+                    &profiles,
+                )?)
+            } else {
+                None
+            };
+            let existing = profiles.clone();
+            let name: String = cliclack::input("What should the new profile be called?")
+                .placeholder("work")
+                .validate(move |input: &String| {
+                    let input = input.trim();
+                    if input.is_empty() {
+                        Err("Please enter a name")
+                    } else if !input
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    {
+                        Err("Names can only contain letters, numbers, - and _")
+                    } else if existing.iter().any(|p| p == input) {
+                        Err("A profile with this name already exists")
+                    } else {
+                        Ok(())
+                    }
+                })
+                .interact()?;
+            let name = name.trim().to_string();
+            config.copy_profile(from.as_deref(), &name)?;
+
+            if cliclack::confirm(format!("Configure the provider of {name} now?"))
+                .initial_value(true)
+                .interact()?
+            {
+                // Everything configured from here on is saved to the new profile
+                std::env::set_var(PROFILE_KEY, &name);
+                configure_provider_dialog().await?;
+            }
+            if cliclack::confirm(format!("Use {name} by default?"))
+                .initial_value(false)
+                .interact()?
+            {
+                config.set_default_profile(Some(&name))?;
+            }
+            cliclack::outro(format!(
+                "Created profile {name}, use it with --profile {name}"
+            ))?;
+        }
+        "delete" => {
+            let name = select_profile("Which profile would you like to delete?", &profiles)?;
+            if cliclack::confirm(format!("Are you sure you want to delete {name}?"))
+                .initial_value(false)
+                .interact()?
+            {
+                config.delete_profile(&name)?;
+                cliclack::outro(format!("Deleted profile {name}"))?;
+            }
+        }
+        "default" => {
+            let current = config.default_profile()?;
+            let mut items: Vec<(String, String, &str)> = vec![(
+                String::new(),
+                "None".to_string(),
+                "Use the settings outside of profiles",
+            )];
+            items.extend(profiles.iter().map(|p| (p.clone(), p.clone(), "")));
+            let name = cliclack::select("Which profile should be used by default?")
+                .items(&items)
+                .initial_value(current.unwrap_or_default())
+                .interact()?;
+            if name.is_empty() {
+                config.set_default_profile(None)?;
+                cliclack::outro("Profiles are now only used with --profile")?;
+            } else {
+                config.set_default_profile(Some(&name))?;
+                cliclack::outro(format!("{name} is now the default profile"))?;
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+fn select_profile(prompt: &str, profiles: &[String]) -> Result<String, Box<dyn Error>> {
+    let items: Vec<(String, &str, &str)> = profiles
+        .iter()
+        .map(|p| (p.clone(), p.as_str(), ""))
+        .collect();
+    Ok(cliclack::select(prompt).items(&items).interact()?)
+}
+
+/// Point out the extensions of the project config, which the dialogs can't change since
+/// only the config file is written
+fn show_project_extensions() {
+    let config = Config::global();
+    let Some(path) = config.project_path() else {
+        return;
+    };
+    let extensions: HashMap<String, ExtensionEntry> =
+        config.get_project("extensions").unwrap_or_default();
+    if extensions.is_empty() {
+        return;
+    }
+    let mut names: Vec<&str> = extensions
+        .values()
+        .map(|entry| entry.config.name())
+        .collect();
+    names.sort();
+    let _ = cliclack::log::info(format!(
+        "The project extensions {} are set in {}, edit that file to change them",
+        names.join(", "),
+        path.display()
+    ));
+}
+
 /// Configure extensions that can be used with goose
 /// Dialog for toggling which extensions are enabled/disabled
 pub fn toggle_extensions_dialog() -> Result<(), Box<dyn Error>> {
-    let extensions = ExtensionManager::get_all()?;
+    show_project_extensions();
+    let extensions = ExtensionManager::get_all_global()?;
 
     if extensions.is_empty() {
         cliclack::outro(
@@ -568,7 +856,8 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
 }
 
 pub fn remove_extension_dialog() -> Result<(), Box<dyn Error>> {
-    let extensions = ExtensionManager::get_all()?;
+    show_project_extensions();
+    let extensions = ExtensionManager::get_all_global()?;
 
     // Create a list of extension names and their enabled status
     let extension_status: Vec<(String, bool)> = extensions
@@ -1,3 +1,5 @@
 pub mod agent_version;
 pub mod configure;
 pub mod mcp;
+pub mod model;
+pub mod prompt;
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use clap::Args;
+use console::style;
+use goose::model::ModelConfig;
+use goose::model_registry::{ModelInfo, ModelRegistry};
+
+#[derive(Args)]
+pub struct ModelCommand {
+    /// Model to show, lists every model in the registry when omitted
+    #[arg(value_name = "MODEL")]
+    name: Option<String>,
+}
+
+impl ModelCommand {
+    pub fn run(&self) -> Result<()> {
+        let registry = ModelRegistry::global();
+
+        let Some(name) = &self.name else {
+            for model in registry.models() {
+                println!(
+                    "{:<32} {:>9} {}",
+                    model.name,
+                    model
+                        .context_limit
+                        .map(|limit| limit.to_string())
+                        .unwrap_or_default(),
+                    style(model.providers.join(", ")).dim()
+                );
+            }
+            if let Some(path) = ModelRegistry::user_registry_path() {
+                println!(
+                    "\nAdd or override models in {}",
+                    style(path.display()).cyan()
+                );
+            }
+            return Ok(());
+        };
+
+        let config = ModelConfig::new(name.clone());
+        match registry.lookup(name) {
+            Some(model) => print_model(name, model),
+            None => println!(
+                "goose has no registry entry for {}, using defaults",
+                style(name).cyan()
+            ),
+        }
+        println!("{:<18} {}", "Context limit:", config.context_limit());
+        println!("{:<18} {}", "Tokenizer:", config.tokenizer_name());
+
+        Ok(())
+    }
+}
+
+fn print_model(name: &str, model: &ModelInfo) {
+    if model.name == name {
+        println!("{}", style(&model.name).cyan().bold());
+    } else {
+        println!(
+            "{} {}",
+            style(name).cyan().bold(),
+            style(format!("(matches {})", model.name)).dim()
+        );
+    }
+
+    if !model.providers.is_empty() {
+        println!("{:<18} {}", "Providers:", model.providers.join(", "));
+    }
+    if let Some(max_output_tokens) = model.max_output_tokens {
+        println!("{:<18} {}", "Max output tokens:", max_output_tokens);
+    }
+    if let Some(pricing) = &model.pricing {
+        println!(
+            "{:<18} ${} input / ${} output per million tokens",
+            "Pricing:", pricing.input, pricing.output
+        );
+    }
+
+    let capabilities = [
+        ("tools", model.capabilities.tools),
+        ("vision", model.capabilities.vision),
+        ("reasoning", model.capabilities.reasoning),
+        ("system prompt", model.capabilities.system_prompt),
+        (
+            "parallel tool calls",
+            model.capabilities.parallel_tool_calls,
+        ),
+    ];
+    let supported: Vec<&str> = capabilities
+        .iter()
+        .filter(|(_, supported)| *supported)
+        .map(|(capability, _)| *capability)
+        .collect();
+    println!(
+        "{:<18} {}",
+        "Capabilities:",
+        if supported.is_empty() {
+            "none".to_string()
+        } else {
+            supported.join(", ")
+        }
+    );
+}
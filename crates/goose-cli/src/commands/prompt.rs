@@ -0,0 +1,60 @@
+use anyhow::Result;
+use clap::Args;
+use console::style;
+use goose::prompt_template::{
+    find_template_override, template_search_paths, validate_system_prompt_template,
+};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct PromptCommand {
+    /// Template to validate, defaults to the system prompt override goose would use
+    #[arg(value_name = "FILE")]
+    path: Option<PathBuf>,
+}
+
+impl PromptCommand {
+    pub fn run(&self) -> Result<()> {
+        let path = match self
+            .path
+            .clone()
+            .or_else(|| find_template_override("system.md"))
+        {
+            Some(path) => path,
+            None => {
+                println!("No custom system prompt found, goose uses the built-in prompt.");
+                println!("Place a system.md in one of these directories to override it:");
+                for dir in template_search_paths() {
+                    println!("  {}", dir.display());
+                }
+                return Ok(());
+            }
+        };
+
+        match validate_system_prompt_template(&path) {
+            Ok(rendered) => {
+                println!("{}\n", rendered);
+                println!(
+                    "{} {} is a valid system prompt template",
+                    style("✔").green(),
+                    path.display()
+                );
+                Ok(())
+            }
+            Err(e) => {
+                // Tera nests the useful detail in the error source chain
+                let mut message = e.to_string();
+                let mut source = std::error::Error::source(&e);
+                while let Some(inner) = source {
+                    message.push_str(&format!("\n  caused by: {}", inner));
+                    source = inner.source();
+                }
+                Err(anyhow::anyhow!(
+                    "{} is not a valid template: {}",
+                    path.display(),
+                    message
+                ))
+            }
+        }
+    }
+}
@@ -2,10 +2,12 @@ use anyhow::Result;
 use clap::{CommandFactory, Parser, Subcommand};
 
 use console::style;
-use goose::config::Config;
+use goose::config::{Config, PROFILE_KEY};
 use goose_cli::commands::agent_version::AgentCommand;
 use goose_cli::commands::configure::handle_configure;
 use goose_cli::commands::mcp::run_server;
+use goose_cli::commands::model::ModelCommand;
+use goose_cli::commands::prompt::PromptCommand;
 use goose_cli::logging::setup_logging;
 use goose_cli::session::build_session;
 use std::io::{self, Read};
@@ -71,6 +73,15 @@ enum Command {
             value_delimiter = ','
         )]
         builtin: Vec<String>,
+
+        /// Configuration profile to use
+        #[arg(
+            long,
+            value_name = "PROFILE",
+            help = "Use a named configuration profile (e.g., 'work')",
+            long_help = "Use the provider, model, agent and extensions of a named profile created with 'goose configure'. Overrides GOOSE_PROFILE and the default profile."
+        )]
+        profile: Option<String>,
     },
 
     /// Execute commands from an instruction file
@@ -136,10 +147,36 @@ enum Command {
             value_delimiter = ','
         )]
         builtin: Vec<String>,
+
+        /// Configuration profile to use
+        #[arg(
+            long,
+            value_name = "PROFILE",
+            help = "Use a named configuration profile (e.g., 'work')",
+            long_help = "Use the provider, model, agent and extensions of a named profile created with 'goose configure'. Overrides GOOSE_PROFILE and the default profile."
+        )]
+        profile: Option<String>,
+
+        /// JSON schema for the final answer
+        #[arg(
+            long = "output-schema",
+            value_name = "SCHEMA",
+            help = "JSON schema the final answer must match, as a file or inline JSON",
+            long_help = "Require the final answer to be JSON matching this schema, given as the path of a schema file or as inline JSON. Only the validated JSON is printed, and the run fails if the model can't produce it."
+        )]
+        output_schema: Option<String>,
     },
 
     /// List available agent versions
     Agents(AgentCommand),
+
+    /// Validate a custom system prompt template
+    #[command(about = "Validate a custom system prompt template")]
+    Prompt(PromptCommand),
+
+    /// Show what goose knows about a model
+    #[command(about = "Show context limits, tokenizer, pricing and capabilities of a model")]
+    Model(ModelCommand),
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -149,6 +186,49 @@ enum CliProviderVariant {
     Ollama,
 }
 
+/// Select a profile for this process and the extensions it starts, or exit if it doesn't exist
+fn use_profile(name: &str) {
+    match Config::global().profiles() {
+        Ok(profiles) if profiles.iter().any(|p| p == name) => {
+            std::env::set_var(PROFILE_KEY, name);
+        }
+        Ok(profiles) => {
+            eprintln!(
+                "Error: No profile named '{}', available profiles: {}",
+                name,
+                if profiles.is_empty() {
+                    "none".to_string()
+                } else {
+                    profiles.join(", ")
+                }
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to read profiles: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Exit if the profile selected by GOOSE_PROFILE doesn't exist
+fn check_profile() {
+    if let Err(e) = Config::global().check_profile() {
+        let profiles = Config::global().profiles().unwrap_or_default();
+        eprintln!(
+            "Error: {}, set {} to one of: {}",
+            e,
+            PROFILE_KEY,
+            if profiles.is_empty() {
+                "none".to_string()
+            } else {
+                profiles.join(", ")
+            }
+        );
+        std::process::exit(1);
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -166,7 +246,12 @@ async fn main() -> Result<()> {
             resume,
             extension,
             builtin,
+            profile,
         }) => {
+            match profile {
+                Some(profile) => use_profile(&profile),
+                None => check_profile(),
+            }
             let mut session = build_session(name, resume, extension, builtin).await;
             setup_logging(session.session_file().file_stem().and_then(|s| s.to_str()))?;
             let _ = session.start().await;
@@ -179,7 +264,13 @@ async fn main() -> Result<()> {
             resume,
             extension,
             builtin,
+            profile,
+            output_schema,
         }) => {
+            match profile {
+                Some(profile) => use_profile(&profile),
+                None => check_profile(),
+            }
             // Validate that we have some input source
             if instructions.is_none() && input_text.is_none() {
                 eprintln!("Error: Must provide either --instructions or --text");
@@ -200,6 +291,17 @@ async fn main() -> Result<()> {
             };
             let mut session = build_session(name, resume, extension, builtin).await;
             setup_logging(session.session_file().file_stem().and_then(|s| s.to_str()))?;
+            if let Some(schema) = output_schema {
+                if let Err(e) = session.set_output_schema(&schema).await {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+                if let Err(e) = session.headless_start(contents).await {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
             let _ = session.headless_start(contents.clone()).await;
             return Ok(());
         }
@@ -207,6 +309,14 @@ async fn main() -> Result<()> {
             cmd.run()?;
             return Ok(());
         }
+        Some(Command::Prompt(cmd)) => {
+            cmd.run()?;
+            return Ok(());
+        }
+        Some(Command::Model(cmd)) => {
+            cmd.run()?;
+            return Ok(());
+        }
         None => {
             Cli::command().print_help()?;
             println!();
@@ -1,8 +1,10 @@
 use console::style;
 use goose::agents::extension::ExtensionError;
 use goose::agents::AgentFactory;
-use goose::config::{Config, ExtensionManager};
+use goose::config::{Config, ExtensionEntry, ExtensionManager};
 use mcp_client::transport::Error as McpClientError;
+use std::collections::HashMap;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::process;
 
@@ -27,7 +29,7 @@ pub async fn build_session(
     let model: String = config
         .get("GOOSE_MODEL")
         .expect("No model configured. Run 'goose configure' first");
-    let model_config = goose::model::ModelConfig::new(model.clone());
+    let model_config = goose::model::ModelConfig::new(model.clone()).with_config_defaults(config);
     let provider =
         goose::providers::create(&provider_name, model_config).expect("Failed to create provider");
 
@@ -39,7 +41,8 @@ pub async fn build_session(
     }
     .expect("Failed to create agent");
 
-    // Setup extensions for the agent
+    // Setup extensions for the agent, those of the project once it is trusted
+    confirm_project_trust(config);
     for extension in ExtensionManager::get_all().expect("should load extensions") {
         if extension.enabled {
             let config = extension.config.clone();
@@ -117,10 +120,79 @@ pub async fn build_session(
         .extend_system_prompt(super::prompt::get_cli_prompt())
         .await;
 
+    // Add the instructions configured for the user or the project
+    if let Ok(instructions) = config.get::<String>("GOOSE_INSTRUCTIONS") {
+        session.agent.extend_system_prompt(instructions).await;
+    }
+
     output::display_session_info(resume, &provider_name, &model, &session_file);
+    match config.layers() {
+        Ok(layers) => output::display_config_layers(&layers),
+        Err(e) => output::render_error(&format!("Failed to load config: {}", e)),
+    }
     session
 }
 
+/// Ask the user to trust the project config if it has extensions, instructions or prompt
+/// templates, which are only used once it is trusted
+///
+/// They run commands chosen by the repository, or can tell the agent to. Without a
+/// terminal to ask on, they are left out.
+fn confirm_project_trust(config: &Config) {
+    let mut parts = Vec::new();
+    let extensions: HashMap<String, ExtensionEntry> =
+        config.get_project("extensions").unwrap_or_default();
+    let mut names: Vec<&str> = extensions
+        .values()
+        .filter(|entry| entry.enabled)
+        .map(|entry| entry.config.name())
+        .collect();
+    if !names.is_empty() {
+        names.sort();
+        parts.push(format!("the extensions {}", names.join(", ")));
+    }
+    if config.get_project::<String>("GOOSE_INSTRUCTIONS").is_ok() {
+        parts.push("instructions".to_string());
+    }
+    let has_templates = config
+        .project_prompts_dir()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .is_some_and(|mut entries| entries.next().is_some());
+    if has_templates {
+        parts.push("prompt templates".to_string());
+    }
+    if parts.is_empty() || config.is_project_trusted().unwrap_or(false) {
+        return;
+    }
+
+    let path = config
+        .project_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    let trusted = std::io::stdin().is_terminal()
+        && cliclack::confirm(format!(
+            "{} has {}, which can run commands on your machine. Trust this project config?",
+            path,
+            parts.join(", ")
+        ))
+        .initial_value(false)
+        .interact()
+        .unwrap_or(false);
+    if trusted {
+        if let Err(e) = config.trust_project() {
+            eprintln!("Failed to save the trusted project config: {}", e);
+        }
+        return;
+    }
+
+    eprintln!(
+        "{} {} {}",
+        style("not using the untrusted").yellow(),
+        style(parts.join(", ")).yellow(),
+        style(format!("of {}", path)).dim(),
+    );
+}
+
 fn generate_session_name() -> String {
     use rand::{distributions::Alphanumeric, Rng};
     rand::thread_rng()
@@ -9,10 +9,11 @@ pub use builder::build_session;
 
 use anyhow::Result;
 use goose::agents::extension::{Envs, ExtensionConfig};
-use goose::agents::Agent;
+use goose::agents::{Agent, AgentEvent};
 use goose::message::{Message, MessageContent};
 use mcp_core::handler::ToolError;
 use rand::{distributions::Alphanumeric, Rng};
+use serde_json::Value;
 use std::path::PathBuf;
 use tokio;
 
@@ -22,6 +23,9 @@ pub struct Session {
     agent: Box<dyn Agent>,
     messages: Vec<Message>,
     session_file: PathBuf,
+    /// Whether only the structured output is printed, see [`Session::set_output_schema`]
+    structured_output: bool,
+    output_printed: bool,
 }
 
 impl Session {
@@ -38,9 +42,30 @@ impl Session {
             agent,
             messages,
             session_file,
+            structured_output: false,
+            output_printed: false,
         }
     }
 
+    /// Require the final answer to match a JSON schema, given as a file path or inline JSON
+    ///
+    /// Only the validated output is printed after this, everything else goes to the
+    /// session file and logs.
+    pub async fn set_output_schema(&mut self, schema: &str) -> Result<()> {
+        let schema = if schema.trim_start().starts_with('{') {
+            schema.to_string()
+        } else {
+            std::fs::read_to_string(schema).map_err(|e| {
+                anyhow::anyhow!("Failed to read the output schema {}: {}", schema, e)
+            })?
+        };
+        let schema: Value = serde_json::from_str(&schema)
+            .map_err(|e| anyhow::anyhow!("The output schema is not valid JSON: {}", e))?;
+        self.agent.set_output_schema(Some(schema)).await?;
+        self.structured_output = true;
+        Ok(())
+    }
+
     /// Add a stdio extension to the session
     ///
     /// # Arguments
@@ -182,24 +207,63 @@ impl Session {
             .push(Message::user().with_text(&initial_message));
         storage::persist_messages(&self.session_file, &self.messages)?;
         self.process_agent_response().await?;
+        if self.structured_output && !self.output_printed {
+            return Err(anyhow::anyhow!(
+                "No answer matching the output schema was produced"
+            ));
+        }
         Ok(())
     }
 
     async fn process_agent_response(&mut self) -> Result<()> {
-        let mut stream = self.agent.reply(&self.messages).await?;
+        let mut stream = self.agent.reply_events(&self.messages).await?;
 
         use futures::StreamExt;
         loop {
             tokio::select! {
                 result = stream.next() => {
                     match result {
-                        Some(Ok(message)) => {
-                            self.messages.push(message.clone());
-                            storage::persist_messages(&self.session_file, &self.messages)?;
+                        Some(Ok(AgentEvent::StructuredOutput(output))) => {
+                            println!("{}", serde_json::to_string_pretty(&output)?);
+                            self.output_printed = true;
+                        }
+                        Some(Ok(event)) if self.structured_output => {
+                            if let AgentEvent::Error(error) = &event {
+                                eprintln!("Error: {}", error);
+                            }
+                            if let Some(message) = event.into_message() {
+                                self.messages.push(message);
+                                storage::persist_messages(&self.session_file, &self.messages)?;
+                            }
+                        }
+                        Some(Ok(AgentEvent::ContextTruncated { messages_before, messages_after })) => {
+                            output::hide_thinking();
+                            output::render_context_truncated(messages_before.saturating_sub(messages_after));
+                            output::show_thinking();
+                        }
+                        Some(Ok(AgentEvent::Notice(notice))) => {
                             output::hide_thinking();
-                            output::render_message(&message);
+                            output::render_notice(&notice);
                             output::show_thinking();
                         }
+                        Some(Ok(AgentEvent::ToolCallFinished { name, duration, .. })) => {
+                            tracing::debug!("Tool call {} finished in {:?}", name, duration);
+                        }
+                        Some(Ok(event)) => {
+                            if let Some(message) = event.into_message() {
+                                self.messages.push(message.clone());
+                                storage::persist_messages(&self.session_file, &self.messages)?;
+                                output::hide_thinking();
+                                output::render_message(&message);
+                                output::show_thinking();
+                            }
+                        }
+                        Some(Err(e)) if self.structured_output => {
+                            // Stdout is reserved for the output, the caller reports the error
+                            drop(stream);
+                            self.handle_interrupted_messages(false);
+                            return Err(e);
+                        }
                         Some(Err(e)) => {
                             eprintln!("Error: {}", e);
                             drop(stream);
@@ -271,7 +335,7 @@ impl Session {
                 last_tool_name
             );
             self.messages.push(Message::assistant().with_text(&prompt));
-            output::render_message(&Message::assistant().with_text(&prompt));
+            self.render_interruption(&prompt);
         } else {
             // An interruption occurred outside of a tool request-response.
             if let Some(last_msg) = self.messages.last() {
@@ -281,13 +345,13 @@ impl Session {
                             // Interruption occurred after a tool had completed but not assistant reply
                             let prompt = "The tool calling loop was interrupted. How would you like to proceed?";
                             self.messages.push(Message::assistant().with_text(prompt));
-                            output::render_message(&Message::assistant().with_text(prompt));
+                            self.render_interruption(prompt);
                         }
                         Some(_) => {
                             // A real users message
                             self.messages.pop();
                             let prompt = "Interrupted before the model replied and removed the last message.";
-                            output::render_message(&Message::assistant().with_text(prompt));
+                            self.render_interruption(prompt);
                         }
                         None => panic!("No content in last message"),
                     }
@@ -296,6 +360,13 @@ impl Session {
         }
     }
 
+    // Show how an interruption was handled, unless stdout is reserved for structured output
+    fn render_interruption(&self, prompt: &str) {
+        if !self.structured_output {
+            output::render_message(&Message::assistant().with_text(prompt));
+        }
+    }
+
     pub fn session_file(&self) -> PathBuf {
         self.session_file.clone()
     }
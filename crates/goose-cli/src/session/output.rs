@@ -1,5 +1,6 @@
 use bat::WrappingMode;
 use console::style;
+use goose::config::ConfigLayer;
 use goose::message::{Message, MessageContent, ToolRequest, ToolResponse};
 use mcp_core::tool::ToolCall;
 use serde_json::Value;
@@ -96,6 +97,16 @@ pub fn render_message(message: &Message) {
             MessageContent::Image(image) => {
                 println!("Image: [data: {}, type: {}]", image.data, image.mime_type);
             }
+            MessageContent::Document(document) => {
+                println!(
+                    "Document: [name: {}, type: {}]",
+                    document.name.as_deref().unwrap_or("unnamed"),
+                    document.mime_type
+                );
+            }
+            MessageContent::Audio(audio) => {
+                println!("Audio: [type: {}]", audio.mime_type);
+            }
         }
     }
     println!();
@@ -148,6 +159,19 @@ pub fn render_error(message: &str) {
     println!("\n  {} {}\n", style("error:").red().bold(), message);
 }
 
+pub fn render_context_truncated(removed: usize) {
+    println!(
+        "\n  {} removed {} earlier message{} to fit the context limit\n",
+        style("context:").yellow().bold(),
+        removed,
+        if removed == 1 { "" } else { "s" }
+    );
+}
+
+pub fn render_notice(message: &str) {
+    println!("\n  {} {}\n", style("note:").yellow().bold(), message);
+}
+
 pub fn render_extension_success(name: &str) {
     println!();
     println!(
@@ -387,7 +411,7 @@ pub fn display_session_info(resume: bool, provider: &str, model: &str, session_f
     } else {
         "starting session |"
     };
-    println!(
+    eprintln!(
         "{} {} {} {} {}",
         style(start_session_msg).dim(),
         style("provider:").dim(),
@@ -395,13 +419,36 @@ pub fn display_session_info(resume: bool, provider: &str, model: &str, session_f
         style("model:").dim(),
         style(model).cyan().dim(),
     );
-    println!(
+    eprintln!(
         "    {} {}",
         style("logging to").dim(),
         style(session_file.display()).dim().cyan(),
     );
 }
 
+pub fn display_config_layers(layers: &[ConfigLayer]) {
+    let names: Vec<String> = layers.iter().map(ToString::to_string).collect();
+    if !names.is_empty() {
+        eprintln!(
+            "    {} {}",
+            style("config").dim(),
+            style(names.join(", ")).dim().cyan(),
+        );
+    }
+    for layer in layers {
+        if let ConfigLayer::Project { path, ignored } = layer {
+            if !ignored.is_empty() {
+                eprintln!(
+                    "    {} {} {}",
+                    style("ignored in").yellow().dim(),
+                    style(path.display()).dim(),
+                    style(ignored.join(", ")).yellow().dim(),
+                );
+            }
+        }
+    }
+}
+
 pub fn display_greeting() {
     println!("\nGoose is running! Enter your instructions, or try asking what goose can do.\n");
 }
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json::{json, Value};
+use tokio::process::Command;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Replies with each text in turn, repeating the last one
+struct Replies {
+    texts: Vec<&'static str>,
+    calls: AtomicUsize,
+}
+
+impl Respond for Replies {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        let text = self.texts[call.min(self.texts.len() - 1)];
+        ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": text},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_stdout_is_only_the_output_after_a_retry() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(Replies {
+            // The tools are done, then an invalid output is corrected
+            texts: vec!["Done.", "The answer is 42", r#"{"answer": 42}"#],
+            calls: AtomicUsize::new(0),
+        })
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_goose"))
+        .args([
+            "run",
+            "--text",
+            "What is the answer?",
+            "--output-schema",
+            r#"{"type": "object", "required": ["answer"], "properties": {"answer": {"type": "integer"}}}"#,
+        ])
+        .current_dir(home.path())
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("XDG_DATA_HOME", home.path().join(".local/share"))
+        .env("XDG_STATE_HOME", home.path().join(".local/state"))
+        .env("GOOSE_PROVIDER", "openai")
+        .env("GOOSE_MODEL", "gpt-4o")
+        .env("OPENAI_HOST", server.uri())
+        .env("OPENAI_API_KEY", "test-key")
+        .env_remove("GOOSE_PROFILE")
+        .env_remove("RUST_LOG")
+        .output()
+        .await
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    let parsed: Value = serde_json::from_str(&stdout)
+        .unwrap_or_else(|e| panic!("stdout is not JSON ({e}): {stdout}"));
+    assert_eq!(parsed, json!({"answer": 42}));
+    assert!(stderr.contains("Invalid structured output"), "{stderr}");
+}
@@ -8,6 +8,9 @@ pub async fn run() -> Result<()> {
     // Initialize logging
     crate::logging::setup_logging(Some("goosed"))?;
 
+    // Fail now if GOOSE_PROFILE names a profile that doesn't exist
+    goose::config::Config::global().check_profile()?;
+
     // Load configuration
     let settings = configuration::Settings::new()?;
 
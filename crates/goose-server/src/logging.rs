@@ -9,6 +9,7 @@ use tracing_subscriber::{
 };
 
 use goose::tracing::langfuse_layer;
+use goose::tracing::redact::RedactingMakeWriter;
 
 /// Returns the directory where log files should be stored.
 /// Creates the directory structure if it doesn't exist.
@@ -39,6 +40,8 @@ fn get_log_directory() -> Result<PathBuf> {
 /// - File-based logging with JSON formatting (DEBUG level)
 /// - Console output for development (INFO level)
 /// - Optional Langfuse integration (DEBUG level)
+///
+/// Secrets are redacted from everything written, see [`goose::tracing::redact`].
 pub fn setup_logging(name: Option<&str>) -> Result<()> {
     // Set up file appender for goose module logs
     let log_dir = get_log_directory()?;
@@ -59,12 +62,13 @@ pub fn setup_logging(name: Option<&str>) -> Result<()> {
     let file_layer = fmt::layer()
         .with_target(true)
         .with_level(true)
-        .with_writer(file_appender)
+        .with_writer(RedactingMakeWriter::new(file_appender))
         .with_ansi(false)
         .with_file(true);
 
     // Create console logging layer for development - INFO and above only
     let console_layer = fmt::layer()
+        .with_writer(RedactingMakeWriter::new(std::io::stdout))
         .with_target(true)
         .with_level(true)
         .with_ansi(true)
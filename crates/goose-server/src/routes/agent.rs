@@ -7,9 +7,12 @@ use axum::{
 };
 use goose::config::Config;
 use goose::{agents::AgentFactory, model::ModelConfig, providers};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::env;
+use std::time::{Duration, Instant};
 
 #[derive(Serialize)]
 struct VersionsResponse {
@@ -32,6 +35,49 @@ struct CreateAgentRequest {
     version: Option<String>,
     provider: String,
     model: Option<String>,
+    #[serde(flatten)]
+    sampling: SamplingRequest,
+}
+
+/// Sampling parameters of a new agent, overriding the configured ones
+#[derive(Deserialize, Default)]
+struct SamplingRequest {
+    temperature: Option<f32>,
+    max_tokens: Option<i32>,
+    top_p: Option<f32>,
+    top_k: Option<u32>,
+    stop_sequences: Option<Vec<String>>,
+    seed: Option<i64>,
+    frequency_penalty: Option<f32>,
+    presence_penalty: Option<f32>,
+    extra_body: Option<Map<String, Value>>,
+}
+
+impl SamplingRequest {
+    fn apply(self, config: ModelConfig) -> ModelConfig {
+        let temperature = self.temperature.or(config.temperature);
+        let max_tokens = self.max_tokens.or(config.max_tokens);
+        let top_p = self.top_p.or(config.top_p);
+        let top_k = self.top_k.or(config.top_k);
+        let stop_sequences = self
+            .stop_sequences
+            .unwrap_or_else(|| config.stop_sequences.clone());
+        let seed = self.seed.or(config.seed);
+        let frequency_penalty = self.frequency_penalty.or(config.frequency_penalty);
+        let presence_penalty = self.presence_penalty.or(config.presence_penalty);
+        let extra_body = self.extra_body.unwrap_or_else(|| config.extra_body.clone());
+
+        config
+            .with_temperature(temperature)
+            .with_max_tokens(max_tokens)
+            .with_top_p(top_p)
+            .with_top_k(top_k)
+            .with_stop_sequences(stop_sequences)
+            .with_seed(seed)
+            .with_frequency_penalty(frequency_penalty)
+            .with_presence_penalty(presence_penalty)
+            .with_extra_body(extra_body)
+    }
 }
 
 #[derive(Serialize)]
@@ -124,7 +170,9 @@ async fn create_agent(
             .get("GOOSE_MODEL")
             .expect("Did not find a model on payload or in env")
     });
-    let model_config = ModelConfig::new(model);
+    let model_config = payload
+        .sampling
+        .apply(ModelConfig::new(model).with_config_defaults(config));
     let provider =
         providers::create(&payload.provider, model_config).expect("Failed to create provider");
 
@@ -146,23 +194,86 @@ async fn list_providers() -> Json<Vec<ProviderList>> {
     let providers: HashMap<String, ProviderFile> =
         serde_json::from_str(contents).expect("Failed to parse providers_and_keys.json");
 
-    let response: Vec<ProviderList> = providers
-        .into_iter()
-        .map(|(id, provider)| ProviderList {
-            id,
-            details: ProviderDetails {
-                name: provider.name,
-                description: provider.description,
-                models: provider.models,
-                required_keys: provider.required_keys,
-            },
-        })
-        .collect();
+    let response: Vec<ProviderList> =
+        futures::future::join_all(providers.into_iter().map(|(id, provider)| async move {
+            let models = configured_models(&id, &provider)
+                .await
+                .unwrap_or(provider.models);
+            ProviderList {
+                id,
+                details: ProviderDetails {
+                    name: provider.name,
+                    description: provider.description,
+                    models,
+                    required_keys: provider.required_keys,
+                },
+            }
+        }))
+        .await;
 
     // Return the response as JSON.
     Json(response)
 }
 
+/// Whether each provider has its required keys, and when that was checked
+///
+/// Checking reads the keyring, which is slow and may prompt to unlock it, so it is reused
+/// until the config is changed through the server. Keys can also change outside the server,
+/// e.g. with `goose configure`, so it is checked again after [`CONFIGURED_PROVIDERS_TTL`].
+static CONFIGURED_PROVIDERS: Lazy<std::sync::Mutex<HashMap<String, (bool, Instant)>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+const CONFIGURED_PROVIDERS_TTL: Duration = Duration::from_secs(60);
+
+/// How long listing the models of a provider may take before its known models are used
+const SUPPORTED_MODELS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Forget which providers are configured, after their keys may have changed
+pub fn clear_configured_providers() {
+    CONFIGURED_PROVIDERS.lock().unwrap().clear();
+}
+
+fn is_configured(id: &str, provider: &ProviderFile) -> bool {
+    if let Some((configured, checked_at)) = CONFIGURED_PROVIDERS.lock().unwrap().get(id) {
+        if checked_at.elapsed() < CONFIGURED_PROVIDERS_TTL {
+            return *configured;
+        }
+    }
+
+    let config = Config::global();
+    let configured = provider
+        .required_keys
+        .iter()
+        .all(|key| config.get::<String>(key).is_ok() || config.get_secret::<String>(key).is_ok());
+    CONFIGURED_PROVIDERS
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), (configured, Instant::now()));
+    configured
+}
+
+/// The models a configured provider currently serves, `None` for providers missing keys,
+/// that would need the user to log in or that don't answer in time
+async fn configured_models(id: &str, provider: &ProviderFile) -> Option<Vec<String>> {
+    if !is_configured(id, provider) {
+        return None;
+    }
+
+    let default_model = provider.models.first()?.clone();
+    let instance = providers::create(id, ModelConfig::new(default_model)).ok()?;
+    if !instance.lists_models_without_login() {
+        return None;
+    }
+    let models = tokio::time::timeout(
+        SUPPORTED_MODELS_TIMEOUT,
+        providers::supported_models(id, instance.as_ref()),
+    )
+    .await
+    .inspect_err(|_| tracing::warn!("Listing the models of {} timed out", id))
+    .ok()?;
+    (!models.is_empty()).then_some(models)
+}
+
 pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/agent/versions", get(get_versions))
@@ -171,3 +282,62 @@ pub fn routes(state: AppState) -> Router {
         .route("/agent", post(create_agent))
         .with_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sampling_request() {
+        let request: CreateAgentRequest = serde_json::from_value(json!({
+            "provider": "openai",
+            "model": "gpt-4o",
+            "temperature": 0.0,
+            "seed": 42,
+            "stop_sequences": ["END"],
+        }))
+        .unwrap();
+
+        let config = ModelConfig::new("gpt-4o".to_string()).with_top_p(Some(0.9));
+        let config = request.sampling.apply(config);
+        assert_eq!(config.temperature, Some(0.0));
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.stop_sequences, vec!["END".to_string()]);
+        // Parameters missing from the request keep their configured values
+        assert_eq!(config.top_p, Some(0.9));
+        assert_eq!(config.top_k, None);
+    }
+
+    #[test]
+    fn test_configured_state_is_cached() {
+        let provider = ProviderFile {
+            name: "Cached".to_string(),
+            description: String::new(),
+            models: vec![],
+            required_keys: vec!["CACHED_PROVIDER_TEST_API_KEY".to_string()],
+        };
+
+        env::set_var("CACHED_PROVIDER_TEST_API_KEY", "key");
+        assert!(is_configured("cached_test", &provider));
+
+        // The key isn't read again until the config changes
+        env::remove_var("CACHED_PROVIDER_TEST_API_KEY");
+        assert!(is_configured("cached_test", &provider));
+        clear_configured_providers();
+        assert!(!is_configured("cached_test", &provider));
+
+        // Changes outside the server are picked up once the entry expires
+        env::set_var("CACHED_PROVIDER_TEST_API_KEY", "key");
+        assert!(!is_configured("cached_test", &provider));
+        let expired = Instant::now()
+            .checked_sub(CONFIGURED_PROVIDERS_TTL + Duration::from_secs(1))
+            .unwrap();
+        CONFIGURED_PROVIDERS
+            .lock()
+            .unwrap()
+            .insert("cached_test".to_string(), (false, expired));
+        assert!(is_configured("cached_test", &provider));
+        env::remove_var("CACHED_PROVIDER_TEST_API_KEY");
+    }
+}
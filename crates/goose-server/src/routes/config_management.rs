@@ -11,6 +11,7 @@ use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
 use utoipa::ToSchema;
 
+use super::agent::clear_configured_providers;
 use crate::state::AppState;
 
 #[derive(Deserialize, ToSchema)]
@@ -56,6 +57,7 @@ pub async fn upsert_config(
     } else {
         config.set(&query.key, query.value)
     };
+    clear_configured_providers();
 
     match result {
         Ok(_) => Ok(Json(Value::String(format!("Upserted key {}", query.key)))),
@@ -79,7 +81,9 @@ pub async fn remove_config(
 ) -> Result<Json<String>, StatusCode> {
     let config = Config::global();
 
-    match config.delete(&query.key) {
+    let result = config.delete(&query.key);
+    clear_configured_providers();
+    match result {
         Ok(_) => Ok(Json(format!("Removed key {}", query.key))),
         Err(_) => Err(StatusCode::NOT_FOUND),
     }
@@ -122,9 +126,11 @@ pub async fn add_extension(
 ) -> Result<Json<String>, StatusCode> {
     let config = Config::global();
 
-    // Get current extensions or initialize empty map
-    let mut extensions: HashMap<String, Value> =
-        config.get("extensions").unwrap_or_else(|_| HashMap::new());
+    // Get current extensions or initialize empty map, without those of the project config
+    // so they aren't copied into the config file
+    let mut extensions: HashMap<String, Value> = config
+        .get_global("extensions")
+        .unwrap_or_else(|_| HashMap::new());
 
     // Add new extension
     extensions.insert(extension.name.clone(), extension.config);
@@ -155,8 +161,8 @@ pub async fn remove_extension(
 ) -> Result<Json<String>, StatusCode> {
     let config = Config::global();
 
-    // Get current extensions
-    let mut extensions: HashMap<String, Value> = match config.get("extensions") {
+    // Get current extensions, without those of the project config
+    let mut extensions: HashMap<String, Value> = match config.get_global("extensions") {
         Ok(exts) => exts,
         Err(_) => return Err(StatusCode::NOT_FOUND),
     };
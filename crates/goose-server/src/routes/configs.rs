@@ -1,3 +1,4 @@
+use super::agent::clear_configured_providers;
 use crate::state::AppState;
 use axum::{extract::State, routing::delete, routing::post, Json, Router};
 use goose::config::Config;
@@ -41,6 +42,7 @@ async fn store_config(
     } else {
         config.set(&request.key, Value::String(request.value))
     };
+    clear_configured_providers();
     match result {
         Ok(_) => Ok(Json(ConfigResponse { error: false })),
         Err(_) => Ok(Json(ConfigResponse { error: true })),
@@ -169,6 +171,7 @@ async fn delete_config(
     } else {
         config.delete(&request.key)
     };
+    clear_configured_providers();
     match result {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(_) => Err(StatusCode::NOT_FOUND),
@@ -8,7 +8,9 @@ use axum::{
 };
 use bytes::Bytes;
 use futures::{stream::StreamExt, Stream};
+use goose::agents::AgentEvent;
 use goose::message::{Message, MessageContent};
+use goose::providers::base::Usage;
 
 use mcp_core::{content::Content, role::Role};
 use serde::Deserialize;
@@ -166,19 +168,30 @@ impl ProtocolFormatter {
         format!("3:{}\n", encoded_error)
     }
 
-    fn format_finish(reason: &str) -> String {
+    fn format_finish(reason: &str, usage: &Usage) -> String {
         // Finish messages start with "d:"
         let finish = json!({
             "finishReason": reason,
             "usage": {
-                "promptTokens": 0,
-                "completionTokens": 0
+                "promptTokens": usage.input_tokens.unwrap_or(0),
+                "completionTokens": usage.output_tokens.unwrap_or(0)
             }
         });
         format!("d:{}\n", finish)
     }
 }
 
+// Sum the usage of every provider request in a reply, for the finish message
+fn accumulate_usage(total: &mut Usage, usage: &Usage) {
+    let add = |a: Option<i32>, b: Option<i32>| match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    };
+    total.input_tokens = add(total.input_tokens, usage.input_tokens);
+    total.output_tokens = add(total.output_tokens, usage.output_tokens);
+    total.total_tokens = add(total.total_tokens, usage.total_tokens);
+}
+
 async fn stream_message(
     message: Message,
     tx: &mpsc::Sender<String>,
@@ -247,7 +260,9 @@ async fn stream_message(
                                 .await?;
                         }
                     }
-                    MessageContent::Image(_) => {
+                    MessageContent::Image(_)
+                    | MessageContent::Document(_)
+                    | MessageContent::Audio(_) => {
                         // TODO
                         continue;
                     }
@@ -303,28 +318,42 @@ async fn handler(
                 let _ = tx
                     .send(ProtocolFormatter::format_error("No agent configured"))
                     .await;
-                let _ = tx.send(ProtocolFormatter::format_finish("error")).await;
+                let _ = tx
+                    .send(ProtocolFormatter::format_finish("error", &Usage::default()))
+                    .await;
                 return;
             }
         };
 
-        let mut stream = match agent.reply(&messages).await {
+        let mut stream = match agent.reply_events(&messages).await {
             Ok(stream) => stream,
             Err(e) => {
                 tracing::error!("Failed to start reply stream: {}", e);
                 let _ = tx
                     .send(ProtocolFormatter::format_error(&e.to_string()))
                     .await;
-                let _ = tx.send(ProtocolFormatter::format_finish("error")).await;
+                let _ = tx
+                    .send(ProtocolFormatter::format_finish("error", &Usage::default()))
+                    .await;
                 return;
             }
         };
 
+        let mut usage = Usage::default();
         loop {
             tokio::select! {
                 response = timeout(Duration::from_millis(500), stream.next()) => {
                     match response {
-                        Ok(Some(Ok(message))) => {
+                        Ok(Some(Ok(AgentEvent::Usage(provider_usage)))) => {
+                            accumulate_usage(&mut usage, &provider_usage.usage);
+                        }
+                        Ok(Some(Ok(AgentEvent::Notice(notice)))) => {
+                            let _ = tx.send(ProtocolFormatter::format_text(&format!("{}\n\n", notice))).await;
+                        }
+                        Ok(Some(Ok(event))) => {
+                            let Some(message) = event.into_message() else {
+                                continue;
+                            };
                             if let Err(e) = stream_message(message, &tx).await {
                                 tracing::error!("Error sending message through channel: {}", e);
                                 let _ = tx.send(ProtocolFormatter::format_error(&e.to_string())).await;
@@ -354,7 +383,9 @@ async fn handler(
         }
 
         // Send finish message
-        let _ = tx.send(ProtocolFormatter::format_finish("stop")).await;
+        let _ = tx
+            .send(ProtocolFormatter::format_finish("stop", &usage))
+            .await;
     });
 
     Ok(SseResponse::new(stream))
@@ -551,9 +582,25 @@ mod tests {
         assert!(formatted.contains("Test error"));
 
         // Test finish formatting
-        let formatted = ProtocolFormatter::format_finish("stop");
+        let formatted = ProtocolFormatter::format_finish("stop", &Usage::default());
         assert!(formatted.starts_with("d:"));
         assert!(formatted.contains("\"finishReason\":\"stop\""));
+        assert!(formatted.contains("\"promptTokens\":0"));
+    }
+
+    #[test]
+    fn test_accumulate_usage() {
+        let mut total = Usage::default();
+        accumulate_usage(&mut total, &Usage::new(Some(10), Some(5), Some(15)));
+        accumulate_usage(&mut total, &Usage::new(Some(20), None, None));
+
+        assert_eq!(total.input_tokens, Some(30));
+        assert_eq!(total.output_tokens, Some(5));
+        assert_eq!(total.total_tokens, Some(15));
+
+        let formatted = ProtocolFormatter::format_finish("stop", &total);
+        assert!(formatted.contains("\"promptTokens\":30"));
+        assert!(formatted.contains("\"completionTokens\":5"));
     }
 
     mod integration_tests {
@@ -1,5 +1,8 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use goose::message::Message;
 use goose::token_counter::TokenCounter;
+use mcp_core::{Content, Tool, ToolCall};
+use serde_json::json;
 
 fn benchmark_tokenization(c: &mut Criterion) {
     let lengths = [1_000, 5_000, 10_000, 50_000, 100_000, 124_000, 200_000];
@@ -16,5 +19,53 @@ fn benchmark_tokenization(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, benchmark_tokenization);
+/// A session of `turns` user/tool round trips with sizeable tool output.
+fn long_session(turns: usize) -> Vec<Message> {
+    let mut messages = Vec::with_capacity(turns * 3);
+    for i in 0..turns {
+        let id = format!("call_{}", i);
+        messages.push(Message::user().with_text(format!("Please read file number {}", i)));
+        messages.push(Message::assistant().with_tool_request(
+            &id,
+            Ok(ToolCall::new(
+                "read_file",
+                json!({"path": format!("src/{}.rs", i)}),
+            )),
+        ));
+        messages.push(Message::user().with_tool_response(
+            &id,
+            Ok(vec![Content::text(
+                "fn main() { println!(\"hello\"); }\n".repeat(50),
+            )]),
+        ));
+    }
+    messages
+}
+
+fn benchmark_long_session(c: &mut Criterion) {
+    let counter = TokenCounter::new("Xenova--gpt-4o");
+    let messages = long_session(200);
+    let tools = vec![Tool::new(
+        "read_file",
+        "Read the contents of a file",
+        json!({"properties": {"path": {"type": "string", "description": "Path to read"}}}),
+    )];
+
+    // Every turn of an agent loop recounts the whole conversation: the first count
+    // tokenizes everything, later ones only tokenize the newly appended messages
+    c.bench_function("long_session_cold", |b| {
+        b.iter_batched(
+            || counter.clear_cache(),
+            |_| counter.count_chat_tokens("", black_box(&messages), &tools),
+            BatchSize::SmallInput,
+        )
+    });
+
+    counter.count_chat_tokens("", &messages, &tools);
+    c.bench_function("long_session_cached", |b| {
+        b.iter(|| counter.count_chat_tokens("", black_box(&messages), &tools))
+    });
+}
+
+criterion_group!(benches, benchmark_tokenization, benchmark_long_session);
 criterion_main!(benches);
@@ -0,0 +1,293 @@
+use mcp_core::{Content, Tool};
+
+use super::tool_emulation;
+use crate::message::{Message, MessageContent, ToolResponse};
+use crate::model_registry::ModelCapabilities;
+
+const IMAGE_REMOVED: &str = "[An image was removed here because this model cannot read images]";
+
+/// A provider request adjusted to the capabilities of the model
+#[derive(Debug, Clone)]
+pub struct AdaptedRequest {
+    pub system: String,
+    pub messages: Vec<Message>,
+    pub tools: Vec<Tool>,
+}
+
+/// Adjust a request so that the model can handle it
+///
+/// - images are replaced by a note when the model can't read them
+/// - the system prompt is merged into the first user message when the model has none
+/// - tools are dropped when the model can't call them
+/// - with tool emulation, tools are described in the system prompt instead and tool
+///   requests and responses are rendered as text
+pub fn adapt_request(
+    capabilities: &ModelCapabilities,
+    tool_emulation: bool,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> AdaptedRequest {
+    let (system, mut messages, tools) = if tool_emulation && !tools.is_empty() {
+        (
+            format!("{}\n\n{}", system, tool_emulation::tools_prompt(tools)),
+            tool_emulation::render_messages(messages),
+            vec![],
+        )
+    } else {
+        (system.to_string(), messages.to_vec(), tools.to_vec())
+    };
+
+    if !capabilities.vision {
+        for message in &mut messages {
+            strip_images(message);
+        }
+    }
+
+    let system = if capabilities.system_prompt || system.is_empty() {
+        system
+    } else {
+        if let Some(first_user) = messages
+            .iter_mut()
+            .find(|message| message.role == mcp_core::Role::User)
+        {
+            first_user.content.insert(
+                0,
+                MessageContent::text(format!("<instructions>\n{}\n</instructions>", system)),
+            );
+        }
+        String::new()
+    };
+
+    let tools = if capabilities.tools { tools } else { vec![] };
+
+    AdaptedRequest {
+        system,
+        messages,
+        tools,
+    }
+}
+
+/// Explanations for the user of what won't work with this model in the coming reply
+///
+/// Only the newest message is checked for images, so each mismatch is explained once.
+pub fn capability_notices(
+    capabilities: &ModelCapabilities,
+    tool_emulation: bool,
+    model_name: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> Vec<String> {
+    let mut notices = Vec::new();
+
+    if !capabilities.tools && !tool_emulation && !tools.is_empty() {
+        notices.push(format!(
+            "{} does not support tool calling, so extensions are unavailable in this session. \
+            Choose a model with tool support, or add it to GOOSE_TOOL_EMULATION to describe \
+            the tools in the prompt instead.",
+            model_name
+        ));
+    }
+
+    let images = messages.last().map(count_images).unwrap_or_default();
+    if !capabilities.vision && images > 0 {
+        notices.push(format!(
+            "{} cannot read images, so {} image{} in your message {} replaced with a note.",
+            model_name,
+            images,
+            if images == 1 { "" } else { "s" },
+            if images == 1 { "was" } else { "were" }
+        ));
+    }
+
+    notices
+}
+
+fn count_images(message: &Message) -> usize {
+    message
+        .content
+        .iter()
+        .map(|content| match content {
+            MessageContent::Image(_) => 1,
+            MessageContent::ToolResponse(ToolResponse {
+                tool_result: Ok(contents),
+                ..
+            }) => contents
+                .iter()
+                .filter(|c| matches!(c, Content::Image(_)))
+                .count(),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn strip_images(message: &mut Message) {
+    for content in &mut message.content {
+        match content {
+            MessageContent::Image(_) => *content = MessageContent::text(IMAGE_REMOVED),
+            MessageContent::ToolResponse(ToolResponse {
+                tool_result: Ok(contents),
+                ..
+            }) => {
+                for content in contents.iter_mut() {
+                    if matches!(content, Content::Image(_)) {
+                        *content = Content::text(IMAGE_REMOVED);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool() -> Tool {
+        Tool::new(
+            "developer__shell",
+            "Run a command",
+            json!({"type": "object"}),
+        )
+    }
+
+    #[test]
+    fn test_capable_model_is_unchanged() {
+        let messages = vec![Message::user()
+            .with_text("What is this?")
+            .with_image("aGVsbG8=", "image/png")];
+
+        let adapted = adapt_request(
+            &ModelCapabilities::all(),
+            false,
+            "system",
+            &messages,
+            &[tool()],
+        );
+        assert_eq!(adapted.system, "system");
+        assert_eq!(adapted.messages, messages);
+        assert_eq!(adapted.tools.len(), 1);
+
+        assert!(capability_notices(
+            &ModelCapabilities::all(),
+            false,
+            "gpt-4o",
+            &messages,
+            &[tool()]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_adapt_to_limited_model() {
+        let capabilities = ModelCapabilities {
+            tools: false,
+            vision: false,
+            system_prompt: false,
+            ..ModelCapabilities::all()
+        };
+        let messages = vec![
+            Message::user().with_text("Hello"),
+            Message::assistant().with_text("Hi"),
+            Message::user()
+                .with_text("What is this?")
+                .with_image("aGVsbG8=", "image/png"),
+        ];
+
+        let adapted = adapt_request(&capabilities, false, "Be brief", &messages, &[tool()]);
+        assert_eq!(adapted.system, "");
+        assert!(adapted.tools.is_empty());
+        assert_eq!(
+            adapted.messages[0].content[0].as_text(),
+            Some("<instructions>\nBe brief\n</instructions>")
+        );
+        assert_eq!(adapted.messages[0].content[1].as_text(), Some("Hello"));
+        assert_eq!(
+            adapted.messages[2].content[1].as_text(),
+            Some(IMAGE_REMOVED)
+        );
+
+        let notices = capability_notices(&capabilities, false, "o1-mini", &messages, &[tool()]);
+        assert_eq!(notices.len(), 2);
+        assert!(notices[0].contains("does not support tool calling"));
+        assert!(notices[1].contains("1 image in your message was replaced"));
+    }
+
+    #[test]
+    fn test_adapted_request_keeps_all_text_parts() {
+        use crate::providers::formats::openai::format_messages;
+        use crate::providers::utils::ImageFormat;
+
+        let capabilities = ModelCapabilities {
+            vision: false,
+            system_prompt: false,
+            ..ModelCapabilities::all()
+        };
+        let messages = vec![Message::user()
+            .with_text("What is this?")
+            .with_image("aGVsbG8=", "image/png")];
+
+        let adapted = adapt_request(&capabilities, false, "Be brief", &messages, &[]);
+        let spec = format_messages(&adapted.messages, &ImageFormat::OpenAi);
+
+        assert_eq!(spec.len(), 1);
+        let content = spec[0]["content"].as_array().unwrap();
+        let texts: Vec<_> = content
+            .iter()
+            .map(|part| part["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "<instructions>\nBe brief\n</instructions>",
+                "What is this?",
+                IMAGE_REMOVED
+            ]
+        );
+    }
+
+    #[test]
+    fn test_images_in_tool_results_are_stripped() {
+        let capabilities = ModelCapabilities {
+            vision: false,
+            ..ModelCapabilities::all()
+        };
+        let messages = vec![Message::user().with_tool_response(
+            "1",
+            Ok(vec![
+                Content::text("Screenshot taken"),
+                Content::image("aGVsbG8=", "image/png"),
+            ]),
+        )];
+
+        let adapted = adapt_request(&capabilities, false, "system", &messages, &[]);
+        let response = adapted.messages[0].content[0].as_tool_response().unwrap();
+        let contents = response.tool_result.as_ref().unwrap();
+        assert_eq!(contents[1].as_text(), Some(IMAGE_REMOVED));
+    }
+
+    #[test]
+    fn test_tool_emulation() {
+        let capabilities = ModelCapabilities {
+            tools: false,
+            ..ModelCapabilities::all()
+        };
+        let messages =
+            vec![Message::user().with_tool_response("1", Ok(vec![Content::text("README.md")]))];
+
+        let adapted = adapt_request(&capabilities, true, "system", &messages, &[tool()]);
+        assert!(adapted.tools.is_empty());
+        assert!(adapted.system.starts_with("system\n\n# Tools"));
+        assert!(adapted.system.contains("### developer__shell"));
+        assert_eq!(
+            adapted.messages[0].content[0].as_text(),
+            Some("<tool_result>\nREADME.md\n</tool_result>")
+        );
+
+        assert!(
+            capability_notices(&capabilities, true, "qwen2.5", &messages, &[tool()]).is_empty()
+        );
+    }
+}
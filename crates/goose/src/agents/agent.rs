@@ -1,8 +1,9 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::stream::BoxStream;
+use futures::stream::{BoxStream, StreamExt};
 use serde_json::Value;
 
+use super::event::AgentEvent;
 use super::extension::{ExtensionConfig, ExtensionResult};
 use crate::message::Message;
 use crate::providers::base::ProviderUsage;
@@ -10,8 +11,18 @@ use crate::providers::base::ProviderUsage;
 /// Core trait defining the behavior of an Agent
 #[async_trait]
 pub trait Agent: Send + Sync {
+    /// Create a stream that yields each event as the agent replies, including the
+    /// messages it generates, tool call progress, truncation, retries and usage
+    async fn reply_events(&self, messages: &[Message])
+        -> Result<BoxStream<'_, Result<AgentEvent>>>;
+
     /// Create a stream that yields each message as it's generated by the agent
-    async fn reply(&self, messages: &[Message]) -> Result<BoxStream<'_, Result<Message>>>;
+    async fn reply(&self, messages: &[Message]) -> Result<BoxStream<'_, Result<Message>>> {
+        let events = self.reply_events(messages).await?;
+        Ok(Box::pin(events.filter_map(|event| async move {
+            event.map(AgentEvent::into_message).transpose()
+        })))
+    }
 
     /// Add a new MCP client to the agent
     async fn add_extension(&mut self, config: ExtensionConfig) -> ExtensionResult<()>;
@@ -31,4 +42,10 @@ pub trait Agent: Send + Sync {
 
     /// Add custom text to be included in the system prompt
     async fn extend_system_prompt(&mut self, extension: String);
+
+    /// Require the final output of each reply to match a JSON schema, or stop requiring it
+    ///
+    /// Replies then end with an [`AgentEvent::StructuredOutput`]. Fails if the schema is
+    /// not a valid JSON schema.
+    async fn set_output_schema(&mut self, schema: Option<Value>) -> Result<()>;
 }
@@ -9,9 +9,15 @@ use tokio::sync::Mutex;
 use tracing::{debug, instrument};
 
 use super::extension::{ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult};
-use crate::prompt_template::load_prompt_file;
+use super::structured_output::OutputSchema;
+use crate::config::Config;
+use crate::prompt_template::{
+    current_git_branch, load_prompt_file, load_prompt_file_with_overrides, system_prompt_variables,
+};
 use crate::providers::base::{Provider, ProviderUsage};
-use mcp_client::client::{ClientCapabilities, ClientInfo, McpClient, McpClientTrait};
+use mcp_client::client::{
+    ClientCapabilities, ClientInfo, McpClient, McpClientTrait, DEFAULT_MAX_CONCURRENT_REQUESTS,
+};
 use mcp_client::transport::{SseTransport, StdioTransport, Transport};
 use mcp_core::{Content, Tool, ToolCall, ToolError, ToolResult};
 use serde_json::Value;
@@ -21,7 +27,8 @@ use serde_json::Value;
 static DEFAULT_TIMESTAMP: LazyLock<DateTime<Utc>> =
     LazyLock::new(|| Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap());
 
-type McpClientBox = Arc<Mutex<Box<dyn McpClientTrait>>>;
+// Shared without a lock, the client multiplexes concurrent calls
+type McpClientBox = Arc<dyn McpClientTrait>;
 
 /// Manages MCP clients and their interactions
 pub struct Capabilities {
@@ -31,6 +38,9 @@ pub struct Capabilities {
     provider: Box<dyn Provider>,
     provider_usage: Mutex<Vec<ProviderUsage>>,
     system_prompt_extensions: Vec<String>,
+    output_schema: Option<Arc<OutputSchema>>,
+    // Looked up once, rather than running git for every system prompt
+    git_branch: Option<String>,
 }
 
 /// A flattened representation of a resource used by the agent to prepare inference
@@ -90,6 +100,8 @@ impl Capabilities {
             provider,
             provider_usage: Mutex::new(Vec::new()),
             system_prompt_extensions: Vec::new(),
+            output_schema: None,
+            git_branch: current_git_branch(),
         }
     }
 
@@ -100,12 +112,17 @@ impl Capabilities {
     /// Add a new MCP extension based on the provided client type
     // TODO IMPORTANT need to ensure this times out if the extension command is broken!
     pub async fn add_extension(&mut self, config: ExtensionConfig) -> ExtensionResult<()> {
+        // How many requests each extension may process at once
+        let max_concurrent = Config::global()
+            .get::<usize>("GOOSE_EXTENSION_MAX_CONCURRENCY")
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_REQUESTS);
+
         let mut client: Box<dyn McpClientTrait> = match &config {
             ExtensionConfig::Sse { uri, envs, .. } => {
                 let transport = SseTransport::new(uri, envs.get_env());
                 let handle = transport.start().await?;
                 let service = McpService::with_timeout(handle, Duration::from_secs(300));
-                Box::new(McpClient::new(service))
+                Box::new(McpClient::new(service).with_max_concurrent_requests(max_concurrent))
             }
             ExtensionConfig::Stdio {
                 cmd, args, envs, ..
@@ -113,7 +130,7 @@ impl Capabilities {
                 let transport = StdioTransport::new(cmd, args.to_vec(), envs.get_env());
                 let handle = transport.start().await?;
                 let service = McpService::with_timeout(handle, Duration::from_secs(300));
-                Box::new(McpClient::new(service))
+                Box::new(McpClient::new(service).with_max_concurrent_requests(max_concurrent))
             }
             ExtensionConfig::Builtin { name } => {
                 // For builtin extensions, we run the current executable with mcp and extension name
@@ -129,7 +146,7 @@ impl Capabilities {
                 );
                 let handle = transport.start().await?;
                 let service = McpService::with_timeout(handle, Duration::from_secs(300));
-                Box::new(McpClient::new(service))
+                Box::new(McpClient::new(service).with_max_concurrent_requests(max_concurrent))
             }
         };
 
@@ -161,7 +178,7 @@ impl Capabilities {
 
         // Store the client using the provided name
         self.clients
-            .insert(sanitized_name.clone(), Arc::new(Mutex::new(client)));
+            .insert(sanitized_name.clone(), Arc::from(client));
 
         Ok(())
     }
@@ -171,6 +188,15 @@ impl Capabilities {
         self.system_prompt_extensions.push(extension);
     }
 
+    /// Set the schema the final output of each reply must match
+    pub fn set_output_schema(&mut self, output_schema: Option<OutputSchema>) {
+        self.output_schema = output_schema.map(Arc::new);
+    }
+
+    pub fn output_schema(&self) -> Option<Arc<OutputSchema>> {
+        self.output_schema.clone()
+    }
+
     /// Get a reference to the provider
     pub fn provider(&self) -> &dyn Provider {
         &*self.provider
@@ -223,8 +249,7 @@ impl Capabilities {
     pub async fn get_prefixed_tools(&mut self) -> ExtensionResult<Vec<Tool>> {
         let mut tools = Vec::new();
         for (name, client) in &self.clients {
-            let client_guard = client.lock().await;
-            let mut client_tools = client_guard.list_tools(None).await?;
+            let mut client_tools = client.list_tools(None).await?;
 
             loop {
                 for tool in client_tools.tools {
@@ -240,7 +265,7 @@ impl Capabilities {
                     break;
                 }
 
-                client_tools = client_guard.list_tools(client_tools.next_cursor).await?;
+                client_tools = client.list_tools(client_tools.next_cursor).await?;
             }
         }
         Ok(tools)
@@ -251,8 +276,7 @@ impl Capabilities {
         let mut result: Vec<ResourceItem> = Vec::new();
 
         for (name, client) in &self.clients {
-            let client_guard = client.lock().await;
-            let resources = client_guard.list_resources(None).await?;
+            let resources = client.list_resources(None).await?;
 
             for resource in resources.resources {
                 // Skip reading the resource if it's not marked active
@@ -261,7 +285,7 @@ impl Capabilities {
                     continue;
                 }
 
-                if let Ok(contents) = client_guard.read_resource(&resource.uri).await {
+                if let Ok(contents) = client.read_resource(&resource.uri).await {
                     for content in contents.contents {
                         let (uri, content_str) = match content {
                             mcp_core::resource::ResourceContents::TextResourceContents {
@@ -292,8 +316,11 @@ impl Capabilities {
     }
 
     /// Get the extension prompt including client instructions
+    ///
+    /// The base prompt is rendered from `system.md`, which can be overridden by a template
+    /// in the project `.goose/prompts` or user config `prompts` directory.
     pub async fn get_system_prompt(&self) -> String {
-        let mut context: HashMap<&str, Value> = HashMap::new();
+        let mut context = system_prompt_variables(self.git_branch.as_deref());
 
         let extensions_info: Vec<ExtensionInfo> = self
             .clients
@@ -305,12 +332,16 @@ impl Capabilities {
             })
             .collect();
 
-        let current_date_time = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-
         context.insert("extensions", serde_json::to_value(extensions_info).unwrap());
-        context.insert("current_date_time", Value::String(current_date_time));
 
-        let base_prompt = load_prompt_file("system.md", &context).expect("Prompt should render");
+        let base_prompt =
+            load_prompt_file_with_overrides("system.md", &context).unwrap_or_else(|e| {
+                tracing::error!(
+                    "Custom system prompt failed to render, using the default: {}",
+                    e
+                );
+                load_prompt_file("system.md", &context).expect("Prompt should render")
+            });
 
         if self.system_prompt_extensions.is_empty() {
             base_prompt
@@ -396,8 +427,7 @@ impl Capabilities {
             .get(extension_name)
             .ok_or(ToolError::InvalidParameters(error_msg))?;
 
-        let client_guard = client.lock().await;
-        let read_result = client_guard.read_resource(uri).await.map_err(|_| {
+        let read_result = client.read_resource(uri).await.map_err(|_| {
             ToolError::ExecutionError(format!("Could not read resource with uri: {}", uri))
         })?;
 
@@ -422,8 +452,7 @@ impl Capabilities {
             ToolError::InvalidParameters(format!("Extension {} is not valid", extension_name))
         })?;
 
-        let client_guard = client.lock().await;
-        client_guard
+        client
             .list_resources(None)
             .await
             .map_err(|e| {
@@ -515,9 +544,7 @@ impl Capabilities {
                 .and_then(|s| s.strip_prefix("__"))
                 .ok_or_else(|| ToolError::NotFound(tool_call.name.clone()))?;
 
-            let client_guard = client.lock().await;
-
-            client_guard
+            client
                 .call_tool(tool_name, tool_call.clone().arguments)
                 .await
                 .map(|result| result.content)
@@ -626,23 +653,21 @@ mod tests {
         // Add some mock clients
         capabilities.clients.insert(
             normalize("test_client".to_string()),
-            Arc::new(Mutex::new(Box::new(MockClient {}))),
+            Arc::new(MockClient {}),
         );
 
-        capabilities.clients.insert(
-            normalize("__client".to_string()),
-            Arc::new(Mutex::new(Box::new(MockClient {}))),
-        );
+        capabilities
+            .clients
+            .insert(normalize("__client".to_string()), Arc::new(MockClient {}));
 
         capabilities.clients.insert(
             normalize("__cli__ent__".to_string()),
-            Arc::new(Mutex::new(Box::new(MockClient {}))),
+            Arc::new(MockClient {}),
         );
 
-        capabilities.clients.insert(
-            normalize("client 🚀".to_string()),
-            Arc::new(Mutex::new(Box::new(MockClient {}))),
-        );
+        capabilities
+            .clients
+            .insert(normalize("client 🚀".to_string()), Arc::new(MockClient {}));
 
         // Test basic case
         assert!(capabilities
@@ -675,18 +700,17 @@ mod tests {
         // Add some mock clients
         capabilities.clients.insert(
             normalize("test_client".to_string()),
-            Arc::new(Mutex::new(Box::new(MockClient {}))),
+            Arc::new(MockClient {}),
         );
 
         capabilities.clients.insert(
             normalize("__cli__ent__".to_string()),
-            Arc::new(Mutex::new(Box::new(MockClient {}))),
+            Arc::new(MockClient {}),
         );
 
-        capabilities.clients.insert(
-            normalize("client 🚀".to_string()),
-            Arc::new(Mutex::new(Box::new(MockClient {}))),
-        );
+        capabilities
+            .clients
+            .insert(normalize("client 🚀".to_string()), Arc::new(MockClient {}));
 
         // verify a normal tool call
         let tool_call = ToolCall {
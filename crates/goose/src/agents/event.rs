@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::message::Message;
+use crate::providers::base::ProviderUsage;
+
+/// Events emitted by an agent while it replies
+///
+/// Consumers that only care about the conversation can use [`AgentEvent::into_message`],
+/// which is how [`Agent::reply`](super::Agent::reply) is derived from the event stream.
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// A new message in the conversation, either an assistant response or the results
+    /// of the tool calls it requested
+    Message(Message),
+
+    /// A tool call was dispatched to its extension
+    ToolCallStarted { id: String, name: String },
+
+    /// A tool call completed, `is_error` is set when the tool returned an error
+    ToolCallFinished {
+        id: String,
+        name: String,
+        duration: Duration,
+        is_error: bool,
+    },
+
+    /// The conversation was truncated to fit the model's context limit
+    ContextTruncated {
+        messages_before: usize,
+        messages_after: usize,
+    },
+
+    /// The request to the provider is being retried
+    Retry {
+        attempt: usize,
+        max_attempts: usize,
+        reason: String,
+    },
+
+    /// Token usage of a completed provider request
+    Usage(ProviderUsage),
+
+    /// An explanation for the user, e.g. that the model can't use tools or read images
+    Notice(String),
+
+    /// The final output of a reply with an output schema, validated against the schema
+    ///
+    /// It follows the message with the same output as JSON text.
+    StructuredOutput(Value),
+
+    /// The reply stopped because of an error the agent could not recover from
+    Error(String),
+}
+
+impl AgentEvent {
+    /// The message this event contributes to the conversation, if any
+    ///
+    /// Terminal errors become an assistant message so they are shown and kept in the session.
+    pub fn into_message(self) -> Option<Message> {
+        match self {
+            AgentEvent::Message(message) => Some(message),
+            AgentEvent::Error(error) => Some(Message::assistant().with_text(error)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+
+    #[test]
+    fn test_into_message() {
+        let message = Message::assistant().with_text("hello");
+        assert_eq!(
+            AgentEvent::Message(message.clone()).into_message(),
+            Some(message)
+        );
+
+        let error = AgentEvent::Error("Ran into this error".to_string())
+            .into_message()
+            .unwrap();
+        assert_eq!(error.content[0].as_text(), Some("Ran into this error"));
+
+        assert!(
+            AgentEvent::Usage(ProviderUsage::new("gpt-4o".to_string(), Usage::default()))
+                .into_message()
+                .is_none()
+        );
+        assert!(AgentEvent::ToolCallStarted {
+            id: "1".to_string(),
+            name: "developer__shell".to_string()
+        }
+        .into_message()
+        .is_none());
+    }
+}
@@ -1,11 +1,17 @@
+mod adapt;
 mod agent;
 mod capabilities;
+mod event;
 pub mod extension;
 mod factory;
 mod reference;
+mod reply;
+pub mod structured_output;
+mod tool_emulation;
 mod truncate;
 
 pub use agent::Agent;
 pub use capabilities::Capabilities;
+pub use event::AgentEvent;
 pub use extension::ExtensionConfig;
 pub use factory::{register_agent, AgentFactory};
@@ -0,0 +1,259 @@
+//! The steps of a reply that the agents share
+//!
+//! An agent builds a [`Reply`] for the conversation, then loops: it requests a completion
+//! with [`Reply::complete`], hands the response to [`Reply::handle_response`] and runs the
+//! tool calls it asks for with [`Reply::run_tools`], yielding the events of each step. The
+//! agents differ in what they do when a completion fails, e.g. truncating the conversation.
+
+use std::time::Instant;
+
+use indoc::indoc;
+use mcp_core::{Tool, ToolCall};
+use serde_json::json;
+
+use super::adapt::{adapt_request, capability_notices};
+use super::capabilities::Capabilities;
+use super::event::AgentEvent;
+use super::structured_output::{FinalOutput, OutputCheck, OutputMode};
+use super::tool_emulation::parse_tool_calls;
+use crate::message::{Message, ToolRequest};
+use crate::model::ModelConfig;
+use crate::model_registry::ModelCapabilities;
+use crate::providers::base::ProviderUsage;
+use crate::providers::errors::ProviderError;
+
+/// The conversation of a reply and what its requests are made with
+pub struct Reply {
+    pub messages: Vec<Message>,
+    pub tools: Vec<Tool>,
+    pub system_prompt: String,
+    model_capabilities: ModelCapabilities,
+    model_config: ModelConfig,
+    final_output: FinalOutput,
+}
+
+/// What to do after a response, see [`Reply::handle_response`]
+pub enum Step {
+    /// Yield the events and end the reply
+    Done(Vec<AgentEvent>),
+    /// Yield the events and request the next completion
+    Continue(Vec<AgentEvent>),
+    /// Yield the events, then run the tool calls with [`Reply::run_tools`]
+    RunTools(Vec<AgentEvent>, ToolCalls),
+}
+
+/// The valid tool calls of a response, with the response they are answered after
+pub struct ToolCalls {
+    response: Message,
+    calls: Vec<(String, ToolCall)>,
+}
+
+impl Reply {
+    /// Start a reply to the conversation, with the notices to show the user first
+    pub async fn new(
+        capabilities: &mut Capabilities,
+        messages: &[Message],
+    ) -> anyhow::Result<(Self, Vec<String>)> {
+        let mut tools = capabilities.get_prefixed_tools().await?;
+        // TODO: make sure there is no collision with another extension's tool name
+        if capabilities.supports_resources() {
+            tools.extend(resource_tools());
+        }
+        let system_prompt = capabilities.get_system_prompt().await;
+
+        // Adapt requests to what the model supports, and tell the user what that costs them
+        let model_capabilities = capabilities.provider().model_capabilities();
+        let model_config = capabilities.provider().get_model_config();
+        let notices = capability_notices(
+            &model_capabilities,
+            model_config.tool_emulation,
+            &model_config.model_name,
+            messages,
+            &tools,
+        );
+        let output_mode = OutputMode::select(
+            capabilities.provider().supports_response_schema(),
+            model_capabilities.tools && !model_config.tool_emulation,
+        );
+        let final_output = FinalOutput::new(capabilities.output_schema(), output_mode);
+
+        let reply = Self {
+            messages: messages.to_vec(),
+            tools,
+            system_prompt,
+            model_capabilities,
+            model_config,
+            final_output,
+        };
+        Ok((reply, notices))
+    }
+
+    /// Request the next response and record its usage
+    ///
+    /// Once the tools are done, the final output is requested with its own tools and options.
+    pub async fn complete(
+        &self,
+        capabilities: &Capabilities,
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let (request_tools, options) = self.final_output.request(&self.tools);
+        let request = adapt_request(
+            &self.model_capabilities,
+            self.model_config.tool_emulation,
+            &self.system_prompt,
+            &self.messages,
+            &request_tools,
+        );
+        let (response, usage) = capabilities
+            .provider()
+            .complete_with_options(&request.system, &request.messages, &request.tools, &options)
+            .await?;
+        let response = if self.model_config.tool_emulation {
+            parse_tool_calls(response, &self.tools)
+        } else {
+            response
+        };
+        capabilities.record_usage(usage.clone()).await;
+        Ok((response, usage))
+    }
+
+    /// Decide how the reply goes on after a response
+    pub fn handle_response(&mut self, response: Message) -> Step {
+        // The final output is checked instead of running tools
+        let response = match self.final_output.check(response) {
+            OutputCheck::Response(response) => response,
+            OutputCheck::Done(events) => return Step::Done(events),
+            OutputCheck::Retry(events, retry_messages) => {
+                self.messages.extend(retry_messages);
+                return Step::Continue(events);
+            }
+        };
+
+        let mut events = vec![AgentEvent::Message(response.clone())];
+        let tool_requests: Vec<&ToolRequest> = response
+            .content
+            .iter()
+            .filter_map(|content| content.as_tool_request())
+            .collect();
+
+        if tool_requests.is_empty() {
+            // Done with the tools, ask for the final output if there is a schema for it
+            let Some(request_message) = self.final_output.request_message() else {
+                return Step::Done(events);
+            };
+            events.push(AgentEvent::Message(request_message.clone()));
+            self.messages.push(response);
+            self.messages.push(request_message);
+            return Step::Continue(events);
+        }
+
+        let calls: Vec<_> = tool_requests
+            .iter()
+            .filter_map(|request| {
+                request
+                    .tool_call
+                    .clone()
+                    .ok()
+                    .map(|call| (request.id.clone(), call))
+            })
+            .collect();
+        events.extend(
+            calls
+                .iter()
+                .map(|(id, tool_call)| AgentEvent::ToolCallStarted {
+                    id: id.clone(),
+                    name: tool_call.name.clone(),
+                }),
+        );
+        Step::RunTools(events, ToolCalls { response, calls })
+    }
+
+    /// Run the tool calls in parallel, timing each call, and add their responses to the
+    /// conversation
+    pub async fn run_tools(
+        &mut self,
+        capabilities: &Capabilities,
+        tool_calls: ToolCalls,
+    ) -> Vec<AgentEvent> {
+        let ToolCalls { response, calls } = tool_calls;
+        let futures: Vec<_> = calls
+            .iter()
+            .map(|(_, tool_call)| async move {
+                let start = Instant::now();
+                let output = capabilities.dispatch_tool_call(tool_call.clone()).await;
+                (output, start.elapsed())
+            })
+            .collect();
+
+        // Process all the futures in parallel but wait until all are finished
+        let outputs = futures::future::join_all(futures).await;
+
+        let mut events: Vec<AgentEvent> = calls
+            .iter()
+            .zip(outputs.iter())
+            .map(
+                |((id, tool_call), (output, duration))| AgentEvent::ToolCallFinished {
+                    id: id.clone(),
+                    name: tool_call.name.clone(),
+                    duration: *duration,
+                    is_error: output.is_err(),
+                },
+            )
+            .collect();
+
+        // Combine the outputs into one message, using the original IDs
+        let mut message_tool_response = Message::user();
+        for ((id, _), (output, _)) in calls.into_iter().zip(outputs) {
+            message_tool_response = message_tool_response.with_tool_response(id, output);
+        }
+        events.push(AgentEvent::Message(message_tool_response.clone()));
+
+        self.messages.push(response);
+        self.messages.push(message_tool_response);
+        events
+    }
+}
+
+/// The platform tools to read and list the resources of extensions
+fn resource_tools() -> [Tool; 2] {
+    let read_resource_tool = Tool::new(
+        "platform__read_resource".to_string(),
+        indoc! {r#"
+            Read a resource from an extension.
+
+            Resources allow extensions to share data that provide context to LLMs, such as
+            files, database schemas, or application-specific information. This tool searches for the
+            resource URI in the provided extension, and reads in the resource content. If no extension
+            is provided, the tool will search all extensions for the resource.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["uri"],
+            "properties": {
+                "uri": {"type": "string", "description": "Resource URI"},
+                "extension_name": {"type": "string", "description": "Optional extension name"}
+            }
+        }),
+    );
+
+    let list_resources_tool = Tool::new(
+        "platform__list_resources".to_string(),
+        indoc! {r#"
+            List resources from an extension(s).
+
+            Resources allow extensions to share data that provide context to LLMs, such as
+            files, database schemas, or application-specific information. This tool lists resources
+            in the provided extension, and returns a list for the user to browse. If no extension
+            is provided, the tool will search all extensions for the resource.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "extension_name": {"type": "string", "description": "Optional extension name"}
+            }
+        }),
+    );
+
+    [read_resource_tool, list_resources_tool]
+}
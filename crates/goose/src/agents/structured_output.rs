@@ -0,0 +1,458 @@
+//! Final responses constrained to a JSON schema
+//!
+//! The agent runs its usual tool loop, and once the model answers without calling tools
+//! asks it for the final output. Providers that can constrain responses to a schema get it
+//! with the request, otherwise the model calls a synthetic `final_output` tool whose
+//! arguments are the output, or for models without tool calling is asked for JSON text.
+//! Outputs that don't match the schema are sent back with the validation errors and
+//! requested again, up to [`MAX_OUTPUT_ATTEMPTS`] times.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use jsonschema::Validator;
+use mcp_core::{Tool, ToolError};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use super::event::AgentEvent;
+use crate::message::Message;
+use crate::providers::base::{CompletionOptions, ToolChoice};
+
+pub const FINAL_OUTPUT_TOOL: &str = "final_output";
+
+/// How often the output is requested before the reply fails
+pub const MAX_OUTPUT_ATTEMPTS: usize = 3;
+
+/// Validation errors reported back to the model, the rest are usually consequences
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// How the model is made to produce the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// The provider constrains the response text to the schema
+    Native,
+    /// The model calls the `final_output` tool with the output as its arguments
+    Tool,
+    /// The model is asked to respond with JSON text
+    Text,
+}
+
+impl OutputMode {
+    pub fn select(supports_response_schema: bool, tool_calling: bool) -> Self {
+        if supports_response_schema {
+            OutputMode::Native
+        } else if tool_calling {
+            OutputMode::Tool
+        } else {
+            OutputMode::Text
+        }
+    }
+}
+
+/// The JSON schema the final output of a reply must match
+#[derive(Debug)]
+pub struct OutputSchema {
+    schema: Value,
+    validator: Validator,
+}
+
+impl OutputSchema {
+    pub fn new(schema: Value) -> Result<Self> {
+        let validator = jsonschema::validator_for(&schema)
+            .map_err(|e| anyhow!("Invalid output schema: {e}"))?;
+        Ok(Self { schema, validator })
+    }
+
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// Tool arguments must be an object, other outputs are wrapped in an `output` property
+    fn wraps_output(&self) -> bool {
+        self.schema.get("type") != Some(&json!("object"))
+    }
+
+    /// The synthetic tool the model calls with the output
+    pub fn tool(&self) -> Tool {
+        let input_schema = if self.wraps_output() {
+            json!({
+                "type": "object",
+                "required": ["output"],
+                "properties": {"output": self.schema}
+            })
+        } else {
+            self.schema.clone()
+        };
+        Tool::new(
+            FINAL_OUTPUT_TOOL,
+            "Return the final answer to the user's request. Call this once you are done.",
+            input_schema,
+        )
+    }
+
+    /// The tools and options of a request for the output
+    pub fn request(&self, mode: OutputMode, tools: &[Tool]) -> (Vec<Tool>, CompletionOptions) {
+        match mode {
+            OutputMode::Native => (
+                tools.to_vec(),
+                CompletionOptions::default()
+                    .with_tool_choice(ToolChoice::None)
+                    .with_response_schema(Some(self.schema.clone())),
+            ),
+            OutputMode::Tool => {
+                let mut tools = tools.to_vec();
+                tools.push(self.tool());
+                let choice = ToolChoice::Tool(FINAL_OUTPUT_TOOL.to_string());
+                (tools, CompletionOptions::default().with_tool_choice(choice))
+            }
+            OutputMode::Text => (tools.to_vec(), CompletionOptions::default()),
+        }
+    }
+
+    /// The message asking the model for the output, once it is done with its tool calls
+    pub fn request_message(&self, mode: OutputMode) -> Message {
+        let text = match mode {
+            OutputMode::Tool => {
+                format!("Now call the {FINAL_OUTPUT_TOOL} tool with your final answer.")
+            }
+            OutputMode::Native | OutputMode::Text => format!(
+                "Now respond with your final answer as JSON matching this schema, without any other text:\n\n{}",
+                self.schema
+            ),
+        };
+        Message::user().with_text(text)
+    }
+
+    /// Extract the output from a response and validate it against the schema
+    ///
+    /// The error describes what is wrong for the model to correct. A call of the
+    /// `final_output` tool is preferred, but JSON text is accepted in any mode.
+    pub fn parse(&self, response: &Message) -> Result<Value, String> {
+        let tool_call = response
+            .content
+            .iter()
+            .filter_map(|content| content.as_tool_request())
+            .find(
+                |request| matches!(&request.tool_call, Ok(call) if call.name == FINAL_OUTPUT_TOOL),
+            )
+            .and_then(|request| request.tool_call.as_ref().ok());
+
+        let output = match tool_call {
+            Some(call) if self.wraps_output() => call
+                .arguments
+                .get("output")
+                .cloned()
+                .ok_or_else(|| "The output property is missing".to_string())?,
+            Some(call) => call.arguments.clone(),
+            None => parse_json_text(&response.as_concat_text())?,
+        };
+
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(&output)
+            .take(MAX_REPORTED_ERRORS)
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                if path.is_empty() {
+                    error.to_string()
+                } else {
+                    format!("{path}: {error}")
+                }
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(output)
+        } else {
+            Err(format!(
+                "The output does not match the schema:\n{}",
+                errors.join("\n")
+            ))
+        }
+    }
+
+    /// The message sending the problems with a response back to the model
+    ///
+    /// Every tool request of the response is answered, the `final_output` call with the
+    /// error, so that the conversation stays valid.
+    pub fn retry_message(&self, response: &Message, error: &str) -> Message {
+        let requests: Vec<_> = response
+            .content
+            .iter()
+            .filter_map(|content| content.as_tool_request())
+            .collect();
+        if requests.is_empty() {
+            return Message::user().with_text(format!(
+                "{error}\n\nRespond again with only the corrected JSON."
+            ));
+        }
+
+        let mut message = Message::user();
+        for request in requests {
+            let is_output =
+                matches!(&request.tool_call, Ok(call) if call.name == FINAL_OUTPUT_TOOL);
+            let result = if is_output {
+                Err(ToolError::InvalidParameters(error.to_string()))
+            } else {
+                Err(ToolError::ExecutionError(format!(
+                    "Only {FINAL_OUTPUT_TOOL} can be called now"
+                )))
+            };
+            message = message.with_tool_response(request.id.clone(), result);
+        }
+        message
+    }
+
+    /// The assistant message that ends the reply with the output
+    pub fn output_message(output: &Value) -> Message {
+        let text = serde_json::to_string_pretty(output).unwrap_or_else(|_| output.to_string());
+        Message::assistant().with_text(text)
+    }
+}
+
+/// The state of a reply that may have to end with a final output
+///
+/// Agents ask it for the tools and options of each request, hand it every response to
+/// check, and ask it for the output once the model is done with its tools.
+pub struct FinalOutput {
+    schema: Option<Arc<OutputSchema>>,
+    mode: OutputMode,
+    requesting: bool,
+    attempt: usize,
+}
+
+/// What to do with a response, see [`FinalOutput::check`]
+pub enum OutputCheck {
+    /// The output isn't requested yet, the response is handled as usual
+    Response(Message),
+    /// Yield the events and end the reply
+    Done(Vec<AgentEvent>),
+    /// Yield the events, add the messages to the conversation and request the output again
+    Retry(Vec<AgentEvent>, Vec<Message>),
+}
+
+impl FinalOutput {
+    pub fn new(schema: Option<Arc<OutputSchema>>, mode: OutputMode) -> Self {
+        Self {
+            schema,
+            mode,
+            requesting: false,
+            attempt: 0,
+        }
+    }
+
+    /// The tools and options of the next request
+    pub fn request(&self, tools: &[Tool]) -> (Vec<Tool>, CompletionOptions) {
+        match &self.schema {
+            Some(schema) if self.requesting => schema.request(self.mode, tools),
+            _ => (tools.to_vec(), CompletionOptions::default()),
+        }
+    }
+
+    /// Check a response while the output is requested, instead of running its tools
+    pub fn check(&mut self, response: Message) -> OutputCheck {
+        let Some(schema) = self.schema.as_ref().filter(|_| self.requesting) else {
+            return OutputCheck::Response(response);
+        };
+
+        match schema.parse(&response) {
+            Ok(output) => OutputCheck::Done(vec![
+                AgentEvent::Message(OutputSchema::output_message(&output)),
+                AgentEvent::StructuredOutput(output),
+            ]),
+            Err(error) => {
+                self.attempt += 1;
+                if self.attempt >= MAX_OUTPUT_ATTEMPTS {
+                    return OutputCheck::Done(vec![AgentEvent::Error(format!(
+                        "Error: The final output did not match the output schema after {} attempts.\n\n{}",
+                        MAX_OUTPUT_ATTEMPTS, error
+                    ))]);
+                }
+                warn!(
+                    "Invalid structured output. Attempt: {}/{}.",
+                    self.attempt, MAX_OUTPUT_ATTEMPTS
+                );
+                let retry_message = schema.retry_message(&response, &error);
+                OutputCheck::Retry(
+                    vec![AgentEvent::Retry {
+                        attempt: self.attempt,
+                        max_attempts: MAX_OUTPUT_ATTEMPTS,
+                        reason: "output does not match the schema".to_string(),
+                    }],
+                    vec![response, retry_message],
+                )
+            }
+        }
+    }
+
+    /// The message asking for the output once the model is done with its tools, `None`
+    /// when the reply has no output schema and ends
+    pub fn request_message(&mut self) -> Option<Message> {
+        let schema = self.schema.as_ref()?;
+        self.requesting = true;
+        Some(schema.request_message(self.mode))
+    }
+}
+
+/// Parse JSON text, which models like to wrap in a code block
+fn parse_json_text(text: &str) -> Result<Value, String> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(text)
+        .trim();
+    serde_json::from_str(text).map_err(|e| format!("The response is not valid JSON: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::ToolCall;
+
+    fn person_schema() -> OutputSchema {
+        OutputSchema::new(json!({
+            "type": "object",
+            "required": ["name", "age"],
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer", "minimum": 0}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        assert!(OutputSchema::new(json!({"type": "not-a-type"})).is_err());
+    }
+
+    #[test]
+    fn test_parse_tool_call() {
+        let schema = person_schema();
+        let response = Message::assistant().with_tool_request(
+            "call_1",
+            Ok(ToolCall::new(
+                FINAL_OUTPUT_TOOL,
+                json!({"name": "Ada", "age": 36}),
+            )),
+        );
+        assert_eq!(
+            schema.parse(&response).unwrap(),
+            json!({"name": "Ada", "age": 36})
+        );
+
+        let response = Message::assistant().with_tool_request(
+            "call_2",
+            Ok(ToolCall::new(
+                FINAL_OUTPUT_TOOL,
+                json!({"name": "Ada", "age": -1}),
+            )),
+        );
+        let error = schema.parse(&response).unwrap_err();
+        assert!(error.contains("/age"), "{error}");
+
+        let retry = schema.retry_message(&response, &error);
+        let tool_response = retry.content[0].as_tool_response().unwrap();
+        assert_eq!(tool_response.id, "call_2");
+        assert!(tool_response.tool_result.is_err());
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let schema = person_schema();
+        let response =
+            Message::assistant().with_text("```json\n{\"name\": \"Ada\", \"age\": 36}\n```");
+        assert_eq!(
+            schema.parse(&response).unwrap(),
+            json!({"name": "Ada", "age": 36})
+        );
+
+        let response = Message::assistant().with_text("Ada is 36");
+        let error = schema.parse(&response).unwrap_err();
+        assert!(error.starts_with("The response is not valid JSON"));
+        let retry = schema.retry_message(&response, &error);
+        assert!(retry.as_concat_text().contains("not valid JSON"));
+
+        let response = Message::assistant().with_text(r#"{"name": "Ada"}"#);
+        assert!(schema.parse(&response).is_err());
+    }
+
+    #[test]
+    fn test_wrapped_output() {
+        let schema =
+            OutputSchema::new(json!({"type": "array", "items": {"type": "string"}})).unwrap();
+        let tool = schema.tool();
+        assert_eq!(tool.input_schema["properties"]["output"]["type"], "array");
+
+        let response = Message::assistant().with_tool_request(
+            "call_1",
+            Ok(ToolCall::new(
+                FINAL_OUTPUT_TOOL,
+                json!({"output": ["a", "b"]}),
+            )),
+        );
+        assert_eq!(schema.parse(&response).unwrap(), json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_request() {
+        let schema = person_schema();
+        let tools = vec![Tool::new("read", "Read a file", json!({"type": "object"}))];
+
+        let (request_tools, options) = schema.request(OutputMode::Tool, &tools);
+        assert_eq!(request_tools.len(), 2);
+        assert_eq!(
+            options.tool_choice,
+            ToolChoice::Tool(FINAL_OUTPUT_TOOL.to_string())
+        );
+
+        let (request_tools, options) = schema.request(OutputMode::Native, &tools);
+        assert_eq!(request_tools.len(), 1);
+        assert_eq!(options.tool_choice, ToolChoice::None);
+        assert_eq!(options.response_schema.as_ref(), Some(schema.schema()));
+
+        assert_eq!(OutputMode::select(true, true), OutputMode::Native);
+        assert_eq!(OutputMode::select(false, true), OutputMode::Tool);
+        assert_eq!(OutputMode::select(false, false), OutputMode::Text);
+    }
+
+    #[test]
+    fn test_final_output_retries() {
+        let mut final_output = FinalOutput::new(Some(Arc::new(person_schema())), OutputMode::Text);
+        let response = Message::assistant().with_text("Ada is 36");
+
+        // Responses are handled as usual until the output is requested
+        assert!(matches!(
+            final_output.check(response.clone()),
+            OutputCheck::Response(_)
+        ));
+        assert!(final_output.request_message().is_some());
+
+        for attempt in 1..MAX_OUTPUT_ATTEMPTS {
+            match final_output.check(response.clone()) {
+                OutputCheck::Retry(events, messages) => {
+                    assert!(
+                        matches!(events[..], [AgentEvent::Retry { attempt: a, .. }] if a == attempt)
+                    );
+                    assert_eq!(messages.len(), 2);
+                }
+                _ => panic!("expected a retry"),
+            }
+        }
+        match final_output.check(response) {
+            OutputCheck::Done(events) => assert!(matches!(events[..], [AgentEvent::Error(_)])),
+            _ => panic!("expected the reply to end"),
+        }
+    }
+
+    #[test]
+    fn test_final_output_without_schema() {
+        let mut final_output = FinalOutput::new(None, OutputMode::Tool);
+        assert!(final_output.request_message().is_none());
+        let (tools, options) = final_output.request(&[]);
+        assert!(tools.is_empty());
+        assert_eq!(options.tool_choice, ToolChoice::Auto);
+    }
+}
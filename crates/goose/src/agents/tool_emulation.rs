@@ -0,0 +1,370 @@
+//! Tool calling for models without structured function calling
+//!
+//! The tools are described in the system prompt and the model is asked to invoke them with
+//! a JSON block. Invocations are parsed from the response text into tool requests, and
+//! previous requests and results are rendered back into text for the next turn. Besides
+//! the JSON blocks we ask for, `<tool_call>` tags as produced by Qwen and Hermes style
+//! models are understood too.
+
+use mcp_core::{Content, Role, Tool, ToolCall};
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::message::{Message, MessageContent};
+
+/// Instructions describing the tools and how to call them, appended to the system prompt
+pub fn tools_prompt(tools: &[Tool]) -> String {
+    if tools.is_empty() {
+        return String::new();
+    }
+
+    let descriptions: Vec<String> = tools
+        .iter()
+        .map(|tool| {
+            format!(
+                "### {}\n{}\nArguments schema: {}",
+                tool.name, tool.description, tool.input_schema
+            )
+        })
+        .collect();
+
+    format!(
+        "# Tools\n\n\
+        You can use the following tools to complete the task:\n\n\
+        {}\n\n\
+        To use a tool, respond with a JSON code block naming the tool and its arguments, \
+        and nothing after it:\n\n\
+        ```json\n{{\"tool\": \"<tool name>\", \"arguments\": {{<arguments>}}}}\n```\n\n\
+        You can call several tools by using several blocks. The results are sent back to you \
+        in <tool_result> tags. When you don't need a tool, answer normally without a JSON block.",
+        descriptions.join("\n\n")
+    )
+}
+
+/// Render tool requests and responses as text so that the model can follow the conversation
+pub fn render_messages(messages: &[Message]) -> Vec<Message> {
+    messages
+        .iter()
+        .map(|message| {
+            let mut rendered = Message {
+                role: message.role.clone(),
+                created: message.created,
+                content: Vec::new(),
+            };
+            for content in &message.content {
+                match content {
+                    MessageContent::ToolRequest(request) => {
+                        rendered
+                            .content
+                            .push(MessageContent::text(match &request.tool_call {
+                                Ok(call) => render_tool_call(call),
+                                Err(e) => format!("(an invalid tool call was made: {})", e),
+                            }))
+                    }
+                    MessageContent::ToolResponse(response) => match &response.tool_result {
+                        Ok(contents) => {
+                            let mut text = Vec::new();
+                            for content in contents.iter().filter(|content| {
+                                content
+                                    .audience()
+                                    .is_none_or(|audience| audience.contains(&Role::Assistant))
+                            }) {
+                                match content.unannotated() {
+                                    Content::Text(t) => text.push(t.text),
+                                    Content::Resource(resource) => text.push(resource.get_text()),
+                                    Content::Image(image) => rendered
+                                        .content
+                                        .push(MessageContent::image(image.data, image.mime_type)),
+                                    Content::Document(document) => {
+                                        rendered.content.push(MessageContent::document(
+                                            document.data,
+                                            document.mime_type,
+                                            document.name,
+                                        ))
+                                    }
+                                    Content::Audio(audio) => rendered
+                                        .content
+                                        .push(MessageContent::audio(audio.data, audio.mime_type)),
+                                }
+                            }
+                            rendered.content.push(MessageContent::text(format!(
+                                "<tool_result>\n{}\n</tool_result>",
+                                text.join("\n")
+                            )));
+                        }
+                        Err(e) => rendered.content.push(MessageContent::text(format!(
+                            "<tool_result error=\"true\">\n{}\n</tool_result>",
+                            e
+                        ))),
+                    },
+                    other => rendered.content.push(other.clone()),
+                }
+            }
+            rendered
+        })
+        .collect()
+}
+
+fn render_tool_call(call: &ToolCall) -> String {
+    format!(
+        "```json\n{}\n```",
+        json!({"tool": call.name, "arguments": call.arguments})
+    )
+}
+
+/// Turn the tool invocations in a response into tool requests
+///
+/// Only invocations of the given tools are recognized, so that JSON the model shows as an
+/// example or answer is left alone. The text around the invocations is kept.
+pub fn parse_tool_calls(message: Message, tools: &[Tool]) -> Message {
+    let mut content = Vec::new();
+
+    for item in message.content {
+        let MessageContent::Text(text) = &item else {
+            content.push(item);
+            continue;
+        };
+
+        let invocations = find_invocations(&text.text, tools);
+        if invocations.is_empty() {
+            content.push(item);
+            continue;
+        }
+
+        let mut remaining = String::new();
+        let mut last = 0;
+        let mut requests = Vec::new();
+        for (range, call) in invocations {
+            // Calls listed in the same block share its range
+            if range.start >= last {
+                remaining.push_str(&text.text[last..range.start]);
+                last = range.end;
+            }
+            let id = format!("call_{}", uuid::Uuid::new_v4().simple());
+            requests.push(MessageContent::tool_request(id, Ok(call)));
+        }
+        remaining.push_str(&text.text[last..]);
+
+        let remaining = remaining.trim();
+        if !remaining.is_empty() {
+            content.push(MessageContent::text(remaining));
+        }
+        content.extend(requests);
+    }
+
+    Message {
+        role: message.role,
+        created: message.created,
+        content,
+    }
+}
+
+fn find_invocations(text: &str, tools: &[Tool]) -> Vec<(std::ops::Range<usize>, ToolCall)> {
+    let patterns = [
+        Regex::new(r"(?s)```(?:json)?\s*\n?(.*?)```").unwrap(),
+        Regex::new(r"(?s)<tool_call>(.*?)</tool_call>").unwrap(),
+    ];
+
+    let mut invocations = Vec::new();
+    for pattern in &patterns {
+        for captures in pattern.captures_iter(text) {
+            let range = captures.get(0).unwrap().range();
+            if invocations
+                .iter()
+                .any(|(r, _): &(std::ops::Range<usize>, ToolCall)| {
+                    r.start < range.end && range.start < r.end
+                })
+            {
+                continue;
+            }
+
+            let Ok(value) = serde_json::from_str::<Value>(captures[1].trim()) else {
+                continue;
+            };
+            // A block can hold a single invocation or a list of them
+            let calls: Vec<ToolCall> = match value {
+                Value::Array(items) => items
+                    .iter()
+                    .filter_map(|v| to_tool_call(v, tools))
+                    .collect(),
+                value => to_tool_call(&value, tools).into_iter().collect(),
+            };
+            for call in calls {
+                invocations.push((range.clone(), call));
+            }
+        }
+    }
+
+    invocations.sort_by_key(|(range, _)| range.start);
+    invocations
+}
+
+fn to_tool_call(value: &Value, tools: &[Tool]) -> Option<ToolCall> {
+    let name = value
+        .get("tool")
+        .or_else(|| value.get("name"))
+        .and_then(|name| name.as_str())?;
+    if !tools.iter().any(|tool| tool.name == name) {
+        return None;
+    }
+
+    let arguments = match value
+        .get("arguments")
+        .or_else(|| value.get("parameters"))
+        .or_else(|| value.get("args"))
+    {
+        // Some models encode the arguments as a string
+        Some(Value::String(encoded)) => serde_json::from_str(encoded).ok()?,
+        Some(arguments) => arguments.clone(),
+        None => json!({}),
+    };
+
+    Some(ToolCall::new(name, arguments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Responses captured from local models asked to use the tools prompt
+    const JSON_BLOCK_RESPONSE: &str = r#"I'll list the files in the current directory.
+
+```json
+{"tool": "developer__shell", "arguments": {"command": "ls -la"}}
+```"#;
+
+    const TOOL_CALL_TAGS_RESPONSE: &str = r#"<tool_call>
+{"name": "developer__shell", "arguments": {"command": "pwd"}}
+</tool_call>
+<tool_call>
+{"name": "developer__text_editor", "arguments": "{\"command\": \"view\", \"path\": \"/tmp/a.txt\"}"}
+</tool_call>"#;
+
+    const EXAMPLE_JSON_RESPONSE: &str = r#"Here is an example config:
+
+```json
+{"name": "my-app", "version": "1.0.0"}
+```"#;
+
+    fn tools() -> Vec<Tool> {
+        vec![
+            Tool::new(
+                "developer__shell",
+                "Run a shell command",
+                json!({"type": "object", "properties": {"command": {"type": "string"}}}),
+            ),
+            Tool::new(
+                "developer__text_editor",
+                "View and edit files",
+                json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+            ),
+        ]
+    }
+
+    fn tool_calls(message: &Message) -> Vec<ToolCall> {
+        message
+            .content
+            .iter()
+            .filter_map(|c| c.as_tool_request())
+            .map(|r| r.tool_call.clone().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_tools_prompt() {
+        let prompt = tools_prompt(&tools());
+        assert!(prompt.contains("### developer__shell\nRun a shell command"));
+        assert!(prompt.contains(r#"{"tool": "<tool name>", "arguments": {<arguments>}}"#));
+        assert!(tools_prompt(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_json_block() {
+        let message = parse_tool_calls(
+            Message::assistant().with_text(JSON_BLOCK_RESPONSE),
+            &tools(),
+        );
+
+        assert_eq!(
+            message.content[0].as_text(),
+            Some("I'll list the files in the current directory.")
+        );
+        assert_eq!(
+            tool_calls(&message),
+            vec![ToolCall::new(
+                "developer__shell",
+                json!({"command": "ls -la"})
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_tool_call_tags() {
+        let message = parse_tool_calls(
+            Message::assistant().with_text(TOOL_CALL_TAGS_RESPONSE),
+            &tools(),
+        );
+
+        assert_eq!(message.content.len(), 2);
+        assert_eq!(
+            tool_calls(&message),
+            vec![
+                ToolCall::new("developer__shell", json!({"command": "pwd"})),
+                ToolCall::new(
+                    "developer__text_editor",
+                    json!({"command": "view", "path": "/tmp/a.txt"})
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_list_of_calls() {
+        let message = parse_tool_calls(
+            Message::assistant().with_text(
+                "Checking both.\n```json\n[{\"tool\": \"developer__shell\", \"arguments\": {\"command\": \"ls\"}}, {\"tool\": \"developer__shell\", \"arguments\": {\"command\": \"pwd\"}}]\n```\nDone.",
+            ),
+            &tools(),
+        );
+
+        assert_eq!(
+            message.content[0].as_text(),
+            Some("Checking both.\n\nDone.")
+        );
+        assert_eq!(tool_calls(&message).len(), 2);
+    }
+
+    #[test]
+    fn test_json_without_known_tool_is_text() {
+        let original = Message::assistant().with_text(EXAMPLE_JSON_RESPONSE);
+        let message = parse_tool_calls(original.clone(), &tools());
+        assert_eq!(message, original);
+    }
+
+    #[test]
+    fn test_render_messages() {
+        let messages = vec![
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ),
+            Message::user().with_tool_response("call_1", Ok(vec![Content::text("README.md")])),
+        ];
+
+        let rendered = render_messages(&messages);
+        let call = rendered[0].content[0].as_text().unwrap();
+        assert!(call.starts_with("```json\n"));
+        assert_eq!(rendered[1].role, Role::User);
+        assert_eq!(
+            rendered[1].content[0].as_text(),
+            Some("<tool_result>\nREADME.md\n</tool_result>")
+        );
+
+        // The rendered call parses back into the same request
+        let reparsed = parse_tool_calls(rendered[0].clone(), &tools());
+        assert_eq!(
+            tool_calls(&reparsed),
+            vec![ToolCall::new("developer__shell", json!({"command": "ls"}))]
+        );
+    }
+}
@@ -7,17 +7,22 @@ use tracing::{debug, error, instrument, warn};
 
 use super::Agent;
 use crate::agents::capabilities::Capabilities;
+use crate::agents::event::AgentEvent;
 use crate::agents::extension::{ExtensionConfig, ExtensionResult};
-use crate::message::{Message, ToolRequest};
+use crate::agents::reply::{Reply, Step};
+use crate::agents::structured_output::OutputSchema;
+use crate::config::Config;
+use crate::message::Message;
 use crate::providers::base::Provider;
 use crate::providers::base::ProviderUsage;
 use crate::providers::errors::ProviderError;
 use crate::register_agent;
 use crate::token_counter::TokenCounter;
-use crate::truncate::{truncate_messages, OldestFirstTruncation};
-use indoc::indoc;
+use crate::truncate::{
+    strategy_from_name, truncate_messages, OldestFirstTruncation, TruncationStrategy,
+};
 use mcp_core::tool::Tool;
-use serde_json::{json, Value};
+use serde_json::Value;
 
 const MAX_TRUNCATION_ATTEMPTS: usize = 3;
 const ESTIMATE_FACTOR_DECAY: f32 = 0.9;
@@ -26,6 +31,7 @@ const ESTIMATE_FACTOR_DECAY: f32 = 0.9;
 pub struct TruncateAgent {
     capabilities: Mutex<Capabilities>,
     token_counter: TokenCounter,
+    truncation_strategy: Box<dyn TruncationStrategy + Send + Sync>,
 }
 
 impl TruncateAgent {
@@ -34,9 +40,22 @@ impl TruncateAgent {
         Self {
             capabilities: Mutex::new(Capabilities::new(provider)),
             token_counter,
+            truncation_strategy: Self::configured_truncation_strategy(),
         }
     }
 
+    /// The truncation strategy set by GOOSE_TRUNCATION_STRATEGY, oldest first by default
+    fn configured_truncation_strategy() -> Box<dyn TruncationStrategy + Send + Sync> {
+        let name: String = match Config::global().get("GOOSE_TRUNCATION_STRATEGY") {
+            Ok(name) => name,
+            Err(_) => return Box::new(OldestFirstTruncation),
+        };
+        strategy_from_name(&name).unwrap_or_else(|e| {
+            warn!("{}, falling back to oldest_first", e);
+            Box::new(OldestFirstTruncation)
+        })
+    }
+
     /// Truncates the messages to fit within the model's context window
     /// Ensures the last message is a user message and removes tool call-response pairs
     async fn truncate_messages(
@@ -74,21 +93,22 @@ impl TruncateAgent {
 
         let context_limit = remaining_tokens;
 
-        // Calculate current token count of each message, use count_chat_tokens to ensure we
-        // capture the full content of the message, include ToolRequests and ToolResponses
-        let mut token_counts: Vec<usize> = messages
-            .iter()
-            .map(|msg| {
-                self.token_counter
-                    .count_chat_tokens("", std::slice::from_ref(msg), &[])
-            })
-            .collect();
+        // Calculate current token count of each message, including ToolRequests and
+        // ToolResponses. Counts are memoized so only new or changed messages are tokenized
+        let mut token_counts = self.token_counter.count_tokens_per_message(messages);
+
+        self.truncation_strategy.compact_messages(
+            messages,
+            &mut token_counts,
+            context_limit,
+            &|msg| self.token_counter.count_message_tokens(msg),
+        )?;
 
         truncate_messages(
             messages,
             &mut token_counts,
             context_limit,
-            &OldestFirstTruncation,
+            self.truncation_strategy.as_ref(),
         )
     }
 }
@@ -122,63 +142,15 @@ impl Agent for TruncateAgent {
     }
 
     #[instrument(skip(self, messages), fields(user_message))]
-    async fn reply(
+    async fn reply_events(
         &self,
         messages: &[Message],
-    ) -> anyhow::Result<BoxStream<'_, anyhow::Result<Message>>> {
-        let mut messages = messages.to_vec();
+    ) -> anyhow::Result<BoxStream<'_, anyhow::Result<AgentEvent>>> {
         let reply_span = tracing::Span::current();
         let mut capabilities = self.capabilities.lock().await;
-        let mut tools = capabilities.get_prefixed_tools().await?;
+        let (mut reply, notices) = Reply::new(&mut capabilities, messages).await?;
         let mut truncation_attempt: usize = 0;
 
-        // we add in the 2 resource tools if any extensions support resources
-        // TODO: make sure there is no collision with another extension's tool name
-        let read_resource_tool = Tool::new(
-            "platform__read_resource".to_string(),
-            indoc! {r#"
-                Read a resource from an extension.
-
-                Resources allow extensions to share data that provide context to LLMs, such as
-                files, database schemas, or application-specific information. This tool searches for the
-                resource URI in the provided extension, and reads in the resource content. If no extension
-                is provided, the tool will search all extensions for the resource.
-            "#}.to_string(),
-            json!({
-                "type": "object",
-                "required": ["uri"],
-                "properties": {
-                    "uri": {"type": "string", "description": "Resource URI"},
-                    "extension_name": {"type": "string", "description": "Optional extension name"}
-                }
-            }),
-        );
-
-        let list_resources_tool = Tool::new(
-            "platform__list_resources".to_string(),
-            indoc! {r#"
-                List resources from an extension(s).
-
-                Resources allow extensions to share data that provide context to LLMs, such as
-                files, database schemas, or application-specific information. This tool lists resources
-                in the provided extension, and returns a list for the user to browse. If no extension
-                is provided, the tool will search all extensions for the resource.
-            "#}.to_string(),
-            json!({
-                "type": "object",
-                "properties": {
-                    "extension_name": {"type": "string", "description": "Optional extension name"}
-                }
-            }),
-        );
-
-        if capabilities.supports_resources() {
-            tools.push(read_resource_tool);
-            tools.push(list_resources_tool);
-        }
-
-        let system_prompt = capabilities.get_system_prompt().await;
-
         // Set the user_message field in the span instead of creating a new event
         if let Some(content) = messages
             .last()
@@ -190,70 +162,57 @@ impl Agent for TruncateAgent {
 
         Ok(Box::pin(async_stream::try_stream! {
             let _reply_guard = reply_span.enter();
+            for notice in notices {
+                yield AgentEvent::Notice(notice);
+            }
             loop {
                 // Attempt to get completion from provider
-                match capabilities.provider().complete(
-                    &system_prompt,
-                    &messages,
-                    &tools,
-                ).await {
+                match reply.complete(&capabilities).await {
                     Ok((response, usage)) => {
-                        capabilities.record_usage(usage).await;
+                        yield AgentEvent::Usage(usage);
 
                         // Reset truncation attempt
                         truncation_attempt = 0;
 
-                        // Yield the assistant's response
-                        yield response.clone();
-
-                        tokio::task::yield_now().await;
-
-                        // First collect any tool requests
-                        let tool_requests: Vec<&ToolRequest> = response.content
-                            .iter()
-                            .filter_map(|content| content.as_tool_request())
-                            .collect();
-
-                        if tool_requests.is_empty() {
-                            break;
-                        }
-
-                        // Then dispatch each in parallel
-                        let futures: Vec<_> = tool_requests
-                            .iter()
-                            .filter_map(|request| request.tool_call.clone().ok())
-                            .map(|tool_call| capabilities.dispatch_tool_call(tool_call))
-                            .collect();
-
-                        // Process all the futures in parallel but wait until all are finished
-                        let outputs = futures::future::join_all(futures).await;
-
-                        // Create a message with the responses
-                        let mut message_tool_response = Message::user();
-                        // Now combine these into MessageContent::ToolResponse using the original ID
-                        for (request, output) in tool_requests.iter().zip(outputs.into_iter()) {
-                            message_tool_response = message_tool_response.with_tool_response(
-                                request.id.clone(),
-                                output,
-                            );
+                        match reply.handle_response(response) {
+                            Step::Done(events) => {
+                                for event in events {
+                                    yield event;
+                                }
+                                break;
+                            }
+                            Step::Continue(events) => {
+                                for event in events {
+                                    yield event;
+                                }
+                            }
+                            Step::RunTools(events, tool_calls) => {
+                                for event in events {
+                                    yield event;
+                                }
+                                tokio::task::yield_now().await;
+                                for event in reply.run_tools(&capabilities, tool_calls).await {
+                                    yield event;
+                                }
+                            }
                         }
-
-                        yield message_tool_response.clone();
-
-                        messages.push(response);
-                        messages.push(message_tool_response);
                     },
                     Err(ProviderError::ContextLengthExceeded(_)) => {
                         if truncation_attempt >= MAX_TRUNCATION_ATTEMPTS {
                             // Create an error message & terminate the stream
                             // the previous message would have been a user message (e.g. before any tool calls, this is just after the input message.
                             // at the start of a loop after a tool call, it would be after a tool_use assistant followed by a tool_result user)
-                            yield Message::assistant().with_text("Error: Context length exceeds limits even after multiple attempts to truncate. Please start a new session with fresh context and try again.");
+                            yield AgentEvent::Error("Error: Context length exceeds limits even after multiple attempts to truncate. Please start a new session with fresh context and try again.".to_string());
                             break;
                         }
 
                         truncation_attempt += 1;
                         warn!("Context length exceeded. Truncation Attempt: {}/{}.", truncation_attempt, MAX_TRUNCATION_ATTEMPTS);
+                        yield AgentEvent::Retry {
+                            attempt: truncation_attempt,
+                            max_attempts: MAX_TRUNCATION_ATTEMPTS,
+                            reason: "context length exceeded".to_string(),
+                        };
 
                         // Decay the estimate factor as we make more truncation attempts
                         // Estimate factor decays like this over time: 0.9, 0.81, 0.729, ...
@@ -262,11 +221,12 @@ impl Agent for TruncateAgent {
                         // release the lock before truncation to prevent deadlock
                         drop(capabilities);
 
-                        if let Err(err) = self.truncate_messages(&mut messages, estimate_factor, &system_prompt, &mut tools).await {
-                            yield Message::assistant().with_text(format!("Error: Unable to truncate messages to stay within context limit. \n\nRan into this error: {}.\n\nPlease start a new session with fresh context and try again.", err));
+                        let messages_before = reply.messages.len();
+                        if let Err(err) = self.truncate_messages(&mut reply.messages, estimate_factor, &reply.system_prompt, &mut reply.tools).await {
+                            yield AgentEvent::Error(format!("Error: Unable to truncate messages to stay within context limit. \n\nRan into this error: {}.\n\nPlease start a new session with fresh context and try again.", err));
                             break;
                         }
-
+                        yield AgentEvent::ContextTruncated { messages_before, messages_after: reply.messages.len() };
 
                         // Re-acquire the lock
                         capabilities = self.capabilities.lock().await;
@@ -277,7 +237,7 @@ impl Agent for TruncateAgent {
                     Err(e) => {
                         // Create an error message & terminate the stream
                         error!("Error: {}", e);
-                        yield Message::assistant().with_text(format!("Ran into this error: {e}.\n\nPlease retry if you think this is a transient or recoverable error."));
+                        yield AgentEvent::Error(format!("Ran into this error: {e}.\n\nPlease retry if you think this is a transient or recoverable error."));
                         break;
                     }
                 }
@@ -297,6 +257,133 @@ impl Agent for TruncateAgent {
         let mut capabilities = self.capabilities.lock().await;
         capabilities.add_system_prompt_extension(extension);
     }
+
+    async fn set_output_schema(&mut self, schema: Option<Value>) -> anyhow::Result<()> {
+        let schema = schema.map(OutputSchema::new).transpose()?;
+        let mut capabilities = self.capabilities.lock().await;
+        capabilities.set_output_schema(schema);
+        Ok(())
+    }
 }
 
 register_agent!("truncate", TruncateAgent);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::structured_output::FINAL_OUTPUT_TOOL;
+    use crate::model::ModelConfig;
+    use crate::providers::base::{CompletionOptions, ProviderMetadata, ToolChoice, Usage};
+    use futures::StreamExt;
+    use mcp_core::ToolCall;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    /// Returns the scripted responses in order and records the options of each completion
+    struct ScriptedProvider {
+        responses: StdMutex<Vec<Message>>,
+        options: Arc<StdMutex<Vec<CompletionOptions>>>,
+    }
+
+    #[async_trait]
+    impl Provider for ScriptedProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("gpt-4o".to_string())
+        }
+
+        async fn complete(
+            &self,
+            system: &str,
+            messages: &[Message],
+            tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+                .await
+        }
+
+        async fn complete_with_options(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+            options: &CompletionOptions,
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            self.options.lock().unwrap().push(options.clone());
+            let response = self.responses.lock().unwrap().remove(0);
+            Ok((
+                response,
+                ProviderUsage::new("mock".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    fn output_call(arguments: Value) -> Message {
+        Message::assistant()
+            .with_tool_request("call", Ok(ToolCall::new(FINAL_OUTPUT_TOOL, arguments)))
+    }
+
+    #[tokio::test]
+    async fn test_structured_output() -> anyhow::Result<()> {
+        let options = Arc::new(StdMutex::new(Vec::new()));
+        let provider = ScriptedProvider {
+            responses: StdMutex::new(vec![
+                Message::assistant().with_text("Ada Lovelace was 36."),
+                output_call(json!({"name": "Ada Lovelace", "age": "36"})),
+                output_call(json!({"name": "Ada Lovelace", "age": 36})),
+            ]),
+            options: options.clone(),
+        };
+        let mut agent = TruncateAgent::new(Box::new(provider));
+        agent
+            .set_output_schema(Some(json!({
+                "type": "object",
+                "required": ["name", "age"],
+                "properties": {"name": {"type": "string"}, "age": {"type": "integer"}}
+            })))
+            .await?;
+
+        let events: Vec<AgentEvent> = agent
+            .reply_events(&[Message::user().with_text("Who was Ada Lovelace?")])
+            .await?
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        let retries = events
+            .iter()
+            .filter(|event| matches!(event, AgentEvent::Retry { .. }))
+            .count();
+        assert_eq!(retries, 1);
+        assert!(matches!(
+            events.last(),
+            Some(AgentEvent::StructuredOutput(output)) if *output == json!({"name": "Ada Lovelace", "age": 36})
+        ));
+
+        // The tool loop runs without a tool choice, then the output tool is required
+        let options = options.lock().unwrap();
+        assert_eq!(options.len(), 3);
+        assert_eq!(options[0].tool_choice, ToolChoice::Auto);
+        assert_eq!(
+            options[2].tool_choice,
+            ToolChoice::Tool(FINAL_OUTPUT_TOOL.to_string())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_output_schema() {
+        let provider = ScriptedProvider {
+            responses: StdMutex::new(vec![]),
+            options: Arc::new(StdMutex::new(Vec::new())),
+        };
+        let mut agent = TruncateAgent::new(Box::new(provider));
+        assert!(agent
+            .set_output_schema(Some(json!({"type": 42})))
+            .await
+            .is_err());
+    }
+}
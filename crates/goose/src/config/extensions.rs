@@ -17,12 +17,14 @@ pub struct ExtensionEntry {
 pub struct ExtensionManager;
 
 impl ExtensionManager {
-    /// Get the extension configuration if enabled
+    /// Get the extension configuration in the config file if enabled
+    ///
+    /// The default extensions are written when the config file has none.
     pub fn get_config(name: &str) -> Result<Option<ExtensionConfig>> {
         let config = Config::global();
 
         // Try to get the extension entry
-        let extensions: HashMap<String, ExtensionEntry> = match config.get("extensions") {
+        let extensions: HashMap<String, ExtensionEntry> = match config.get_global("extensions") {
             Ok(exts) => exts,
             Err(super::ConfigError::NotFound(_)) => {
                 // Initialize with default developer extension
@@ -54,8 +56,9 @@ impl ExtensionManager {
     pub fn set(entry: ExtensionEntry) -> Result<()> {
         let config = Config::global();
 
-        let mut extensions: HashMap<String, ExtensionEntry> =
-            config.get("extensions").unwrap_or_else(|_| HashMap::new());
+        let mut extensions: HashMap<String, ExtensionEntry> = config
+            .get_global("extensions")
+            .unwrap_or_else(|_| HashMap::new());
 
         extensions.insert(entry.config.name().parse()?, entry);
         config.set("extensions", serde_json::to_value(extensions)?)?;
@@ -66,8 +69,9 @@ impl ExtensionManager {
     pub fn remove(name: &str) -> Result<()> {
         let config = Config::global();
 
-        let mut extensions: HashMap<String, ExtensionEntry> =
-            config.get("extensions").unwrap_or_else(|_| HashMap::new());
+        let mut extensions: HashMap<String, ExtensionEntry> = config
+            .get_global("extensions")
+            .unwrap_or_else(|_| HashMap::new());
 
         extensions.remove(name);
         config.set("extensions", serde_json::to_value(extensions)?)?;
@@ -78,8 +82,9 @@ impl ExtensionManager {
     pub fn set_enabled(name: &str, enabled: bool) -> Result<()> {
         let config = Config::global();
 
-        let mut extensions: HashMap<String, ExtensionEntry> =
-            config.get("extensions").unwrap_or_else(|_| HashMap::new());
+        let mut extensions: HashMap<String, ExtensionEntry> = config
+            .get_global("extensions")
+            .unwrap_or_else(|_| HashMap::new());
 
         if let Some(entry) = extensions.get_mut(name) {
             entry.enabled = enabled;
@@ -89,6 +94,10 @@ impl ExtensionManager {
     }
 
     /// Get all extensions and their configurations
+    ///
+    /// These are the extensions a session starts, those of a trusted project config replace
+    /// the others. The other functions read and change only the extensions of the config
+    /// file.
     pub fn get_all() -> Result<Vec<ExtensionEntry>> {
         let config = Config::global();
         let extensions: HashMap<String, ExtensionEntry> =
@@ -96,19 +105,29 @@ impl ExtensionManager {
         Ok(Vec::from_iter(extensions.values().cloned()))
     }
 
-    /// Get all extension names
+    /// Get the extensions in the config file and their configurations, the ones the other
+    /// functions change
+    pub fn get_all_global() -> Result<Vec<ExtensionEntry>> {
+        let config = Config::global();
+        let extensions: HashMap<String, ExtensionEntry> =
+            config.get_global("extensions").unwrap_or_default();
+        Ok(Vec::from_iter(extensions.values().cloned()))
+    }
+
+    /// Get the names of the extensions in the config file
     pub fn get_all_names() -> Result<Vec<String>> {
         let config = Config::global();
-        Ok(config
-            .get("extensions")
-            .unwrap_or_else(|_| get_keys(Default::default())))
+        Ok(get_keys(
+            config.get_global("extensions").unwrap_or_default(),
+        ))
     }
 
-    /// Check if an extension is enabled
+    /// Check if an extension is enabled in the config file
     pub fn is_enabled(name: &str) -> Result<bool> {
         let config = Config::global();
-        let extensions: HashMap<String, ExtensionEntry> =
-            config.get("extensions").unwrap_or_else(|_| HashMap::new());
+        let extensions: HashMap<String, ExtensionEntry> = config
+            .get_global("extensions")
+            .unwrap_or_else(|_| HashMap::new());
 
         Ok(extensions.get(name).map(|e| e.enabled).unwrap_or(false))
     }
@@ -2,5 +2,8 @@ mod base;
 mod extensions;
 
 pub use crate::agents::ExtensionConfig;
-pub use base::{Config, ConfigError, APP_STRATEGY};
+pub use base::{
+    Config, ConfigError, ConfigLayer, APP_STRATEGY, PROFILE_KEY, PROFILE_KEYS, PROJECT_CONFIG_DIR,
+    PROJECT_KEYS,
+};
 pub use extensions::{ExtensionEntry, ExtensionManager};
@@ -2,6 +2,7 @@ pub mod agents;
 pub mod config;
 pub mod message;
 pub mod model;
+pub mod model_registry;
 pub mod prompt_template;
 pub mod providers;
 pub mod token_counter;
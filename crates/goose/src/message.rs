@@ -8,7 +8,7 @@ use std::collections::HashSet;
 /// The content of the messages uses MCP types to avoid additional conversions
 /// when interacting with MCP servers.
 use chrono::Utc;
-use mcp_core::content::{Content, ImageContent, TextContent};
+use mcp_core::content::{AudioContent, Content, DocumentContent, ImageContent, TextContent};
 use mcp_core::handler::ToolResult;
 use mcp_core::role::Role;
 use mcp_core::tool::ToolCall;
@@ -30,6 +30,8 @@ pub struct ToolResponse {
 pub enum MessageContent {
     Text(TextContent),
     Image(ImageContent),
+    Document(DocumentContent),
+    Audio(AudioContent),
     ToolRequest(ToolRequest),
     ToolResponse(ToolResponse),
 }
@@ -50,6 +52,27 @@ impl MessageContent {
         })
     }
 
+    pub fn document<S: Into<String>, T: Into<String>>(
+        data: S,
+        mime_type: T,
+        name: Option<String>,
+    ) -> Self {
+        MessageContent::Document(DocumentContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            name,
+            annotations: None,
+        })
+    }
+
+    pub fn audio<S: Into<String>, T: Into<String>>(data: S, mime_type: T) -> Self {
+        MessageContent::Audio(AudioContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+        })
+    }
+
     pub fn tool_request<S: Into<String>>(id: S, tool_call: ToolResult<ToolCall>) -> Self {
         MessageContent::ToolRequest(ToolRequest {
             id: id.into(),
@@ -109,6 +132,8 @@ impl From<Content> for MessageContent {
         match content {
             Content::Text(text) => MessageContent::Text(text),
             Content::Image(image) => MessageContent::Image(image),
+            Content::Document(document) => MessageContent::Document(document),
+            Content::Audio(audio) => MessageContent::Audio(audio),
             Content::Resource(resource) => MessageContent::Text(TextContent {
                 text: resource.get_text(),
                 annotations: None,
@@ -160,6 +185,21 @@ impl Message {
         self.with_content(MessageContent::image(data, mime_type))
     }
 
+    /// Add document content (e.g. a PDF or plain file) to the message
+    pub fn with_document<S: Into<String>, T: Into<String>>(
+        self,
+        data: S,
+        mime_type: T,
+        name: Option<String>,
+    ) -> Self {
+        self.with_content(MessageContent::document(data, mime_type, name))
+    }
+
+    /// Add audio content to the message
+    pub fn with_audio<S: Into<String>, T: Into<String>>(self, data: S, mime_type: T) -> Self {
+        self.with_content(MessageContent::audio(data, mime_type))
+    }
+
     /// Add a tool request to the message
     pub fn with_tool_request<S: Into<String>>(
         self,
@@ -1,4 +1,8 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::model_registry::{ModelCapabilities, ModelRegistry};
 
 const DEFAULT_CONTEXT_LIMIT: usize = 128_000;
 
@@ -13,7 +17,8 @@ pub struct ModelConfig {
     pub model_name: String,
     // Optional tokenizer name (corresponds to the sanitized HuggingFace tokenizer name)
     // "Xenova/gpt-4o" -> "Xenova/gpt-4o"
-    // If not provided, best attempt will be made to infer from model name or default
+    // If not provided, GOOSE_TOKENIZER is used when set, otherwise best attempt will be made
+    // to infer from model name or default
     pub tokenizer_name: String,
     /// Optional explicit context limit that overrides any defaults
     pub context_limit: Option<usize>,
@@ -21,6 +26,29 @@ pub struct ModelConfig {
     pub temperature: Option<f32>,
     /// Optional maximum tokens to generate
     pub max_tokens: Option<i32>,
+    /// Optional nucleus sampling, the probability mass of the tokens considered
+    pub top_p: Option<f32>,
+    /// Optional number of most likely tokens considered
+    pub top_k: Option<u32>,
+    /// Sequences that end generation when produced
+    #[serde(default)]
+    pub stop_sequences: Vec<String>,
+    /// Optional seed for reproducible sampling, where the provider supports it
+    pub seed: Option<i64>,
+    /// Optional penalty of tokens by how often they already appeared
+    pub frequency_penalty: Option<f32>,
+    /// Optional penalty of tokens that already appeared
+    pub presence_penalty: Option<f32>,
+    /// Provider specific fields merged into the request body
+    #[serde(default)]
+    pub extra_body: Map<String, Value>,
+    /// What the model supports, from the model registry
+    #[serde(default = "ModelCapabilities::all")]
+    pub capabilities: ModelCapabilities,
+    /// Describe tools in the prompt and parse calls from the response text instead of
+    /// using the provider's function calling, see GOOSE_TOOL_EMULATION
+    #[serde(default)]
+    pub tool_emulation: bool,
 }
 
 impl ModelConfig {
@@ -28,21 +56,90 @@ impl ModelConfig {
     ///
     /// The context limit is set with the following precedence:
     /// 1. Explicit context_limit if provided in config
-    /// 2. Model-specific default based on model name
+    /// 2. Model-specific default from the model registry
     /// 3. Global default (128_000) (in get_context_limit)
+    ///
+    /// Models without a registry entry are assumed to support every capability.
     pub fn new(model_name: String) -> Self {
-        let context_limit = Self::get_model_specific_limit(&model_name);
-        let tokenizer_name = Self::infer_tokenizer_name(&model_name);
+        let model_info = ModelRegistry::global().lookup(&model_name);
+        let context_limit = model_info.and_then(|info| info.context_limit);
+        let tokenizer_name = Config::global()
+            .get::<String>("GOOSE_TOKENIZER")
+            .ok()
+            .or_else(|| model_info.and_then(|info| info.tokenizer.clone()))
+            .unwrap_or_else(|| Self::infer_tokenizer_name(&model_name).to_string());
+        let capabilities = model_info
+            .map(|info| info.capabilities)
+            .unwrap_or_else(ModelCapabilities::all);
+        let tool_emulation = Self::tool_emulation_enabled(&model_name);
 
         Self {
             model_name,
-            tokenizer_name: tokenizer_name.to_string(),
+            tokenizer_name,
             context_limit,
             temperature: None,
             max_tokens: None,
+            top_p: None,
+            top_k: None,
+            stop_sequences: vec![],
+            seed: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            extra_body: Map::new(),
+            capabilities,
+            tool_emulation,
         }
     }
 
+    /// Set the sampling parameters configured with GOOSE_TEMPERATURE, GOOSE_MAX_TOKENS,
+    /// GOOSE_TOP_P, GOOSE_TOP_K, GOOSE_STOP_SEQUENCES, GOOSE_SEED, GOOSE_FREQUENCY_PENALTY,
+    /// GOOSE_PRESENCE_PENALTY and GOOSE_EXTRA_BODY
+    ///
+    /// Used for the model of the agent, so that other requests such as checking a
+    /// provider's connection aren't affected by them.
+    pub fn with_config_defaults(mut self, config: &Config) -> Self {
+        self.temperature = config.get("GOOSE_TEMPERATURE").ok();
+        self.max_tokens = config.get("GOOSE_MAX_TOKENS").ok();
+        self.top_p = config.get("GOOSE_TOP_P").ok();
+        self.top_k = config.get("GOOSE_TOP_K").ok();
+        // A single stop sequence can be given as a string
+        self.stop_sequences = config
+            .get::<Vec<String>>("GOOSE_STOP_SEQUENCES")
+            .or_else(|_| {
+                config
+                    .get::<String>("GOOSE_STOP_SEQUENCES")
+                    .map(|s| vec![s])
+            })
+            .unwrap_or_default();
+        self.seed = config.get("GOOSE_SEED").ok();
+        self.frequency_penalty = config.get("GOOSE_FREQUENCY_PENALTY").ok();
+        self.presence_penalty = config.get("GOOSE_PRESENCE_PENALTY").ok();
+        self.extra_body = config.get("GOOSE_EXTRA_BODY").unwrap_or_default();
+        self
+    }
+
+    /// Whether GOOSE_TOOL_EMULATION, a list of model names, contains this model
+    ///
+    /// Entries match any model whose name contains them, e.g. `qwen2.5-coder` matches
+    /// `qwen2.5-coder:32b`. The list may also be given as a comma separated string.
+    fn tool_emulation_enabled(model_name: &str) -> bool {
+        let config = Config::global();
+        let models: Vec<String> = config
+            .get::<Vec<String>>("GOOSE_TOOL_EMULATION")
+            .or_else(|_| {
+                config
+                    .get::<String>("GOOSE_TOOL_EMULATION")
+                    .map(|models| models.split(',').map(|m| m.trim().to_string()).collect())
+            })
+            .unwrap_or_default();
+
+        let model_name = model_name.to_lowercase();
+        models
+            .iter()
+            .filter(|m| !m.is_empty())
+            .any(|m| model_name.contains(&m.to_lowercase()))
+    }
+
     fn infer_tokenizer_name(model_name: &str) -> &'static str {
         if model_name.contains("claude") {
             CLAUDE_TOKENIZER
@@ -52,24 +149,6 @@ impl ModelConfig {
         }
     }
 
-    /// Get model-specific context limit based on model name
-    fn get_model_specific_limit(model_name: &str) -> Option<usize> {
-        // Implement some sensible defaults
-        match model_name {
-            // OpenAI models, https://platform.openai.com/docs/models#models-overview
-            name if name.contains("gpt-4o") => Some(128_000),
-            name if name.contains("gpt-4-turbo") => Some(128_000),
-
-            // Anthropic models, https://docs.anthropic.com/en/docs/about-claude/models
-            name if name.contains("claude-3") => Some(200_000),
-
-            // Meta Llama models, https://github.com/meta-llama/llama-models/tree/main?tab=readme-ov-file#llama-models-1
-            name if name.contains("llama3.2") => Some(128_000),
-            name if name.contains("llama3.3") => Some(128_000),
-            _ => None,
-        }
-    }
-
     /// Set an explicit context limit
     pub fn with_context_limit(mut self, limit: Option<usize>) -> Self {
         // Default is None and therefore DEFAULT_CONTEXT_LIMIT, only set
@@ -81,6 +160,14 @@ impl ModelConfig {
         self
     }
 
+    /// Set the tokenizer name, overriding the one inferred from the model name
+    pub fn with_tokenizer_name(mut self, tokenizer_name: Option<String>) -> Self {
+        if let Some(tokenizer_name) = tokenizer_name {
+            self.tokenizer_name = tokenizer_name;
+        }
+        self
+    }
+
     /// Set the temperature
     pub fn with_temperature(mut self, temp: Option<f32>) -> Self {
         self.temperature = temp;
@@ -93,6 +180,60 @@ impl ModelConfig {
         self
     }
 
+    /// Set the nucleus sampling probability mass
+    pub fn with_top_p(mut self, top_p: Option<f32>) -> Self {
+        self.top_p = top_p;
+        self
+    }
+
+    /// Set the number of most likely tokens considered
+    pub fn with_top_k(mut self, top_k: Option<u32>) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Set the sequences that end generation
+    pub fn with_stop_sequences(mut self, stop_sequences: Vec<String>) -> Self {
+        self.stop_sequences = stop_sequences;
+        self
+    }
+
+    /// Set the sampling seed
+    pub fn with_seed(mut self, seed: Option<i64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the frequency penalty
+    pub fn with_frequency_penalty(mut self, penalty: Option<f32>) -> Self {
+        self.frequency_penalty = penalty;
+        self
+    }
+
+    /// Set the presence penalty
+    pub fn with_presence_penalty(mut self, penalty: Option<f32>) -> Self {
+        self.presence_penalty = penalty;
+        self
+    }
+
+    /// Set provider specific fields merged into the request body
+    pub fn with_extra_body(mut self, extra_body: Map<String, Value>) -> Self {
+        self.extra_body = extra_body;
+        self
+    }
+
+    /// Set the model capabilities, overriding the ones from the model registry
+    pub fn with_capabilities(mut self, capabilities: ModelCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Enable or disable tool emulation for the model
+    pub fn with_tool_emulation(mut self, tool_emulation: bool) -> Self {
+        self.tool_emulation = tool_emulation;
+        self
+    }
+
     // Get the tokenizer name
     pub fn tokenizer_name(&self) -> &str {
         &self.tokenizer_name
@@ -108,6 +249,8 @@ impl ModelConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
+    use serial_test::serial;
 
     #[test]
     fn test_model_config_context_limits() {
@@ -139,4 +282,67 @@ mod tests {
         assert_eq!(config.max_tokens, Some(1000));
         assert_eq!(config.context_limit, Some(50_000));
     }
+
+    #[test]
+    #[serial]
+    fn test_model_config_tokenizer_name() {
+        let config = ModelConfig::new("claude-3-opus".to_string());
+        assert_eq!(config.tokenizer_name(), CLAUDE_TOKENIZER);
+
+        let config = ModelConfig::new("qwen2.5-coder".to_string());
+        assert_eq!(config.tokenizer_name(), GPT_4O_TOKENIZER);
+
+        let config = ModelConfig::new("qwen2.5-coder".to_string())
+            .with_tokenizer_name(Some("Qwen--Qwen2.5-Coder-32B-Instruct".to_string()));
+        assert_eq!(config.tokenizer_name(), "Qwen--Qwen2.5-Coder-32B-Instruct");
+
+        std::env::set_var("GOOSE_TOKENIZER", "Qwen--Qwen2.5-Coder-32B-Instruct");
+        let config = ModelConfig::new("qwen2.5-coder".to_string());
+        assert_eq!(config.tokenizer_name(), "Qwen--Qwen2.5-Coder-32B-Instruct");
+        std::env::remove_var("GOOSE_TOKENIZER");
+    }
+
+    #[test]
+    #[serial]
+    fn test_model_config_tool_emulation() {
+        assert!(!ModelConfig::new("qwen2.5-coder:32b".to_string()).tool_emulation);
+
+        std::env::set_var("GOOSE_TOOL_EMULATION", "deepseek-r1, qwen2.5-coder");
+        assert!(ModelConfig::new("qwen2.5-coder:32b".to_string()).tool_emulation);
+        assert!(!ModelConfig::new("qwen2.5".to_string()).tool_emulation);
+
+        std::env::set_var("GOOSE_TOOL_EMULATION", r#"["Llama3.2"]"#);
+        assert!(ModelConfig::new("llama3.2:3b".to_string()).tool_emulation);
+        std::env::remove_var("GOOSE_TOOL_EMULATION");
+    }
+
+    #[test]
+    fn test_model_config_with_config_defaults() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = Config::new(temp_file.path(), "goose-test-sampling").unwrap();
+        config.set("GOOSE_TOP_P", json!(0.9)).unwrap();
+        config.set("GOOSE_TOP_K", json!(40)).unwrap();
+        config.set("GOOSE_STOP_SEQUENCES", json!("END")).unwrap();
+        config.set("GOOSE_SEED", json!(42)).unwrap();
+        config
+            .set("GOOSE_EXTRA_BODY", json!({"repetition_penalty": 1.1}))
+            .unwrap();
+
+        let model = ModelConfig::new("test-model".to_string()).with_config_defaults(&config);
+        assert_eq!(model.top_p, Some(0.9));
+        assert_eq!(model.top_k, Some(40));
+        assert_eq!(model.stop_sequences, vec!["END".to_string()]);
+        assert_eq!(model.seed, Some(42));
+        assert_eq!(model.frequency_penalty, None);
+        assert_eq!(model.extra_body["repetition_penalty"], json!(1.1));
+
+        config
+            .set("GOOSE_STOP_SEQUENCES", json!(["END", "STOP"]))
+            .unwrap();
+        let model = ModelConfig::new("test-model".to_string())
+            .with_config_defaults(&config)
+            .with_presence_penalty(Some(0.5));
+        assert_eq!(model.stop_sequences, vec!["END", "STOP"]);
+        assert_eq!(model.presence_penalty, Some(0.5));
+    }
 }
@@ -0,0 +1,297 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use etcetera::{choose_app_strategy, AppStrategy};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::config::APP_STRATEGY;
+
+// The registry bundled with goose, see the file for the format
+const BUNDLED_REGISTRY: &str = include_str!("model_registry.yaml");
+
+static GLOBAL_REGISTRY: Lazy<ModelRegistry> = Lazy::new(ModelRegistry::load);
+
+/// Price in USD per million tokens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// What a model supports beyond plain text chat
+///
+/// In the registry `vision` and `reasoning` must be listed to be enabled, while tool
+/// calling and the other features almost every model has are enabled unless set to false.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelCapabilities {
+    /// Structured tool calling
+    pub tools: bool,
+    /// Images in messages and tool results
+    pub vision: bool,
+    pub reasoning: bool,
+    /// A separate system prompt, otherwise it is sent as part of the first user message
+    pub system_prompt: bool,
+    /// Several tool calls in a single response, turned off in the request where the API
+    /// allows it (OpenAI style and Anthropic)
+    pub parallel_tool_calls: bool,
+}
+
+impl Default for ModelCapabilities {
+    fn default() -> Self {
+        Self {
+            tools: true,
+            vision: false,
+            reasoning: false,
+            system_prompt: true,
+            parallel_tool_calls: true,
+        }
+    }
+}
+
+impl ModelCapabilities {
+    /// Every capability, assumed for models goose has no registry entry for
+    pub fn all() -> Self {
+        Self {
+            tools: true,
+            vision: true,
+            reasoning: true,
+            system_prompt: true,
+            parallel_tool_calls: true,
+        }
+    }
+}
+
+/// Everything goose knows about a model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub name: String,
+    /// Additional substrings that identify this model, e.g. provider specific spellings
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Providers that serve this model under `name`
+    #[serde(default)]
+    pub providers: Vec<String>,
+    pub context_limit: Option<usize>,
+    pub max_output_tokens: Option<usize>,
+    pub tokenizer: Option<String>,
+    pub pricing: Option<ModelPricing>,
+    #[serde(default)]
+    pub capabilities: ModelCapabilities,
+}
+
+impl ModelInfo {
+    /// The length of the longest name or pattern contained in `model_name`, if any
+    fn match_len(&self, model_name: &str) -> Option<usize> {
+        std::iter::once(&self.name)
+            .chain(self.patterns.iter())
+            .filter(|pattern| contains_at_boundary(model_name, &pattern.to_lowercase()))
+            .map(|pattern| pattern.len())
+            .max()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    models: Vec<ModelInfo>,
+}
+
+/// A registry of model limits, tokenizers, pricing and capabilities
+///
+/// The bundled registry is extended by `models.yaml` in the goose config directory,
+/// where entries with the same name replace the bundled ones.
+#[derive(Debug, Clone, Default)]
+pub struct ModelRegistry {
+    models: Vec<ModelInfo>,
+}
+
+impl ModelRegistry {
+    /// The registry loaded from the bundled and user registry files
+    pub fn global() -> &'static ModelRegistry {
+        &GLOBAL_REGISTRY
+    }
+
+    /// Parse a registry from its yaml representation
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let file: RegistryFile = serde_yaml::from_str(yaml)?;
+        Ok(Self {
+            models: file.models,
+        })
+    }
+
+    fn load() -> Self {
+        let mut registry =
+            Self::from_yaml(BUNDLED_REGISTRY).expect("bundled model registry is valid");
+
+        if let Some(path) = Self::user_registry_path().filter(|path| path.exists()) {
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|yaml| Self::from_yaml(&yaml))
+            {
+                Ok(user) => registry.extend(user),
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid model registry {}: {}", path.display(), e)
+                }
+            }
+        }
+
+        registry
+    }
+
+    /// The path of the user registry, ~/.config/goose/models.yaml on macOS and Linux
+    pub fn user_registry_path() -> Option<PathBuf> {
+        choose_app_strategy(APP_STRATEGY.clone())
+            .ok()
+            .map(|strategy| strategy.in_config_dir("models.yaml"))
+    }
+
+    /// Add the models of `other`, replacing any existing models with the same name
+    pub fn extend(&mut self, other: ModelRegistry) {
+        for model in other.models {
+            match self.models.iter_mut().find(|m| m.name == model.name) {
+                Some(existing) => *existing = model,
+                None => self.models.push(model),
+            }
+        }
+    }
+
+    /// All models in the registry
+    pub fn models(&self) -> &[ModelInfo] {
+        &self.models
+    }
+
+    /// Find the registry entry for a model name
+    ///
+    /// An exact name match wins, otherwise the entry whose name or pattern is the
+    /// longest substring of `model_name`.
+    pub fn lookup(&self, model_name: &str) -> Option<&ModelInfo> {
+        let model_name = model_name.to_lowercase();
+        if let Some(model) = self
+            .models
+            .iter()
+            .find(|m| m.name.to_lowercase() == model_name)
+        {
+            return Some(model);
+        }
+
+        self.models
+            .iter()
+            .filter_map(|m| m.match_len(&model_name).map(|len| (len, m)))
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, m)| m)
+    }
+
+    /// Names of the models served by the given provider
+    pub fn models_for_provider(&self, provider: &str) -> Vec<String> {
+        self.models
+            .iter()
+            .filter(|m| m.providers.iter().any(|p| p == provider))
+            .map(|m| m.name.clone())
+            .collect()
+    }
+}
+
+/// Whether `pattern` occurs in `name` at the start of a word, so that short names
+/// such as "o1" don't match inside unrelated model names
+fn contains_at_boundary(name: &str, pattern: &str) -> bool {
+    name.match_indices(pattern).any(|(idx, _)| {
+        name[..idx]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_ascii_alphanumeric())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_registry_lookup() {
+        let registry = ModelRegistry::global();
+
+        let model = registry.lookup("claude-3-5-sonnet-latest").unwrap();
+        assert_eq!(model.context_limit, Some(200_000));
+        assert!(model.capabilities.tools);
+
+        // Provider specific spellings resolve to the most specific entry
+        let model = registry
+            .lookup("anthropic.claude-3-5-sonnet-20241022-v2:0")
+            .unwrap();
+        assert_eq!(model.name, "claude-3-5-sonnet-latest");
+        let model = registry.lookup("anthropic/claude-3.5-sonnet").unwrap();
+        assert_eq!(model.name, "claude-3-5-sonnet-latest");
+        let model = registry.lookup("claude-3-haiku-20240307").unwrap();
+        assert_eq!(model.name, "claude-3");
+
+        assert_eq!(registry.lookup("gpt-4o-mini").unwrap().name, "gpt-4o-mini");
+        assert_eq!(
+            registry
+                .lookup("models/gemini-1.5-flash-latest")
+                .unwrap()
+                .name,
+            "gemini-1.5-flash"
+        );
+        assert_eq!(
+            registry
+                .lookup("databricks-meta-llama-3-3-70b-instruct")
+                .unwrap()
+                .name,
+            "llama3.3"
+        );
+
+        // Entries without capabilities keep tool calling
+        let model = registry.lookup("dbrx-instruct").unwrap();
+        assert!(model.capabilities.tools);
+        assert!(!model.capabilities.vision);
+
+        let model = registry.lookup("o1-mini-2024-09-12").unwrap();
+        assert!(!model.capabilities.tools);
+        assert!(!model.capabilities.system_prompt);
+        let model = registry.lookup("o1-preview-2024-09-12").unwrap();
+        assert_eq!(model.name, "o1-preview");
+        assert!(!model.capabilities.tools);
+
+        assert!(registry.lookup("unknown-model").is_none());
+        assert!(registry.lookup("foo1").is_none());
+    }
+
+    #[test]
+    fn test_user_registry_overrides_bundled() {
+        let mut registry = ModelRegistry::from_yaml(BUNDLED_REGISTRY).unwrap();
+        let user = ModelRegistry::from_yaml(
+            r#"
+models:
+  - name: gpt-4o
+    context_limit: 64000
+  - name: my-local-model
+    providers: [ollama]
+    context_limit: 8192
+    tokenizer: Qwen--Qwen2.5-Coder-32B-Instruct
+    capabilities: { tools: true }
+"#,
+        )
+        .unwrap();
+        registry.extend(user);
+
+        let model = registry.lookup("gpt-4o").unwrap();
+        assert_eq!(model.context_limit, Some(64_000));
+        assert!(model.pricing.is_none());
+        assert!(model.capabilities.tools);
+
+        let model = registry.lookup("my-local-model:latest").unwrap();
+        assert_eq!(
+            model.tokenizer.as_deref(),
+            Some("Qwen--Qwen2.5-Coder-32B-Instruct")
+        );
+        assert!(model.capabilities.tools);
+        assert!(!model.capabilities.vision);
+        assert!(model.capabilities.system_prompt);
+
+        assert!(registry
+            .models_for_provider("ollama")
+            .contains(&"my-local-model".to_string()));
+    }
+}
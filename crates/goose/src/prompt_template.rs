@@ -1,11 +1,18 @@
+use etcetera::{choose_app_strategy, AppStrategy};
 use include_dir::{include_dir, Dir};
 use serde::Serialize;
-use std::path::PathBuf;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tera::{Context, Error as TeraError, Tera};
 
 // The prompts directory needs to be embedded in the binary (so it works when distributed)
 static PROMPTS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/src/prompts");
 
+/// Name prefix under which the embedded prompts are available to custom templates,
+/// e.g. `{% include "builtin/system.md" %}`
+const BUILTIN_PREFIX: &str = "builtin/";
+
 pub fn load_prompt<T: Serialize>(template: &str, context_data: &T) -> Result<String, TeraError> {
     let mut tera = Tera::default();
     tera.add_raw_template("inline_template", template)?;
@@ -37,6 +44,116 @@ pub fn load_prompt_file<T: Serialize>(
     load_prompt(&template_content, context_data)
 }
 
+/// Directories searched for user provided prompt templates, highest priority first
+///
+/// - the project directory: `.goose/prompts` next to the project config file, once the user
+///   trusts the project config, see [`crate::config::Config::trust_project`]
+/// - the user config directory: `~/.config/goose/prompts` on macOS/Linux
+pub fn template_search_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let config = crate::config::Config::global();
+    if config.is_project_trusted().unwrap_or(false) {
+        paths.extend(config.project_prompts_dir());
+    }
+    if let Ok(strategy) = choose_app_strategy(crate::config::APP_STRATEGY.clone()) {
+        paths.push(strategy.config_dir().join("prompts"));
+    }
+    paths
+}
+
+/// Find the first user provided template with this name in the given directories
+pub fn find_template_override_in(dirs: &[PathBuf], template_name: &str) -> Option<PathBuf> {
+    dirs.iter()
+        .map(|dir| dir.join(template_name))
+        .find(|path| path.is_file())
+}
+
+/// Find the user provided template overriding the embedded one, if any
+pub fn find_template_override(template_name: &str) -> Option<PathBuf> {
+    find_template_override_in(&template_search_paths(), template_name)
+}
+
+/// Render a template that may include or extend the embedded prompts as `builtin/<name>`
+pub fn load_prompt_with_builtins<T: Serialize>(
+    template: &str,
+    context_data: &T,
+) -> Result<String, TeraError> {
+    let mut tera = Tera::default();
+    for file in PROMPTS_DIR.files() {
+        let name = format!("{}{}", BUILTIN_PREFIX, file.path().to_string_lossy());
+        tera.add_raw_template(&name, &String::from_utf8_lossy(file.contents()))?;
+    }
+    tera.add_raw_template("inline_template", template)?;
+    let context = Context::from_serialize(context_data)?;
+    let rendered = tera.render("inline_template", &context)?;
+    Ok(rendered.trim().to_string())
+}
+
+/// Load a prompt by name, preferring a user override from the template search path
+/// and falling back to the embedded prompt
+pub fn load_prompt_file_with_overrides<T: Serialize>(
+    template_name: &str,
+    context_data: &T,
+) -> Result<String, TeraError> {
+    match find_template_override(template_name) {
+        Some(path) => {
+            tracing::debug!("Using prompt template override at {}", path.display());
+            let template = std::fs::read_to_string(&path).map_err(|e| {
+                TeraError::chain(format!("Failed to read template {}", path.display()), e)
+            })?;
+            load_prompt_with_builtins(&template, context_data)
+        }
+        None => load_prompt_file(template_name, context_data),
+    }
+}
+
+/// Variables available to every system prompt template besides `extensions`
+///
+/// `git_branch` comes from [`current_git_branch`], which callers run once per session.
+pub fn system_prompt_variables(git_branch: Option<&str>) -> HashMap<&'static str, Value> {
+    let mut context: HashMap<&str, Value> = HashMap::new();
+
+    let current_date_time = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let cwd = std::env::current_dir()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    context.insert("current_date_time", Value::String(current_date_time));
+    context.insert("cwd", Value::String(cwd));
+    context.insert("os", Value::String(std::env::consts::OS.to_string()));
+    context.insert(
+        "git_branch",
+        git_branch.map_or(Value::Null, |branch| Value::String(branch.to_string())),
+    );
+    context
+}
+
+/// The checked out git branch of the current working directory, if any
+pub fn current_git_branch() -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!branch.is_empty()).then_some(branch)
+}
+
+/// Check that a custom system prompt template renders, returning the rendered prompt
+///
+/// The template is rendered with the same variables goose provides at runtime, with
+/// an empty extension list.
+pub fn validate_system_prompt_template(path: &Path) -> Result<String, TeraError> {
+    let template = std::fs::read_to_string(path)
+        .map_err(|e| TeraError::chain(format!("Failed to read template {}", path.display()), e))?;
+    let mut context = system_prompt_variables(current_git_branch().as_deref());
+    context.insert("extensions", Value::Array(vec![]));
+    load_prompt_with_builtins(&template, &context)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,6 +205,64 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_find_template_override_in() {
+        let project = tempfile::tempdir().unwrap();
+        let user = tempfile::tempdir().unwrap();
+        let dirs = vec![project.path().to_path_buf(), user.path().to_path_buf()];
+
+        assert_eq!(find_template_override_in(&dirs, "system.md"), None);
+
+        std::fs::write(user.path().join("system.md"), "user").unwrap();
+        assert_eq!(
+            find_template_override_in(&dirs, "system.md"),
+            Some(user.path().join("system.md"))
+        );
+
+        // the project directory takes priority over the user config directory
+        std::fs::write(project.path().join("system.md"), "project").unwrap();
+        assert_eq!(
+            find_template_override_in(&dirs, "system.md"),
+            Some(project.path().join("system.md"))
+        );
+    }
+
+    #[test]
+    fn test_load_prompt_with_builtins() {
+        let template = "Team rules first.\n\n{% include \"builtin/mock.md\" %}";
+        let mut context = HashMap::new();
+        context.insert("name".to_string(), "Alice".to_string());
+        context.insert("age".to_string(), 30.to_string());
+
+        let result = load_prompt_with_builtins(template, &context).unwrap();
+        assert_eq!(
+            result,
+            "Team rules first.\n\nThis prompt is only used for testing.\n\nHello, Alice! You are 30 years old."
+        );
+    }
+
+    #[test]
+    fn test_validate_system_prompt_template() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let valid = dir.path().join("valid.md");
+        std::fs::write(
+            &valid,
+            "Running on {{ os }} in {{ cwd }}.\n{% include \"builtin/system.md\" %}",
+        )
+        .unwrap();
+        let rendered = validate_system_prompt_template(&valid).unwrap();
+        assert!(rendered.starts_with(&format!("Running on {}", std::env::consts::OS)));
+        assert!(rendered.contains("You are a general-purpose AI agent called Goose"));
+
+        let invalid = dir.path().join("invalid.md");
+        std::fs::write(&invalid, "{{ unknown_variable }}").unwrap();
+        assert!(validate_system_prompt_template(&invalid).is_err());
+
+        let missing = dir.path().join("missing.md");
+        assert!(validate_system_prompt_template(&missing).is_err());
+    }
+
     #[test]
     fn test_load_prompt_with_tools() {
         let template = "### Tool Descriptions\n{% for tool in tools %}\n{{tool.name}}: {{tool.description}}{% endfor %}";
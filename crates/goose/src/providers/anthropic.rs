@@ -1,13 +1,16 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
 use serde_json::Value;
-use std::time::Duration;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
+use super::base::{CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
-use super::formats::anthropic::{create_request, get_usage, response_to_message};
-use super::utils::{emit_debug_trace, get_model};
+use super::formats::anthropic::{add_tool_choice, create_request, get_usage, response_to_message};
+use super::http::provider_client;
+use super::rate_limit::{rate_limiter, RateLimiter};
+use super::utils::{emit_debug_trace, get_model, get_model_ids, handle_response_openai_compat};
 use crate::message::Message;
 use crate::model::ModelConfig;
 use mcp_core::tool::Tool;
@@ -45,9 +48,7 @@ impl AnthropicProvider {
             .get("ANTHROPIC_HOST")
             .unwrap_or_else(|_| "https://api.anthropic.com".to_string());
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let client = provider_client("ANTHROPIC")?;
 
         Ok(Self {
             client,
@@ -143,17 +144,54 @@ impl Provider for AnthropicProvider {
         self.model.clone()
     }
 
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        rate_limiter("ANTHROPIC", &self.api_key)
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        Some(self.host.clone())
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let url = url::Url::parse(&self.host)
+            .and_then(|base_url| base_url.join("v1/models?limit=1000"))
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let response = self
+            .client
+            .get(url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .send()
+            .await?;
+        let payload = handle_response_openai_compat(response).await?;
+
+        Ok(Some(get_model_ids(&payload)?))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools)?;
+        let mut payload = create_request(&self.model, system, messages, tools)?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
 
         // Make request
         let response = self.post(payload.clone()).await?;
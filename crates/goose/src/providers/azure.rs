@@ -1,12 +1,19 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::Value;
-use std::time::Duration;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::embedding::{EmbeddingModel, EmbeddingProvider};
 use super::errors::ProviderError;
-use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::formats::openai::{
+    add_response_format, add_tool_choice, create_embedding_request, create_request,
+    embeddings_from_response, get_usage, response_to_message, EMBEDDING_BATCH_SIZE,
+};
+use super::http::provider_client;
+use super::rate_limit::{rate_limiter, RateLimiter};
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -16,6 +23,8 @@ pub const AZURE_DEFAULT_MODEL: &str = "gpt-4o";
 pub const AZURE_DOC_URL: &str =
     "https://learn.microsoft.com/en-us/azure/ai-services/openai/concepts/models";
 pub const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+// Azure serves models through deployments, the embedding model is the name of a deployment
+pub const AZURE_DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
 pub const AZURE_OPENAI_KNOWN_MODELS: &[&str] = &["gpt-4o", "gpt-4o-mini", "gpt-4"];
 
 #[derive(Debug, serde::Serialize)]
@@ -27,6 +36,7 @@ pub struct AzureProvider {
     deployment_name: String,
     api_version: String,
     model: ModelConfig,
+    embedding: EmbeddingModel,
 }
 
 impl Default for AzureProvider {
@@ -46,9 +56,7 @@ impl AzureProvider {
             .get("AZURE_OPENAI_API_VERSION")
             .unwrap_or_else(|_| AZURE_DEFAULT_API_VERSION.to_string());
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let client = provider_client("AZURE_OPENAI")?;
 
         Ok(Self {
             client,
@@ -57,16 +65,32 @@ impl AzureProvider {
             deployment_name,
             api_version,
             model,
+            embedding: EmbeddingModel::new(AZURE_DEFAULT_EMBEDDING_MODEL),
         })
     }
 
+    pub fn with_embedding_model(mut self, embedding: EmbeddingModel) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
     async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+        self.post_to_deployment(&self.deployment_name, "chat/completions", payload)
+            .await
+    }
+
+    async fn post_to_deployment(
+        &self,
+        deployment_name: &str,
+        operation: &str,
+        payload: Value,
+    ) -> Result<Value, ProviderError> {
         let mut base_url = url::Url::parse(&self.endpoint)
             .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
 
         base_url.set_path(&format!(
-            "openai/deployments/{}/chat/completions",
-            self.deployment_name
+            "openai/deployments/{}/{}",
+            deployment_name, operation
         ));
         base_url.set_query(Some(&format!("api-version={}", self.api_version)));
 
@@ -118,17 +142,39 @@ impl Provider for AzureProvider {
         self.model.clone()
     }
 
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        rate_limiter("AZURE_OPENAI", &self.api_key)
+    }
+
+    fn supports_response_schema(&self) -> bool {
+        true
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        let mut payload =
+            create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
+        add_response_format(&mut payload, options.response_schema.as_ref());
         let response = self.post(payload.clone()).await?;
 
         let message = response_to_message(response.clone())?;
@@ -145,3 +191,37 @@ impl Provider for AzureProvider {
         Ok((message, ProviderUsage::new(model, usage)))
     }
 }
+
+#[async_trait]
+impl EmbeddingProvider for AzureProvider {
+    fn embedding_model(&self) -> EmbeddingModel {
+        self.embedding.clone()
+    }
+
+    fn max_batch_size(&self) -> usize {
+        EMBEDDING_BATCH_SIZE
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError> {
+        let mut payload = create_embedding_request(&self.embedding, texts);
+        // The deployment determines the model
+        payload
+            .as_object_mut()
+            .expect("payload should have model key")
+            .remove("model");
+
+        let response = self
+            .post_to_deployment(&self.embedding.model_name, "embeddings", payload)
+            .await?;
+
+        let embeddings = embeddings_from_response(&response)?;
+        let usage = get_usage(&response).unwrap_or_default();
+        Ok((
+            embeddings,
+            ProviderUsage::new(self.embedding.model_name.clone(), usage),
+        ))
+    }
+}
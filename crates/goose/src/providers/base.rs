@@ -1,9 +1,13 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
 
 use super::errors::ProviderError;
+use super::rate_limit::RateLimiter;
 use crate::message::Message;
 use crate::model::ModelConfig;
+use crate::model_registry::{ModelCapabilities, ModelRegistry};
 use mcp_core::tool::Tool;
 
 /// Metadata about a provider's configuration requirements and capabilities
@@ -17,8 +21,8 @@ pub struct ProviderMetadata {
     pub description: String,
     /// The default/recommended model for this provider
     pub default_model: String,
-    /// A list of currently known models
-    /// TODO: eventually query the apis directly
+    /// A list of currently known models, including those listed for this provider
+    /// in the model registry, used when the models can't be fetched from the provider
     pub known_models: Vec<String>,
     /// Link to the docs where models can be found
     pub model_doc_link: String,
@@ -36,6 +40,13 @@ impl ProviderMetadata {
         model_doc_link: &str,
         config_keys: Vec<ConfigKey>,
     ) -> Self {
+        let mut known_models = known_models;
+        for model in ModelRegistry::global().models_for_provider(name) {
+            if !known_models.contains(&model) {
+                known_models.push(model);
+            }
+        }
+
         Self {
             name: name.to_string(),
             display_name: display_name.to_string(),
@@ -112,6 +123,73 @@ impl Usage {
     }
 }
 
+/// How the model may use the tools offered in a completion
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call tools
+    #[default]
+    Auto,
+    /// The model must answer without calling tools
+    None,
+    /// The model must call at least one tool
+    Required,
+    /// The model must call the tool with this name
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// Check that the choice can be honored with the tools of the completion
+    pub fn validate(&self, tools: &[Tool]) -> Result<()> {
+        match self {
+            ToolChoice::Required if tools.is_empty() => {
+                Err(anyhow!("Tool use is required but no tools are available"))
+            }
+            ToolChoice::Tool(name) if !tools.iter().any(|tool| &tool.name == name) => Err(anyhow!(
+                "Tool choice {name} is not one of the available tools"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The tools to offer when the provider's API has no tool choice
+    ///
+    /// Only offering the tools the model may call approximates the choice, except that a
+    /// tool call can't be required.
+    pub fn restrict_tools(&self, tools: &[Tool]) -> Vec<Tool> {
+        match self {
+            ToolChoice::Auto | ToolChoice::Required => tools.to_vec(),
+            ToolChoice::None => vec![],
+            ToolChoice::Tool(name) => tools
+                .iter()
+                .filter(|tool| &tool.name == name)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Options of a single completion, on top of the provider's model config
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompletionOptions {
+    pub tool_choice: ToolChoice,
+    /// A JSON schema the text of the response must match, only for providers that
+    /// [support it](Provider::supports_response_schema)
+    pub response_schema: Option<Value>,
+}
+
+impl CompletionOptions {
+    pub fn with_tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.tool_choice = tool_choice;
+        self
+    }
+
+    pub fn with_response_schema(mut self, response_schema: Option<Value>) -> Self {
+        self.response_schema = response_schema;
+        self
+    }
+}
+
 use async_trait::async_trait;
 
 /// Base trait for AI providers (OpenAI, Anthropic, etc)
@@ -142,8 +220,79 @@ pub trait Provider: Send + Sync {
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError>;
 
+    /// Generate the next message with options for this completion only
+    ///
+    /// Providers whose API can control tool use override this. The default approximates
+    /// the tool choice by only offering the tools the model may call.
+    async fn complete_with_options(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        options: &CompletionOptions,
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        if options.response_schema.is_some() {
+            return Err(ProviderError::RequestFailed(
+                "This provider can't constrain responses to a JSON schema".to_string(),
+            ));
+        }
+        options.tool_choice.validate(tools)?;
+        let tools = options.tool_choice.restrict_tools(tools);
+        self.complete(system, messages, &tools).await
+    }
+
+    /// Whether the provider's API can constrain responses to a JSON schema, see
+    /// [`CompletionOptions::response_schema`]
+    fn supports_response_schema(&self) -> bool {
+        false
+    }
+
     /// Get the model config from the provider
     fn get_model_config(&self) -> ModelConfig;
+
+    /// What the configured model supports through this provider
+    ///
+    /// Providers whose API lacks features the model has restrict this, the agent adapts
+    /// its requests accordingly.
+    fn model_capabilities(&self) -> ModelCapabilities {
+        self.get_model_config().capabilities
+    }
+
+    /// Fetch the models the provider currently serves from its API
+    ///
+    /// Returns `None` for providers that can't list their models, callers then fall back
+    /// to `ProviderMetadata::known_models`. See [`super::model_cache::ModelCache`] for a
+    /// cached version of this call.
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        Ok(None)
+    }
+
+    /// Identifies where [`Provider::fetch_supported_models`] gets its list from, such as the
+    /// host, so that a list cached for another configuration isn't used
+    ///
+    /// Returns `None` when the list shouldn't be cached, e.g. because it is read from the
+    /// config.
+    fn supported_models_key(&self) -> Option<String> {
+        Some(String::new())
+    }
+
+    /// Whether [`Provider::fetch_supported_models`] can run unattended, or would have to ask
+    /// the user to log in first
+    ///
+    /// Background listings such as the server's provider list skip providers that return
+    /// `false`, so that they never start an interactive login.
+    fn lists_models_without_login(&self) -> bool {
+        true
+    }
+
+    /// The limiter of the requests sent with this provider's credentials, if limits are
+    /// configured
+    ///
+    /// Providers made with [`super::create`] wait on it before each completion, see
+    /// [`super::rate_limit::RateLimitedProvider`].
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -152,6 +301,39 @@ mod tests {
 
     use serde_json::json;
 
+    #[test]
+    fn test_tool_choice() {
+        let tools = vec![
+            Tool::new("read", "Read a file", json!({"type": "object"})),
+            Tool::new("write", "Write a file", json!({"type": "object"})),
+        ];
+
+        assert!(ToolChoice::Auto.validate(&[]).is_ok());
+        assert!(ToolChoice::None.validate(&[]).is_ok());
+        assert!(ToolChoice::Required.validate(&[]).is_err());
+        assert!(ToolChoice::Tool("read".to_string())
+            .validate(&tools)
+            .is_ok());
+        assert!(ToolChoice::Tool("delete".to_string())
+            .validate(&tools)
+            .is_err());
+
+        assert_eq!(ToolChoice::Required.restrict_tools(&tools).len(), 2);
+        assert!(ToolChoice::None.restrict_tools(&tools).is_empty());
+        let restricted = ToolChoice::Tool("write".to_string()).restrict_tools(&tools);
+        assert_eq!(restricted.len(), 1);
+        assert_eq!(restricted[0].name, "write");
+
+        assert_eq!(
+            serde_json::from_value::<ToolChoice>(json!({"tool": "read"})).unwrap(),
+            ToolChoice::Tool("read".to_string())
+        );
+        assert_eq!(
+            serde_json::from_value::<ToolChoice>(json!("required")).unwrap(),
+            ToolChoice::Required
+        );
+    }
+
     #[test]
     fn test_usage_creation() {
         let usage = Usage::new(Some(10), Some(20), Some(30));
@@ -1,10 +1,13 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use aws_sdk_bedrockruntime::operation::converse::ConverseError;
+use aws_sdk_bedrockruntime::operation::invoke_model::InvokeModelError;
 use aws_sdk_bedrockruntime::{types as bedrock, Client};
 use mcp_core::Tool;
+use serde_json::Value;
 
-use super::base::{Provider, ProviderMetadata, ProviderUsage};
+use super::base::{CompletionOptions, Provider, ProviderMetadata, ProviderUsage};
+use super::embedding::{EmbeddingModel, EmbeddingProvider};
 use super::errors::ProviderError;
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -12,7 +15,9 @@ use crate::providers::utils::emit_debug_trace;
 
 // Import the migrated helper functions from providers/formats/bedrock.rs
 use super::formats::bedrock::{
-    from_bedrock_message, from_bedrock_usage, to_bedrock_message, to_bedrock_tool_config,
+    from_bedrock_embedding_response, from_bedrock_message, from_bedrock_usage,
+    to_bedrock_embedding_request, to_bedrock_inference_config, to_bedrock_json, to_bedrock_message,
+    to_bedrock_tool_config, EmbeddingFamily,
 };
 
 pub const BEDROCK_DOC_LINK: &str =
@@ -23,12 +28,14 @@ pub const BEDROCK_KNOWN_MODELS: &[&str] = &[
     "anthropic.claude-3-5-sonnet-20240620-v1:0",
     "anthropic.claude-3-5-sonnet-20241022-v2:0",
 ];
+pub const BEDROCK_DEFAULT_EMBEDDING_MODEL: &str = "amazon.titan-embed-text-v2:0";
 
 #[derive(Debug, serde::Serialize)]
 pub struct BedrockProvider {
     #[serde(skip)]
     client: Client,
     model: ModelConfig,
+    embedding: EmbeddingModel,
 }
 
 impl BedrockProvider {
@@ -36,7 +43,16 @@ impl BedrockProvider {
         let sdk_config = futures::executor::block_on(aws_config::load_from_env());
         let client = Client::new(&sdk_config);
 
-        Ok(Self { client, model })
+        Ok(Self {
+            client,
+            model,
+            embedding: EmbeddingModel::new(BEDROCK_DEFAULT_EMBEDDING_MODEL),
+        })
+    }
+
+    pub fn with_embedding_model(mut self, embedding: EmbeddingModel) -> Self {
+        self.embedding = embedding;
+        self
     }
 }
 
@@ -65,15 +81,26 @@ impl Provider for BedrockProvider {
         self.model.clone()
     }
 
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
         let model_name = &self.model.model_name;
 
@@ -89,8 +116,16 @@ impl Provider for BedrockProvider {
                     .collect::<Result<_>>()?,
             ));
 
-        if !tools.is_empty() {
-            request = request.tool_config(to_bedrock_tool_config(tools)?);
+        if let Some(tool_config) = to_bedrock_tool_config(tools, &options.tool_choice, messages)? {
+            request = request.tool_config(tool_config);
+        }
+        if let Some(inference_config) = to_bedrock_inference_config(&self.model)? {
+            request = request.inference_config(inference_config);
+        }
+        if !self.model.extra_body.is_empty() {
+            request = request.additional_model_request_fields(to_bedrock_json(&Value::Object(
+                self.model.extra_body.clone(),
+            )));
         }
 
         let response = request.send().await;
@@ -160,3 +195,54 @@ impl Provider for BedrockProvider {
         Ok((message, provider_usage))
     }
 }
+
+#[async_trait]
+impl EmbeddingProvider for BedrockProvider {
+    fn embedding_model(&self) -> EmbeddingModel {
+        self.embedding.clone()
+    }
+
+    fn max_batch_size(&self) -> usize {
+        EmbeddingFamily::of(&self.embedding)
+            .map(|family| family.max_batch_size())
+            .unwrap_or(1)
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError> {
+        let body = to_bedrock_embedding_request(&self.embedding, texts)?;
+
+        let response = self
+            .client
+            .invoke_model()
+            .model_id(self.embedding.model_name.clone())
+            .content_type("application/json")
+            .accept("application/json")
+            .body(
+                serde_json::to_vec(&body)
+                    .map_err(anyhow::Error::from)?
+                    .into(),
+            )
+            .send()
+            .await
+            .map_err(|err| match err.into_service_error() {
+                InvokeModelError::AccessDeniedException(err) => {
+                    ProviderError::Authentication(format!("Failed to call Bedrock: {:?}", err))
+                }
+                InvokeModelError::ThrottlingException(err) => {
+                    ProviderError::RateLimitExceeded(format!("Failed to call Bedrock: {:?}", err))
+                }
+                err => ProviderError::ServerError(format!("Failed to call Bedrock: {:?}", err)),
+            })?;
+
+        let response: serde_json::Value = serde_json::from_slice(response.body.as_ref())
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid Bedrock response: {e}")))?;
+        let (embeddings, usage) = from_bedrock_embedding_response(&self.embedding, &response)?;
+        Ok((
+            embeddings,
+            ProviderUsage::new(self.embedding.model_name.clone(), usage),
+        ))
+    }
+}
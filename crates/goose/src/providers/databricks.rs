@@ -1,15 +1,18 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::Duration;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
-use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::formats::openai::{add_tool_choice, create_request, get_usage, response_to_message};
+use super::http::provider_client;
 use super::oauth;
-use super::utils::{get_model, ImageFormat};
+use super::rate_limit::{rate_limiter, RateLimiter};
+use super::utils::{get_model, get_model_list, handle_response_openai_compat, ImageFormat};
 use crate::config::ConfigError;
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -18,7 +21,8 @@ use url::Url;
 
 const DEFAULT_CLIENT_ID: &str = "databricks-cli";
 const DEFAULT_REDIRECT_URL: &str = "http://localhost:8020";
-const DEFAULT_SCOPES: &[&str] = &["all-apis"];
+// offline_access issues a refresh token, so that the login lasts beyond the first hour
+const DEFAULT_SCOPES: &[&str] = &["all-apis", "offline_access"];
 
 pub const DATABRICKS_DEFAULT_MODEL: &str = "databricks-meta-llama-3-3-70b-instruct";
 // Databricks can passthrough to a wide range of models, we only provide the default
@@ -96,9 +100,7 @@ impl DatabricksProvider {
 
         let host = host?;
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let client = provider_client("DATABRICKS")?;
 
         // If we find a databricks token we prefer that
         if let Ok(api_key) = config.get_secret("DATABRICKS_TOKEN") {
@@ -229,17 +231,77 @@ impl Provider for DatabricksProvider {
         self.model.clone()
     }
 
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        rate_limiter("DATABRICKS", &self.host)
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        Some(self.host.clone())
+    }
+
+    fn lists_models_without_login(&self) -> bool {
+        match &self.auth {
+            DatabricksAuth::Token(_) => true,
+            DatabricksAuth::OAuth {
+                host,
+                client_id,
+                scopes,
+                ..
+            } => oauth::has_fresh_token(host, client_id, scopes),
+        }
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let url = Url::parse(&self.host)
+            .and_then(|base_url| base_url.join("api/2.0/serving-endpoints"))
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let auth_header = self.ensure_auth_header().await?;
+        let response = self
+            .client
+            .get(url)
+            .header("Authorization", auth_header)
+            .send()
+            .await?;
+        let payload = handle_response_openai_compat(response).await?;
+
+        // Serving endpoints also host embedding and custom models, keep the chat ones
+        let mut models: Vec<String> = get_model_list(&payload, "endpoints")?
+            .iter()
+            .filter(|endpoint| {
+                endpoint["task"]
+                    .as_str()
+                    .is_none_or(|task| task == "llm/v1/chat")
+            })
+            .filter_map(|endpoint| endpoint["name"].as_str().map(String::from))
+            .collect();
+        models.sort();
+        Ok(Some(models))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
         let mut payload = create_request(&self.model, system, messages, tools, &self.image_format)?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
         // Remove the model key which is part of the url with databricks
         payload
             .as_object_mut()
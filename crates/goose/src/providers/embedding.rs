@@ -0,0 +1,202 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::base::{ProviderUsage, Usage};
+use super::errors::ProviderError;
+
+/// The size of the vectors of well known embedding models
+const KNOWN_DIMENSIONS: &[(&str, usize)] = &[
+    ("text-embedding-3-small", 1536),
+    ("text-embedding-3-large", 3072),
+    ("text-embedding-ada-002", 1536),
+    ("text-embedding-004", 768),
+    ("nomic-embed-text", 768),
+    ("mxbai-embed-large", 1024),
+    ("all-minilm", 384),
+    ("amazon.titan-embed-text-v1", 1536),
+    ("amazon.titan-embed-text-v2:0", 1024),
+    ("cohere.embed-english-v3", 1024),
+    ("cohere.embed-multilingual-v3", 1024),
+];
+
+/// The size of the vectors a model produces, if it is a well known model
+pub fn known_dimensions(model_name: &str) -> Option<usize> {
+    // Ollama models can carry a tag, e.g. nomic-embed-text:latest
+    let base_name = model_name.split(':').next().unwrap_or(model_name);
+    KNOWN_DIMENSIONS
+        .iter()
+        .find(|(name, _)| *name == model_name || *name == base_name)
+        .map(|(_, dimensions)| *dimensions)
+}
+
+/// The embedding model of a provider
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingModel {
+    pub model_name: String,
+    /// Requested size of the vectors, for models that can shorten them
+    pub dimensions: Option<usize>,
+}
+
+impl EmbeddingModel {
+    pub fn new(model_name: impl Into<String>) -> Self {
+        Self {
+            model_name: model_name.into(),
+            dimensions: None,
+        }
+    }
+
+    pub fn with_dimensions(mut self, dimensions: Option<usize>) -> Self {
+        self.dimensions = dimensions;
+        self
+    }
+
+    /// The size of the vectors this model produces, if known before embedding anything
+    pub fn output_dimensions(&self) -> Option<usize> {
+        self.dimensions
+            .or_else(|| known_dimensions(&self.model_name))
+    }
+}
+
+/// Providers that can turn text into embedding vectors
+///
+/// This is separate from [`Provider`](super::base::Provider) since not every provider offers
+/// embeddings, and the embedding provider can be configured independently from the chat
+/// provider with GOOSE_EMBEDDING_PROVIDER and GOOSE_EMBEDDING_MODEL.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    fn embedding_model(&self) -> EmbeddingModel;
+
+    /// The most texts sent in one request
+    fn max_batch_size(&self) -> usize;
+
+    /// Embed texts in a single request, at most `max_batch_size` of them
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError>;
+
+    /// Embed any number of texts, in as many requests as needed
+    ///
+    /// The vectors are in the order of the texts and the usage is summed over the requests.
+    async fn embed(
+        &self,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let mut usage = ProviderUsage::new(self.embedding_model().model_name, Usage::default());
+
+        for batch in texts.chunks(self.max_batch_size().max(1)) {
+            let (batch_embeddings, batch_usage) = self.embed_batch(batch).await?;
+            if batch_embeddings.len() != batch.len() {
+                return Err(ProviderError::ExecutionError(format!(
+                    "Expected {} embeddings but received {}",
+                    batch.len(),
+                    batch_embeddings.len()
+                )));
+            }
+            embeddings.extend(batch_embeddings);
+            usage.usage = add_usage(&usage.usage, &batch_usage.usage);
+        }
+
+        Ok((embeddings, usage))
+    }
+}
+
+/// Read an embedding vector from a JSON array of numbers
+pub fn vector_from_json(value: &Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|v| v as f32))
+        .collect()
+}
+
+fn add_usage(a: &Usage, b: &Usage) -> Usage {
+    let add = |a: Option<i32>, b: Option<i32>| match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    };
+    Usage::new(
+        add(a.input_tokens, b.input_tokens),
+        add(a.output_tokens, b.output_tokens),
+        add(a.total_tokens, b.total_tokens),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingProvider {
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingProvider {
+        fn embedding_model(&self) -> EmbeddingModel {
+            EmbeddingModel::new("test-embedding")
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+
+        async fn embed_batch(
+            &self,
+            texts: &[String],
+        ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            let embeddings = texts.iter().map(|t| vec![t.len() as f32]).collect();
+            let tokens = texts.len() as i32;
+            Ok((
+                embeddings,
+                ProviderUsage::new(
+                    "test-embedding".to_string(),
+                    Usage::new(Some(tokens), None, Some(tokens)),
+                ),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_in_batches() -> Result<(), ProviderError> {
+        let provider = CountingProvider {
+            requests: AtomicUsize::new(0),
+        };
+        let texts: Vec<String> = ["a", "bb", "ccc", "dddd", "eeeee"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let (embeddings, usage) = provider.embed(&texts).await?;
+        assert_eq!(
+            embeddings,
+            vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]
+        );
+        assert_eq!(provider.requests.load(Ordering::SeqCst), 3);
+        assert_eq!(usage.usage.input_tokens, Some(5));
+        assert_eq!(usage.usage.output_tokens, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_dimensions() {
+        assert_eq!(
+            EmbeddingModel::new("text-embedding-3-small").output_dimensions(),
+            Some(1536)
+        );
+        assert_eq!(
+            EmbeddingModel::new("nomic-embed-text:latest").output_dimensions(),
+            Some(768)
+        );
+        assert_eq!(
+            EmbeddingModel::new("text-embedding-3-large")
+                .with_dimensions(Some(256))
+                .output_dimensions(),
+            Some(256)
+        );
+        assert_eq!(EmbeddingModel::new("custom").output_dimensions(), None);
+    }
+}
@@ -8,6 +8,9 @@ pub enum ProviderError {
     #[error("Context length exceeded: {0}")]
     ContextLengthExceeded(String),
 
+    #[error("Response blocked: {0}")]
+    ContentFiltered(String),
+
     #[error("Rate limit exceeded: {0}")]
     RateLimitExceeded(String),
 
@@ -4,12 +4,17 @@ use super::{
     base::{Provider, ProviderMetadata},
     bedrock::BedrockProvider,
     databricks::DatabricksProvider,
+    embedding::{EmbeddingModel, EmbeddingProvider},
     google::GoogleProvider,
     groq::GroqProvider,
+    model_cache::ModelCache,
     ollama::OllamaProvider,
     openai::OpenAiProvider,
+    openai_compatible::OpenAiCompatibleProvider,
     openrouter::OpenRouterProvider,
+    rate_limit::RateLimitedProvider,
 };
+use crate::config::Config;
 use crate::model::ModelConfig;
 use anyhow::Result;
 
@@ -23,11 +28,18 @@ pub fn providers() -> Vec<ProviderMetadata> {
         GroqProvider::metadata(),
         OllamaProvider::metadata(),
         OpenAiProvider::metadata(),
+        OpenAiCompatibleProvider::metadata(),
         OpenRouterProvider::metadata(),
     ]
 }
 
+/// Create the provider `name` using `model`, limited to the rates configured for it
 pub fn create(name: &str, model: ModelConfig) -> Result<Box<dyn Provider + Send + Sync>> {
+    let provider = create_unlimited(name, model)?;
+    Ok(Box::new(RateLimitedProvider::new(provider)))
+}
+
+fn create_unlimited(name: &str, model: ModelConfig) -> Result<Box<dyn Provider + Send + Sync>> {
     match name {
         "openai" => Ok(Box::new(OpenAiProvider::from_env(model)?)),
         "anthropic" => Ok(Box::new(AnthropicProvider::from_env(model)?)),
@@ -38,6 +50,318 @@ pub fn create(name: &str, model: ModelConfig) -> Result<Box<dyn Provider + Send
         "ollama" => Ok(Box::new(OllamaProvider::from_env(model)?)),
         "openrouter" => Ok(Box::new(OpenRouterProvider::from_env(model)?)),
         "google" => Ok(Box::new(GoogleProvider::from_env(model)?)),
+        "openai_compatible" => Ok(Box::new(OpenAiCompatibleProvider::from_env(model)?)),
+        // Named instances of the OpenAI compatible provider, e.g. openai_compatible:vllm
+        _ if name.starts_with("openai_compatible:") => {
+            let instance = name.trim_start_matches("openai_compatible:");
+            Ok(Box::new(OpenAiCompatibleProvider::from_instance(
+                Some(instance),
+                model,
+            )?))
+        }
         _ => Err(anyhow::anyhow!("Unknown provider: {}", name)),
     }
 }
+
+/// Create the embedding provider `name` using `model`
+///
+/// Only some providers offer embeddings, the provider still needs its usual credentials.
+pub fn create_embedding(name: &str, model: EmbeddingModel) -> Result<Box<dyn EmbeddingProvider>> {
+    // The chat model is not used for embeddings
+    let chat_model = |metadata: ProviderMetadata| ModelConfig::new(metadata.default_model);
+    match name {
+        "openai" => Ok(Box::new(
+            OpenAiProvider::from_env(chat_model(OpenAiProvider::metadata()))?
+                .with_embedding_model(model),
+        )),
+        "azure_openai" => Ok(Box::new(
+            AzureProvider::from_env(chat_model(AzureProvider::metadata()))?
+                .with_embedding_model(model),
+        )),
+        "bedrock" => Ok(Box::new(
+            BedrockProvider::from_env(chat_model(BedrockProvider::metadata()))?
+                .with_embedding_model(model),
+        )),
+        "google" => Ok(Box::new(
+            GoogleProvider::from_env(chat_model(GoogleProvider::metadata()))?
+                .with_embedding_model(model),
+        )),
+        "ollama" => Ok(Box::new(
+            OllamaProvider::from_env(chat_model(OllamaProvider::metadata()))?
+                .with_embedding_model(model),
+        )),
+        _ => Err(anyhow::anyhow!(
+            "Provider {} does not support embeddings, set GOOSE_EMBEDDING_PROVIDER to one of \
+            openai, azure_openai, bedrock, google or ollama",
+            name
+        )),
+    }
+}
+
+/// The default embedding model of a provider
+pub fn default_embedding_model(name: &str) -> Option<&'static str> {
+    match name {
+        "openai" => Some(super::openai::OPEN_AI_DEFAULT_EMBEDDING_MODEL),
+        "azure_openai" => Some(super::azure::AZURE_DEFAULT_EMBEDDING_MODEL),
+        "bedrock" => Some(super::bedrock::BEDROCK_DEFAULT_EMBEDDING_MODEL),
+        "google" => Some(super::google::GOOGLE_DEFAULT_EMBEDDING_MODEL),
+        "ollama" => Some(super::ollama::OLLAMA_DEFAULT_EMBEDDING_MODEL),
+        _ => None,
+    }
+}
+
+/// Create the embedding provider set in the config
+///
+/// - GOOSE_EMBEDDING_PROVIDER: by default the chat provider GOOSE_PROVIDER
+/// - GOOSE_EMBEDDING_MODEL: by default the provider's default embedding model
+/// - GOOSE_EMBEDDING_DIMENSIONS: shorter vectors, for models that support it
+pub fn create_embedding_from_config(config: &Config) -> Result<Box<dyn EmbeddingProvider>> {
+    let name: String = config
+        .get("GOOSE_EMBEDDING_PROVIDER")
+        .or_else(|_| config.get("GOOSE_PROVIDER"))
+        .map_err(|_| anyhow::anyhow!("No embedding provider is configured"))?;
+
+    let model_name = match config.get::<String>("GOOSE_EMBEDDING_MODEL") {
+        Ok(model_name) => model_name,
+        Err(_) => default_embedding_model(&name)
+            .ok_or_else(|| anyhow::anyhow!("Provider {} does not support embeddings", name))?
+            .to_string(),
+    };
+    let model = EmbeddingModel::new(model_name)
+        .with_dimensions(config.get("GOOSE_EMBEDDING_DIMENSIONS").ok());
+
+    create_embedding(&name, model)
+}
+
+/// The models a provider supports
+///
+/// Uses the cached list fetched from the provider's API, falling back to the static
+/// `known_models` of its metadata when the provider can't list models or is unreachable.
+pub async fn supported_models(name: &str, provider: &dyn Provider) -> Vec<String> {
+    let fetched = match ModelCache::from_env() {
+        Ok(cache) => cache.supported_models(name, provider).await,
+        Err(e) => {
+            tracing::warn!("Model cache is unavailable: {}", e);
+            provider.fetch_supported_models().await.ok().flatten()
+        }
+    };
+
+    fetched.unwrap_or_else(|| {
+        // Named instances such as openai_compatible:vllm share the metadata of their provider
+        let base_name = name.split(':').next().unwrap_or(name);
+        providers()
+            .into_iter()
+            .find(|metadata| metadata.name == base_name)
+            .map(|metadata| metadata.known_models)
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::errors::ProviderError;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    /// A provider that lists its models, and how it is pointed at the mock server
+    struct ModelsCase {
+        name: &'static str,
+        env: &'static [(&'static str, &'static str)],
+        host_key: &'static str,
+        path: &'static str,
+        authorized: fn(&Request) -> bool,
+        body: Value,
+        expected: &'static [&'static str],
+    }
+
+    fn has_header(request: &Request, name: &str, value: &str) -> bool {
+        request
+            .headers
+            .get(name)
+            .is_some_and(|header| header == value)
+    }
+
+    fn models_cases() -> Vec<ModelsCase> {
+        vec![
+            ModelsCase {
+                name: "anthropic",
+                env: &[("ANTHROPIC_API_KEY", "test-key")],
+                host_key: "ANTHROPIC_HOST",
+                path: "/v1/models",
+                authorized: |request| has_header(request, "x-api-key", "test-key"),
+                body: json!({
+                    "data": [
+                        {"type": "model", "id": "claude-3-7-sonnet-20250219"},
+                        {"type": "model", "id": "claude-3-5-haiku-20241022"}
+                    ],
+                    "has_more": false
+                }),
+                expected: &["claude-3-5-haiku-20241022", "claude-3-7-sonnet-20250219"],
+            },
+            ModelsCase {
+                name: "databricks",
+                env: &[("DATABRICKS_TOKEN", "test-token")],
+                host_key: "DATABRICKS_HOST",
+                path: "/api/2.0/serving-endpoints",
+                authorized: |request| has_header(request, "Authorization", "Bearer test-token"),
+                body: json!({
+                    "endpoints": [
+                        {"name": "databricks-meta-llama-3-3-70b-instruct", "task": "llm/v1/chat"},
+                        {"name": "databricks-gte-large-en", "task": "llm/v1/embeddings"},
+                        {"name": "custom-model"}
+                    ]
+                }),
+                expected: &["custom-model", "databricks-meta-llama-3-3-70b-instruct"],
+            },
+            ModelsCase {
+                name: "google",
+                env: &[("GOOGLE_API_KEY", "test-key")],
+                host_key: "GOOGLE_HOST",
+                path: "/v1beta/models",
+                authorized: |request| {
+                    request
+                        .url
+                        .query_pairs()
+                        .any(|(key, value)| key == "key" && value == "test-key")
+                },
+                body: json!({
+                    "models": [
+                        {
+                            "name": "models/gemini-2.0-flash",
+                            "supportedGenerationMethods": ["generateContent", "countTokens"]
+                        },
+                        {
+                            "name": "models/text-embedding-004",
+                            "supportedGenerationMethods": ["embedContent"]
+                        }
+                    ]
+                }),
+                expected: &["gemini-2.0-flash"],
+            },
+            ModelsCase {
+                name: "groq",
+                env: &[("GROQ_API_KEY", "test-key")],
+                host_key: "GROQ_HOST",
+                path: "/openai/v1/models",
+                authorized: |request| has_header(request, "Authorization", "Bearer test-key"),
+                body: json!({
+                    "object": "list",
+                    "data": [{"id": "llama-3.3-70b-versatile"}, {"id": "whisper-large-v3"}]
+                }),
+                expected: &["llama-3.3-70b-versatile"],
+            },
+            ModelsCase {
+                name: "openai",
+                env: &[("OPENAI_API_KEY", "test-key")],
+                host_key: "OPENAI_HOST",
+                path: "/v1/models",
+                authorized: |request| has_header(request, "Authorization", "Bearer test-key"),
+                body: json!({
+                    "object": "list",
+                    "data": [
+                        {"id": "gpt-4o"},
+                        {"id": "gpt-4o-realtime-preview"},
+                        {"id": "o3-mini"},
+                        {"id": "text-embedding-3-small"}
+                    ]
+                }),
+                expected: &["gpt-4o", "o3-mini"],
+            },
+            ModelsCase {
+                name: "openrouter",
+                env: &[("OPENROUTER_API_KEY", "test-key")],
+                host_key: "OPENROUTER_HOST",
+                path: "/api/v1/models",
+                authorized: |request| has_header(request, "Authorization", "Bearer test-key"),
+                body: json!({
+                    "data": [
+                        {"id": "openai/gpt-4o", "supported_parameters": ["tools", "temperature"]},
+                        {"id": "some/base-model", "supported_parameters": ["temperature"]},
+                        {"id": "anthropic/claude-3.5-sonnet"}
+                    ]
+                }),
+                expected: &["anthropic/claude-3.5-sonnet", "openai/gpt-4o"],
+            },
+        ]
+    }
+
+    /// Create the provider of `case`, with `server` answering its model list request
+    async fn models_provider(
+        case: &ModelsCase,
+        server: &MockServer,
+        response: ResponseTemplate,
+    ) -> Box<dyn Provider + Send + Sync> {
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path(case.path))
+            .and(case.authorized)
+            .respond_with(response)
+            .mount(server)
+            .await;
+
+        std::env::set_var(case.host_key, server.uri());
+        for (key, value) in case.env {
+            std::env::set_var(key, value);
+        }
+        let metadata = providers()
+            .into_iter()
+            .find(|metadata| metadata.name == case.name)
+            .unwrap();
+        let provider = create(case.name, ModelConfig::new(metadata.default_model)).unwrap();
+        std::env::remove_var(case.host_key);
+        for (key, _) in case.env {
+            std::env::remove_var(key);
+        }
+        provider
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_fetch_supported_models() {
+        let server = MockServer::start().await;
+        for case in models_cases() {
+            let provider = models_provider(
+                &case,
+                &server,
+                ResponseTemplate::new(200).set_body_json(&case.body),
+            )
+            .await;
+            let models = provider.fetch_supported_models().await.unwrap();
+            let expected = case.expected.iter().map(|m| m.to_string()).collect();
+            assert_eq!(models, Some(expected), "{}", case.name);
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_fetch_supported_models_errors() {
+        let server = MockServer::start().await;
+        for case in models_cases() {
+            let provider = models_provider(
+                &case,
+                &server,
+                ResponseTemplate::new(500).set_body_json(json!({"error": "down"})),
+            )
+            .await;
+            let result = provider.fetch_supported_models().await;
+            assert!(
+                matches!(result, Err(ProviderError::ServerError(_))),
+                "{}: {:?}",
+                case.name,
+                result
+            );
+
+            for response in [
+                ResponseTemplate::new(200).set_body_string("not json"),
+                ResponseTemplate::new(200).set_body_json(json!({"object": "list"})),
+            ] {
+                let provider = models_provider(&case, &server, response).await;
+                let result = provider.fetch_supported_models().await;
+                assert!(result.is_err(), "{}: {:?}", case.name, result);
+            }
+        }
+    }
+}
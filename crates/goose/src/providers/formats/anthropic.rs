@@ -1,9 +1,13 @@
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
-use crate::providers::base::Usage;
+use crate::providers::base::{ToolChoice, Usage};
 use crate::providers::errors::ProviderError;
+use crate::providers::utils::{
+    audio_to_text, document_to_text, is_text_document, merge_extra_body,
+};
 use anyhow::{anyhow, Result};
-use mcp_core::content::Content;
+use base64::Engine;
+use mcp_core::content::{Content, DocumentContent};
 use mcp_core::role::Role;
 use mcp_core::tool::{Tool, ToolCall};
 use serde_json::{json, Value};
@@ -45,6 +49,8 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                             .iter()
                             .filter_map(|c| match c {
                                 Content::Text(t) => Some(t.text.clone()),
+                                Content::Document(d) => Some(document_to_text(d)),
+                                Content::Audio(a) => Some(audio_to_text(a)),
                                 _ => None,
                             })
                             .collect::<Vec<_>>()
@@ -58,6 +64,16 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                     }
                 }
                 MessageContent::Image(_) => continue, // Anthropic doesn't support image content yet
+                MessageContent::Document(document) => {
+                    content.push(format_document(document));
+                }
+                MessageContent::Audio(audio) => {
+                    // Anthropic has no audio input, so let the model know something was dropped
+                    content.push(json!({
+                        "type": "text",
+                        "text": audio_to_text(audio)
+                    }));
+                }
             }
         }
 
@@ -108,6 +124,49 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
     anthropic_messages
 }
 
+/// Convert a document to an Anthropic document block
+///
+/// PDFs are sent as base64 sources and plain text files as text sources, other
+/// document types fall back to a text description.
+fn format_document(document: &DocumentContent) -> Value {
+    let text = if is_text_document(&document.mime_type) {
+        base64::prelude::BASE64_STANDARD
+            .decode(&document.data)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+    } else {
+        None
+    };
+
+    let source = match (document.mime_type.as_str(), text) {
+        ("application/pdf", _) => json!({
+            "type": "base64",
+            "media_type": document.mime_type,
+            "data": document.data,
+        }),
+        (_, Some(text)) => json!({
+            "type": "text",
+            "media_type": "text/plain",
+            "data": text,
+        }),
+        _ => {
+            return json!({
+                "type": "text",
+                "text": document_to_text(document)
+            })
+        }
+    };
+
+    let mut block = json!({
+        "type": "document",
+        "source": source,
+    });
+    if let Some(name) = &document.name {
+        block["title"] = json!(name);
+    }
+    block
+}
+
 /// Convert internal Tool format to Anthropic's API tool specification
 pub fn format_tools(tools: &[Tool]) -> Vec<Value> {
     let mut unique_tools = HashSet::new();
@@ -260,6 +319,12 @@ pub fn create_request(
             .as_object_mut()
             .unwrap()
             .insert("tools".to_string(), json!(tool_specs));
+        if !model_config.capabilities.parallel_tool_calls {
+            payload.as_object_mut().unwrap().insert(
+                "tool_choice".to_string(),
+                json!({"type": "auto", "disable_parallel_tool_use": true}),
+            );
+        }
     }
 
     // Add temperature if specified
@@ -270,12 +335,61 @@ pub fn create_request(
             .insert("temperature".to_string(), json!(temp));
     }
 
+    if model_config.seed.is_some()
+        || model_config.frequency_penalty.is_some()
+        || model_config.presence_penalty.is_some()
+    {
+        return Err(anyhow!(
+            "Anthropic's API does not support seed, frequency_penalty or presence_penalty"
+        ));
+    }
+    if let Some(top_p) = model_config.top_p {
+        payload["top_p"] = json!(top_p);
+    }
+    if let Some(top_k) = model_config.top_k {
+        payload["top_k"] = json!(top_k);
+    }
+    if !model_config.stop_sequences.is_empty() {
+        payload["stop_sequences"] = json!(model_config.stop_sequences);
+    }
+    merge_extra_body(&mut payload, &model_config.extra_body);
+
     Ok(payload)
 }
 
+/// Add the tool choice to a request made with [`create_request`]
+pub fn add_tool_choice(
+    payload: &mut Value,
+    tool_choice: &ToolChoice,
+    tools: &[Tool],
+) -> Result<()> {
+    tool_choice.validate(tools)?;
+    if payload.get("tools").is_none() {
+        return Ok(());
+    }
+    let mut choice = match tool_choice {
+        ToolChoice::Auto => return Ok(()),
+        ToolChoice::None => json!({"type": "none"}),
+        ToolChoice::Required => json!({"type": "any"}),
+        ToolChoice::Tool(name) => json!({"type": "tool", "name": name}),
+    };
+    // Keep parallel tool calls disabled if create_request did so
+    if let Some(disabled) = payload["tool_choice"].get("disable_parallel_tool_use") {
+        if !matches!(tool_choice, ToolChoice::None) {
+            choice["disable_parallel_tool_use"] = disabled.clone();
+        }
+    }
+    payload
+        .as_object_mut()
+        .unwrap()
+        .insert("tool_choice".to_string(), choice);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model_registry::ModelCapabilities;
     use serde_json::json;
 
     #[test]
@@ -378,6 +492,35 @@ mod tests {
         assert_eq!(spec[2]["content"][0]["text"], "How are you?");
     }
 
+    #[test]
+    fn test_document_and_audio_to_anthropic_spec() {
+        let text_data = base64::prelude::BASE64_STANDARD.encode("plain text");
+        let messages = vec![Message::user()
+            .with_document("JVBERi0=", "application/pdf", Some("spec.pdf".to_string()))
+            .with_document(text_data, "text/plain", None)
+            .with_document("AAAA", "application/zip", None)
+            .with_audio("AAAA", "audio/wav")];
+
+        let spec = format_messages(&messages);
+        let content = spec[0]["content"].as_array().unwrap();
+
+        assert_eq!(content.len(), 4);
+        assert_eq!(content[0]["type"], "document");
+        assert_eq!(content[0]["source"]["type"], "base64");
+        assert_eq!(content[0]["source"]["media_type"], "application/pdf");
+        assert_eq!(content[0]["title"], "spec.pdf");
+        assert_eq!(content[1]["type"], "document");
+        assert_eq!(content[1]["source"]["type"], "text");
+        assert_eq!(content[1]["source"]["data"], "plain text");
+        assert_eq!(content[2]["type"], "text");
+        assert!(content[2]["text"]
+            .as_str()
+            .unwrap()
+            .contains("application/zip"));
+        assert_eq!(content[3]["type"], "text");
+        assert!(content[3]["text"].as_str().unwrap().contains("audio/wav"));
+    }
+
     #[test]
     fn test_tools_to_anthropic_spec() {
         let tools = vec![
@@ -421,6 +564,70 @@ mod tests {
         assert!(spec[1].get("cache_control").is_some());
     }
 
+    #[test]
+    fn test_add_tool_choice() -> Result<()> {
+        let model_config = ModelConfig::new("claude-3-5-sonnet-latest".to_string());
+        let messages = [Message::user().with_text("Hello")];
+        let tools = [Tool::new(
+            "calculator",
+            "Calculate",
+            json!({"type": "object"}),
+        )];
+        let request = |choice: ToolChoice| -> Result<Value> {
+            let mut payload = create_request(&model_config, "system", &messages, &tools)?;
+            add_tool_choice(&mut payload, &choice, &tools)?;
+            Ok(payload)
+        };
+
+        assert!(request(ToolChoice::Auto)?.get("tool_choice").is_none());
+        assert_eq!(
+            request(ToolChoice::None)?["tool_choice"],
+            json!({"type": "none"})
+        );
+        assert_eq!(
+            request(ToolChoice::Required)?["tool_choice"],
+            json!({"type": "any"})
+        );
+        assert_eq!(
+            request(ToolChoice::Tool("calculator".to_string()))?["tool_choice"],
+            json!({"type": "tool", "name": "calculator"})
+        );
+        assert!(request(ToolChoice::Tool("weather".to_string())).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_disable_parallel_tool_calls() -> Result<()> {
+        let model_config = ModelConfig::new("claude-3-5-sonnet-latest".to_string())
+            .with_capabilities(ModelCapabilities {
+                parallel_tool_calls: false,
+                ..ModelCapabilities::all()
+            });
+        let messages = [Message::user().with_text("Hello")];
+        let tools = [Tool::new(
+            "calculator",
+            "Calculate",
+            json!({"type": "object"}),
+        )];
+
+        let payload = create_request(&model_config, "system", &messages, &[])?;
+        assert!(payload.get("tool_choice").is_none());
+
+        let mut payload = create_request(&model_config, "system", &messages, &tools)?;
+        assert_eq!(
+            payload["tool_choice"],
+            json!({"type": "auto", "disable_parallel_tool_use": true})
+        );
+        add_tool_choice(&mut payload, &ToolChoice::Required, &tools)?;
+        assert_eq!(
+            payload["tool_choice"],
+            json!({"type": "any", "disable_parallel_tool_use": true})
+        );
+        add_tool_choice(&mut payload, &ToolChoice::None, &tools)?;
+        assert_eq!(payload["tool_choice"], json!({"type": "none"}));
+        Ok(())
+    }
+
     #[test]
     fn test_system_to_anthropic_spec() {
         let system = "You are a helpful assistant.";
@@ -433,4 +640,22 @@ mod tests {
         assert_eq!(spec_array[0]["text"], system);
         assert!(spec_array[0].get("cache_control").is_some());
     }
+
+    #[test]
+    fn test_create_request_sampling() -> Result<()> {
+        let messages = vec![Message::user().with_text("Hello")];
+        let model_config = ModelConfig::new("claude-3-5-sonnet-latest".to_string())
+            .with_top_p(Some(0.5))
+            .with_top_k(Some(40))
+            .with_stop_sequences(vec!["END".to_string()]);
+
+        let payload = create_request(&model_config, "system", &messages, &[])?;
+        assert_eq!(payload["top_p"], json!(0.5f32));
+        assert_eq!(payload["top_k"], 40);
+        assert_eq!(payload["stop_sequences"], json!(["END"]));
+
+        let model_config = model_config.with_seed(Some(7));
+        assert!(create_request(&model_config, "system", &messages, &[]).is_err());
+        Ok(())
+    }
 }
@@ -4,12 +4,19 @@ use std::path::Path;
 use anyhow::{anyhow, bail, Result};
 use aws_sdk_bedrockruntime::types as bedrock;
 use aws_smithy_types::{Document, Number};
+use base64::Engine;
 use chrono::Utc;
-use mcp_core::{Content, ResourceContents, Role, Tool, ToolCall, ToolError, ToolResult};
+use mcp_core::{
+    Content, DocumentContent, ImageContent, ResourceContents, Role, Tool, ToolCall, ToolError,
+    ToolResult,
+};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 
-use super::super::base::Usage;
+use super::super::base::{ToolChoice, Usage};
 use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::providers::embedding::{vector_from_json, EmbeddingModel};
 
 pub fn to_bedrock_message(message: &Message) -> Result<bedrock::Message> {
     bedrock::Message::builder()
@@ -28,8 +35,14 @@ pub fn to_bedrock_message(message: &Message) -> Result<bedrock::Message> {
 pub fn to_bedrock_message_content(content: &MessageContent) -> Result<bedrock::ContentBlock> {
     Ok(match content {
         MessageContent::Text(text) => bedrock::ContentBlock::Text(text.text.to_string()),
-        MessageContent::Image(_) => {
-            bail!("Image content is not supported by Bedrock provider yet")
+        MessageContent::Image(image) => {
+            bedrock::ContentBlock::Image(to_bedrock_image_block(image)?)
+        }
+        MessageContent::Document(document) => {
+            bedrock::ContentBlock::Document(to_bedrock_document_block(document)?)
+        }
+        MessageContent::Audio(_) => {
+            bail!("Audio content is not supported by Bedrock provider")
         }
         MessageContent::ToolRequest(tool_req) => {
             let tool_use_id = tool_req.id.to_string();
@@ -77,7 +90,13 @@ pub fn to_bedrock_tool_result_content_block(
 ) -> Result<bedrock::ToolResultContentBlock> {
     Ok(match content {
         Content::Text(text) => bedrock::ToolResultContentBlock::Text(text.text.to_string()),
-        Content::Image(_) => bail!("Image content is not supported by Bedrock provider yet"),
+        Content::Image(image) => {
+            bedrock::ToolResultContentBlock::Image(to_bedrock_image_block(image)?)
+        }
+        Content::Document(document) => {
+            bedrock::ToolResultContentBlock::Document(to_bedrock_document_block(document)?)
+        }
+        Content::Audio(_) => bail!("Audio content is not supported by Bedrock provider"),
         Content::Resource(resource) => bedrock::ToolResultContentBlock::Document(
             to_bedrock_document(tool_use_id, &resource.resource)?,
         ),
@@ -91,12 +110,86 @@ pub fn to_bedrock_role(role: &Role) -> bedrock::ConversationRole {
     }
 }
 
-pub fn to_bedrock_tool_config(tools: &[Tool]) -> Result<bedrock::ToolConfiguration> {
-    Ok(bedrock::ToolConfiguration::builder()
-        .set_tools(Some(
-            tools.iter().map(to_bedrock_tool).collect::<Result<_>>()?,
-        ))
-        .build()?)
+/// The tool config of a request, or `None` if no tools are offered
+///
+/// Bedrock has no way to forbid tool use, so the tools are left out instead. It only accepts
+/// that while the conversation has no tool calls.
+pub fn to_bedrock_tool_config(
+    tools: &[Tool],
+    tool_choice: &ToolChoice,
+    messages: &[Message],
+) -> Result<Option<bedrock::ToolConfiguration>> {
+    tool_choice.validate(tools)?;
+    if tools.is_empty() {
+        return Ok(None);
+    }
+
+    let choice = match tool_choice {
+        ToolChoice::Auto => None,
+        ToolChoice::None => {
+            if messages
+                .iter()
+                .any(|message| message.is_tool_call() || message.is_tool_response())
+            {
+                bail!("Bedrock can't forbid tool use in a conversation with tool calls");
+            }
+            return Ok(None);
+        }
+        ToolChoice::Required => Some(bedrock::ToolChoice::Any(
+            bedrock::AnyToolChoice::builder().build(),
+        )),
+        ToolChoice::Tool(name) => Some(bedrock::ToolChoice::Tool(
+            bedrock::SpecificToolChoice::builder()
+                .name(name.to_string())
+                .build()?,
+        )),
+    };
+
+    Ok(Some(
+        bedrock::ToolConfiguration::builder()
+            .set_tools(Some(
+                tools.iter().map(to_bedrock_tool).collect::<Result<_>>()?,
+            ))
+            .set_tool_choice(choice)
+            .build()?,
+    ))
+}
+
+/// The sampling parameters of a request, or `None` if none are set
+///
+/// The Converse API only has common parameters, others are model specific and can be sent
+/// with GOOSE_EXTRA_BODY, e.g. `top_k` for Anthropic models.
+pub fn to_bedrock_inference_config(
+    model_config: &ModelConfig,
+) -> Result<Option<bedrock::InferenceConfiguration>> {
+    if model_config.top_k.is_some()
+        || model_config.seed.is_some()
+        || model_config.frequency_penalty.is_some()
+        || model_config.presence_penalty.is_some()
+    {
+        bail!(
+            "Bedrock's Converse API does not support top_k, seed, frequency_penalty or presence_penalty, models that accept them can be sent them with GOOSE_EXTRA_BODY"
+        );
+    }
+    if model_config.temperature.is_none()
+        && model_config.max_tokens.is_none()
+        && model_config.top_p.is_none()
+        && model_config.stop_sequences.is_empty()
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        bedrock::InferenceConfiguration::builder()
+            .set_temperature(model_config.temperature)
+            .set_max_tokens(model_config.max_tokens)
+            .set_top_p(model_config.top_p)
+            .set_stop_sequences(
+                (!model_config.stop_sequences.is_empty())
+                    .then(|| model_config.stop_sequences.clone()),
+            )
+            .build(),
+    ))
 }
 
 pub fn to_bedrock_tool(tool: &Tool) -> Result<bedrock::Tool> {
@@ -173,6 +266,80 @@ fn to_bedrock_document(
         .map_err(|err| anyhow!("Failed to construct Bedrock document: {}", err))
 }
 
+fn to_bedrock_image_block(image: &ImageContent) -> Result<bedrock::ImageBlock> {
+    let format = match image.mime_type.as_str() {
+        "image/png" => bedrock::ImageFormat::Png,
+        "image/jpeg" | "image/jpg" => bedrock::ImageFormat::Jpeg,
+        "image/gif" => bedrock::ImageFormat::Gif,
+        "image/webp" => bedrock::ImageFormat::Webp,
+        other => bail!("Image type {} is not supported by Bedrock provider", other),
+    };
+
+    let bytes = base64::prelude::BASE64_STANDARD
+        .decode(&image.data)
+        .map_err(|err| anyhow!("Failed to decode image data: {}", err))?;
+
+    bedrock::ImageBlock::builder()
+        .format(format)
+        .source(bedrock::ImageSource::Bytes(bytes.into()))
+        .build()
+        .map_err(|err| anyhow!("Failed to construct Bedrock image: {}", err))
+}
+
+fn to_bedrock_document_block(document: &DocumentContent) -> Result<bedrock::DocumentBlock> {
+    let format = match document.mime_type.as_str() {
+        "application/pdf" => bedrock::DocumentFormat::Pdf,
+        "text/csv" => bedrock::DocumentFormat::Csv,
+        "text/html" => bedrock::DocumentFormat::Html,
+        "text/markdown" => bedrock::DocumentFormat::Md,
+        "text/plain" => bedrock::DocumentFormat::Txt,
+        "application/msword" => bedrock::DocumentFormat::Doc,
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+            bedrock::DocumentFormat::Docx
+        }
+        "application/vnd.ms-excel" => bedrock::DocumentFormat::Xls,
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => {
+            bedrock::DocumentFormat::Xlsx
+        }
+        other => bail!(
+            "Document type {} is not supported by Bedrock provider",
+            other
+        ),
+    };
+
+    let bytes = base64::prelude::BASE64_STANDARD
+        .decode(&document.data)
+        .map_err(|err| anyhow!("Failed to decode document data: {}", err))?;
+
+    // Bedrock only accepts alphanumerics, whitespace, hyphens, parentheses and square brackets in
+    // document names, and names must be unique within a conversation, so we suffix a content hash.
+    let stem = document
+        .name
+        .as_deref()
+        .and_then(|name| Path::new(name).file_stem())
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("document");
+    let stem: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c.is_whitespace() || "-()[]".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    let name = format!("{}-{}", stem, &hash[..8]);
+
+    bedrock::DocumentBlock::builder()
+        .format(format)
+        .name(name)
+        .source(bedrock::DocumentSource::Bytes(bytes.into()))
+        .build()
+        .map_err(|err| anyhow!("Failed to construct Bedrock document: {}", err))
+}
+
 pub fn from_bedrock_message(message: &bedrock::Message) -> Result<Message> {
     let role = from_bedrock_role(message.role())?;
     let content = message
@@ -192,6 +359,10 @@ pub fn from_bedrock_message(message: &bedrock::Message) -> Result<Message> {
 pub fn from_bedrock_content_block(block: &bedrock::ContentBlock) -> Result<MessageContent> {
     Ok(match block {
         bedrock::ContentBlock::Text(text) => MessageContent::text(text),
+        bedrock::ContentBlock::Image(image) => {
+            let (data, mime_type) = from_bedrock_image_block(image)?;
+            MessageContent::image(data, mime_type)
+        }
         bedrock::ContentBlock::ToolUse(tool_use) => MessageContent::tool_request(
             tool_use.tool_use_id.to_string(),
             Ok(ToolCall::new(
@@ -222,6 +393,11 @@ pub fn from_bedrock_tool_result_content_block(
 ) -> ToolResult<Content> {
     Ok(match content {
         bedrock::ToolResultContentBlock::Text(text) => Content::text(text.to_string()),
+        bedrock::ToolResultContentBlock::Image(image) => {
+            let (data, mime_type) = from_bedrock_image_block(image)
+                .map_err(|err| ToolError::ExecutionError(err.to_string()))?;
+            Content::image(data, mime_type)
+        }
         _ => {
             return Err(ToolError::ExecutionError(
                 "Unsupported tool result from Bedrock".to_string(),
@@ -230,6 +406,26 @@ pub fn from_bedrock_tool_result_content_block(
     })
 }
 
+/// The base64 data and mime type of an image from Bedrock
+fn from_bedrock_image_block(image: &bedrock::ImageBlock) -> Result<(String, String)> {
+    let mime_type = match image.format() {
+        bedrock::ImageFormat::Png => "image/png",
+        bedrock::ImageFormat::Jpeg => "image/jpeg",
+        bedrock::ImageFormat::Gif => "image/gif",
+        bedrock::ImageFormat::Webp => "image/webp",
+        other => bail!("Unsupported image format {} from Bedrock", other.as_str()),
+    };
+    let bytes = match image.source() {
+        Some(bedrock::ImageSource::Bytes(bytes)) => bytes.as_ref(),
+        _ => bail!("Unsupported image source from Bedrock"),
+    };
+
+    Ok((
+        base64::prelude::BASE64_STANDARD.encode(bytes),
+        mime_type.to_string(),
+    ))
+}
+
 pub fn from_bedrock_role(role: &bedrock::ConversationRole) -> Result<Role> {
     Ok(match role {
         bedrock::ConversationRole::User => Role::User,
@@ -268,3 +464,245 @@ pub fn from_bedrock_json(document: &Document) -> Result<Value> {
         ),
     })
 }
+
+/// The embedding models on Bedrock, which each have their own request format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbeddingFamily {
+    /// Amazon Titan, which embeds a single text per request
+    Titan,
+    /// Cohere Embed, which takes up to 96 texts per request
+    Cohere,
+}
+
+impl EmbeddingFamily {
+    pub fn of(model: &EmbeddingModel) -> Result<Self> {
+        let name = &model.model_name;
+        if name.contains("titan-embed") {
+            Ok(Self::Titan)
+        } else if name.contains("cohere.embed") {
+            Ok(Self::Cohere)
+        } else {
+            bail!(
+                "Embedding model {} is not supported by Bedrock provider, use a Titan or Cohere embedding model",
+                name
+            )
+        }
+    }
+
+    pub fn max_batch_size(&self) -> usize {
+        match self {
+            Self::Titan => 1,
+            Self::Cohere => 96,
+        }
+    }
+}
+
+/// Create the body of an InvokeModel request embedding the texts
+pub fn to_bedrock_embedding_request(model: &EmbeddingModel, texts: &[String]) -> Result<Value> {
+    Ok(match EmbeddingFamily::of(model)? {
+        EmbeddingFamily::Titan => {
+            let [text] = texts else {
+                bail!("Titan embeds one text per request")
+            };
+            let mut body = serde_json::json!({ "inputText": text });
+            if let Some(dimensions) = model.dimensions {
+                body["dimensions"] = serde_json::json!(dimensions);
+            }
+            body
+        }
+        EmbeddingFamily::Cohere => serde_json::json!({
+            "texts": texts,
+            "input_type": "search_document",
+        }),
+    })
+}
+
+/// Read the vectors and usage from the body of an InvokeModel embedding response
+pub fn from_bedrock_embedding_response(
+    model: &EmbeddingModel,
+    response: &Value,
+) -> Result<(Vec<Vec<f32>>, Usage)> {
+    let invalid = || anyhow!("Invalid embedding in Bedrock response");
+    Ok(match EmbeddingFamily::of(model)? {
+        EmbeddingFamily::Titan => {
+            let embedding = vector_from_json(&response["embedding"]).ok_or_else(invalid)?;
+            let tokens = response["inputTextTokenCount"].as_i64().map(|t| t as i32);
+            (vec![embedding], Usage::new(tokens, None, tokens))
+        }
+        EmbeddingFamily::Cohere => {
+            let embeddings = response["embeddings"]
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|embedding| vector_from_json(embedding).ok_or_else(invalid))
+                .collect::<Result<_>>()?;
+            (embeddings, Usage::default())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // A 1x1 transparent PNG
+    const PNG_DATA: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    #[test]
+    fn test_image_round_trip() -> Result<()> {
+        let message = Message::user()
+            .with_text("What is in this image?")
+            .with_image(PNG_DATA, "image/png");
+
+        let converted = to_bedrock_message(&message)?;
+        match &converted.content()[1] {
+            bedrock::ContentBlock::Image(image) => {
+                assert_eq!(image.format(), &bedrock::ImageFormat::Png)
+            }
+            other => panic!("Expected an image block, got {:?}", other),
+        }
+
+        let round_tripped = from_bedrock_message(&converted)?;
+        assert_eq!(round_tripped.role, Role::User);
+        assert_eq!(round_tripped.content, message.content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tool_result_image_round_trip() -> Result<()> {
+        let message = Message::user().with_tool_response(
+            "toolu_1",
+            Ok(vec![
+                Content::text("Screenshot taken"),
+                Content::image(PNG_DATA, "image/webp"),
+            ]),
+        );
+
+        let converted = to_bedrock_message(&message)?;
+        let round_tripped = from_bedrock_message(&converted)?;
+        assert_eq!(round_tripped.content, message.content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tool_request_round_trip() -> Result<()> {
+        let message = Message::assistant()
+            .with_text("Listing files")
+            .with_tool_request(
+                "toolu_1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "ls", "timeout": 30}),
+                )),
+            );
+
+        let round_tripped = from_bedrock_message(&to_bedrock_message(&message)?)?;
+        assert_eq!(round_tripped.role, Role::Assistant);
+        assert_eq!(round_tripped.content, message.content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tool_config() -> Result<()> {
+        let tools = [Tool::new(
+            "developer__shell",
+            "Run a command",
+            json!({"type": "object"}),
+        )];
+        let question = [Message::user().with_text("What is in this folder?")];
+
+        let config = to_bedrock_tool_config(&tools, &ToolChoice::Auto, &question)?.unwrap();
+        assert_eq!(config.tools().len(), 1);
+        assert!(config.tool_choice().is_none());
+
+        let config = to_bedrock_tool_config(&tools, &ToolChoice::Required, &question)?.unwrap();
+        assert!(matches!(
+            config.tool_choice(),
+            Some(bedrock::ToolChoice::Any(_))
+        ));
+
+        let config = to_bedrock_tool_config(
+            &tools,
+            &ToolChoice::Tool("developer__shell".to_string()),
+            &question,
+        )?
+        .unwrap();
+        match config.tool_choice() {
+            Some(bedrock::ToolChoice::Tool(choice)) => {
+                assert_eq!(choice.name(), "developer__shell")
+            }
+            other => panic!("Unexpected tool choice {other:?}"),
+        }
+
+        // Tools are left out to forbid tool use, which fails once tools were called
+        assert!(to_bedrock_tool_config(&tools, &ToolChoice::None, &question)?.is_none());
+        let conversation = [
+            question[0].clone(),
+            Message::assistant().with_tool_request(
+                "toolu_1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ),
+        ];
+        assert!(to_bedrock_tool_config(&tools, &ToolChoice::None, &conversation).is_err());
+
+        assert!(to_bedrock_tool_config(&[], &ToolChoice::Auto, &question)?.is_none());
+        assert!(to_bedrock_tool_config(&[], &ToolChoice::Required, &question).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_image_type() {
+        let message = Message::user().with_image(PNG_DATA, "image/tiff");
+        let err = to_bedrock_message(&message).unwrap_err();
+        assert!(err.to_string().contains("image/tiff"));
+    }
+
+    #[test]
+    fn test_embedding_requests() -> Result<()> {
+        let titan = EmbeddingModel::new("amazon.titan-embed-text-v2:0").with_dimensions(Some(512));
+        assert_eq!(EmbeddingFamily::of(&titan)?.max_batch_size(), 1);
+        assert_eq!(
+            to_bedrock_embedding_request(&titan, &["hello".to_string()])?,
+            json!({"inputText": "hello", "dimensions": 512})
+        );
+        let (embeddings, usage) = from_bedrock_embedding_response(
+            &titan,
+            &json!({"embedding": [0.5, 0.25], "inputTextTokenCount": 2}),
+        )?;
+        assert_eq!(embeddings, vec![vec![0.5, 0.25]]);
+        assert_eq!(usage.input_tokens, Some(2));
+
+        let cohere = EmbeddingModel::new("cohere.embed-english-v3");
+        let body = to_bedrock_embedding_request(&cohere, &["a".to_string(), "b".to_string()])?;
+        assert_eq!(body["texts"], json!(["a", "b"]));
+        let (embeddings, _) = from_bedrock_embedding_response(
+            &cohere,
+            &json!({"id": "1", "embeddings": [[1.0], [2.0]], "texts": ["a", "b"]}),
+        )?;
+        assert_eq!(embeddings, vec![vec![1.0], vec![2.0]]);
+
+        assert!(EmbeddingFamily::of(&EmbeddingModel::new("anthropic.claude-3")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_inference_config() -> Result<()> {
+        let model_config = ModelConfig::new("anthropic.claude-3-5-sonnet".to_string());
+        assert!(to_bedrock_inference_config(&model_config)?.is_none());
+
+        let model_config = model_config
+            .with_temperature(Some(0.2))
+            .with_top_p(Some(0.5))
+            .with_stop_sequences(vec!["END".to_string()]);
+        let inference_config = to_bedrock_inference_config(&model_config)?.unwrap();
+        assert_eq!(inference_config.temperature(), Some(0.2));
+        assert_eq!(inference_config.top_p(), Some(0.5));
+        assert_eq!(inference_config.max_tokens(), None);
+        assert_eq!(inference_config.stop_sequences(), ["END".to_string()]);
+
+        let model_config = model_config.with_seed(Some(7));
+        assert!(to_bedrock_inference_config(&model_config).is_err());
+        Ok(())
+    }
+}
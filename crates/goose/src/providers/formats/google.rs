@@ -1,15 +1,109 @@
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
-use crate::providers::base::Usage;
+use crate::providers::base::{ToolChoice, Usage};
+use crate::providers::embedding::{vector_from_json, EmbeddingModel};
 use crate::providers::errors::ProviderError;
-use crate::providers::utils::{is_valid_function_name, sanitize_function_name};
+use crate::providers::utils::{
+    audio_to_text, document_to_text, is_valid_function_name, merge_extra_body,
+    sanitize_function_name,
+};
 use anyhow::Result;
-use mcp_core::content::Content;
+use mcp_core::content::{AudioContent, Content, DocumentContent};
 use mcp_core::role::Role;
 use mcp_core::tool::{Tool, ToolCall};
 use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
 use serde_json::{json, Map, Value};
 
+/// The harm categories Gemini applies safety thresholds to
+pub const HARM_CATEGORIES: &[&str] = &[
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+    "HARM_CATEGORY_CIVIC_INTEGRITY",
+];
+
+/// Thresholds at which Gemini blocks content of a harm category
+pub const HARM_BLOCK_THRESHOLDS: &[&str] = &[
+    "BLOCK_NONE",
+    "BLOCK_ONLY_HIGH",
+    "BLOCK_MEDIUM_AND_ABOVE",
+    "BLOCK_LOW_AND_ABOVE",
+    "OFF",
+];
+
+/// Document types Gemini reads from inline data, see
+/// https://ai.google.dev/gemini-api/docs/document-processing
+const DOCUMENT_MIME_TYPES: &[&str] = &[
+    "application/pdf",
+    "application/x-javascript",
+    "text/javascript",
+    "application/x-python",
+    "text/x-python",
+    "text/plain",
+    "text/html",
+    "text/css",
+    "text/md",
+    "text/csv",
+    "text/xml",
+    "text/rtf",
+];
+
+/// Audio types Gemini reads from inline data, see https://ai.google.dev/gemini-api/docs/audio
+const AUDIO_MIME_TYPES: &[&str] = &[
+    "audio/wav",
+    "audio/mp3",
+    "audio/aiff",
+    "audio/aac",
+    "audio/ogg",
+    "audio/flac",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+/// Gemini specific request settings
+///
+/// See https://ai.google.dev/api/generate-content#v1beta.GenerationConfig
+#[derive(Debug, Clone, Default)]
+pub struct GoogleSettings {
+    pub safety_settings: Vec<SafetySetting>,
+    pub response_mime_type: Option<String>,
+    pub candidate_count: Option<u32>,
+}
+
+/// Send a document inline if Gemini supports its type, otherwise as text
+fn document_part(document: &DocumentContent) -> Value {
+    if DOCUMENT_MIME_TYPES.contains(&document.mime_type.as_str()) {
+        json!({
+            "inline_data": {
+                "mime_type": document.mime_type,
+                "data": document.data,
+            }
+        })
+    } else {
+        json!({"text": document_to_text(document)})
+    }
+}
+
+/// Send audio inline if Gemini supports its type, otherwise note that it was dropped
+fn audio_part(audio: &AudioContent) -> Value {
+    if AUDIO_MIME_TYPES.contains(&audio.mime_type.as_str()) {
+        json!({
+            "inline_data": {
+                "mime_type": audio.mime_type,
+                "data": audio.data,
+            }
+        })
+    } else {
+        json!({"text": audio_to_text(audio)})
+    }
+}
+
 /// Convert internal Message format to Google's API message specification
 pub fn format_messages(messages: &[Message]) -> Vec<Value> {
     messages
@@ -28,6 +122,20 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                             parts.push(json!({"text": text.text}));
                         }
                     }
+                    MessageContent::Image(image) => {
+                        parts.push(json!({
+                            "inline_data": {
+                                "mime_type": image.mime_type,
+                                "data": image.data,
+                            }
+                        }));
+                    }
+                    MessageContent::Document(document) => {
+                        parts.push(document_part(document));
+                    }
+                    MessageContent::Audio(audio) => {
+                        parts.push(audio_part(audio));
+                    }
                     MessageContent::ToolRequest(request) => match &request.tool_call {
                         Ok(tool_call) => {
                             let mut function_call_part = Map::new();
@@ -73,6 +181,12 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                                                 }
                                             }));
                                         }
+                                        Content::Document(document) => {
+                                            parts.push(document_part(&document));
+                                        }
+                                        Content::Audio(audio) => {
+                                            parts.push(audio_part(&audio));
+                                        }
                                         _ => {
                                             parts.push(json!({
                                                 "functionResponse": {
@@ -89,8 +203,6 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                             }
                         }
                     }
-
-                    _ => {}
                 }
             }
             json!({"role": role, "parts": parts})
@@ -238,6 +350,73 @@ pub fn response_to_message(response: Value) -> Result<Message> {
     })
 }
 
+/// The error explaining why a response has no usable content, based on Gemini's
+/// `promptFeedback.blockReason` and the `finishReason` of the candidate
+///
+/// A response cut off at the token limit is only an error when nothing was produced,
+/// partial output is returned as is.
+pub fn response_error(response: &Value) -> Option<ProviderError> {
+    let candidate = response
+        .get("candidates")
+        .and_then(|candidates| candidates.get(0));
+
+    let Some(candidate) = candidate else {
+        let reason = response
+            .get("promptFeedback")
+            .and_then(|feedback| feedback.get("blockReason"))
+            .and_then(|reason| reason.as_str())?;
+        return Some(ProviderError::ContentFiltered(format!(
+            "Gemini blocked the prompt ({}). Rephrase the request or adjust GOOGLE_SAFETY_SETTINGS.",
+            reason
+        )));
+    };
+
+    let has_content = candidate
+        .get("content")
+        .and_then(|content| content.get("parts"))
+        .and_then(|parts| parts.as_array())
+        .is_some_and(|parts| !parts.is_empty());
+
+    match candidate.get("finishReason").and_then(|r| r.as_str())? {
+        "SAFETY" | "PROHIBITED_CONTENT" | "BLOCKLIST" | "SPII" => {
+            Some(ProviderError::ContentFiltered(format!(
+                "Gemini stopped the response for safety reasons{}. \
+                Adjust GOOGLE_SAFETY_SETTINGS if this is a false positive.",
+                blocked_categories(candidate)
+            )))
+        }
+        "RECITATION" => Some(ProviderError::ContentFiltered(
+            "Gemini stopped the response because it was reciting training data".to_string(),
+        )),
+        "MAX_TOKENS" if !has_content => Some(ProviderError::ExecutionError(
+            "Gemini reached the output token limit before producing a response. \
+            Increase the max tokens setting of the model."
+                .to_string(),
+        )),
+        "MALFORMED_FUNCTION_CALL" => Some(ProviderError::ExecutionError(
+            "Gemini produced a malformed function call".to_string(),
+        )),
+        _ => None,
+    }
+}
+
+fn blocked_categories(candidate: &Value) -> String {
+    let categories: Vec<&str> = candidate
+        .get("safetyRatings")
+        .and_then(|ratings| ratings.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|rating| rating.get("blocked").and_then(|b| b.as_bool()) == Some(true))
+        .filter_map(|rating| rating.get("category").and_then(|c| c.as_str()))
+        .collect();
+
+    if categories.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", categories.join(", "))
+    }
+}
+
 /// Extract usage information from Google's API response
 pub fn get_usage(data: &Value) -> Result<Usage> {
     if let Some(usage_meta_data) = data.get("usageMetadata") {
@@ -267,6 +446,7 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
 /// Create a complete request payload for Google's API
 pub fn create_request(
     model_config: &ModelConfig,
+    settings: &GoogleSettings,
     system: &str,
     messages: &[Message],
     tools: &[Tool],
@@ -283,6 +463,12 @@ pub fn create_request(
             json!({"functionDeclarations": format_tools(tools)}),
         );
     }
+    if !settings.safety_settings.is_empty() {
+        payload.insert(
+            "safetySettings".to_string(),
+            json!(settings.safety_settings),
+        );
+    }
     let mut generation_config = Map::new();
     if let Some(temp) = model_config.temperature {
         generation_config.insert("temperature".to_string(), json!(temp));
@@ -290,11 +476,97 @@ pub fn create_request(
     if let Some(tokens) = model_config.max_tokens {
         generation_config.insert("maxOutputTokens".to_string(), json!(tokens));
     }
+    if let Some(top_p) = model_config.top_p {
+        generation_config.insert("topP".to_string(), json!(top_p));
+    }
+    if let Some(top_k) = model_config.top_k {
+        generation_config.insert("topK".to_string(), json!(top_k));
+    }
+    if !model_config.stop_sequences.is_empty() {
+        generation_config.insert(
+            "stopSequences".to_string(),
+            json!(model_config.stop_sequences),
+        );
+    }
+    if let Some(seed) = model_config.seed {
+        generation_config.insert("seed".to_string(), json!(seed));
+    }
+    if let Some(penalty) = model_config.frequency_penalty {
+        generation_config.insert("frequencyPenalty".to_string(), json!(penalty));
+    }
+    if let Some(penalty) = model_config.presence_penalty {
+        generation_config.insert("presencePenalty".to_string(), json!(penalty));
+    }
+    if let Some(mime_type) = &settings.response_mime_type {
+        generation_config.insert("responseMimeType".to_string(), json!(mime_type));
+    }
+    if let Some(count) = settings.candidate_count {
+        generation_config.insert("candidateCount".to_string(), json!(count));
+    }
     if !generation_config.is_empty() {
         payload.insert("generationConfig".to_string(), json!(generation_config));
     }
 
-    Ok(Value::Object(payload))
+    let mut payload = Value::Object(payload);
+    merge_extra_body(&mut payload, &model_config.extra_body);
+    Ok(payload)
+}
+
+/// Add the tool choice to a request made with [`create_request`]
+pub fn add_tool_choice(
+    payload: &mut Value,
+    tool_choice: &ToolChoice,
+    tools: &[Tool],
+) -> Result<()> {
+    tool_choice.validate(tools)?;
+    if payload.get("tools").is_none() {
+        return Ok(());
+    }
+    let config = match tool_choice {
+        ToolChoice::Auto => return Ok(()),
+        ToolChoice::None => json!({"mode": "NONE"}),
+        ToolChoice::Required => json!({"mode": "ANY"}),
+        ToolChoice::Tool(name) => json!({"mode": "ANY", "allowedFunctionNames": [name]}),
+    };
+    payload.as_object_mut().unwrap().insert(
+        "toolConfig".to_string(),
+        json!({"functionCallingConfig": config}),
+    );
+    Ok(())
+}
+
+/// Create a request for the batchEmbedContents endpoint
+pub fn create_embedding_request(model: &EmbeddingModel, texts: &[String]) -> Value {
+    let requests: Vec<Value> = texts
+        .iter()
+        .map(|text| {
+            let mut request = json!({
+                "model": format!("models/{}", model.model_name),
+                "content": {"parts": [{"text": text}]},
+            });
+            if let Some(dimensions) = model.dimensions {
+                request["outputDimensionality"] = json!(dimensions);
+            }
+            request
+        })
+        .collect();
+    json!({ "requests": requests })
+}
+
+/// Read the vectors of a batchEmbedContents response, in the order of the input texts
+pub fn embeddings_from_response(response: &Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+    response["embeddings"]
+        .as_array()
+        .ok_or_else(|| {
+            ProviderError::RequestFailed("No embeddings in Gemini response".to_string())
+        })?
+        .iter()
+        .map(|embedding| {
+            vector_from_json(&embedding["values"]).ok_or_else(|| {
+                ProviderError::RequestFailed("Invalid embedding in response".to_string())
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -400,6 +672,201 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_message_to_google_spec_document_and_audio() {
+        let messages = vec![Message::user()
+            .with_document("JVBERi0=", "application/pdf", None)
+            .with_audio("UklGRg==", "audio/wav")];
+        let payload = format_messages(&messages);
+
+        let parts = payload[0]["parts"].as_array().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0]["inline_data"]["mime_type"], "application/pdf");
+        assert_eq!(parts[0]["inline_data"]["data"], "JVBERi0=");
+        assert_eq!(parts[1]["inline_data"]["mime_type"], "audio/wav");
+    }
+
+    #[test]
+    fn test_message_to_google_spec_unsupported_document_and_audio() {
+        let messages = vec![Message::user()
+            .with_document("UEsDBA==", "application/zip", Some("src.zip".to_string()))
+            .with_document("bmFtZTogZ29vc2U=", "application/yaml", None)
+            .with_audio("AAAA", "audio/webm")];
+        let payload = format_messages(&messages);
+
+        let parts = payload[0]["parts"].as_array().unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(parts.iter().all(|part| part.get("inline_data").is_none()));
+        assert_eq!(
+            parts[0]["text"],
+            "[The user attached a document 'src.zip' (application/zip) which this model cannot read]"
+        );
+        // Text documents Gemini doesn't list are inlined as text
+        assert_eq!(
+            parts[1]["text"],
+            "Contents of document 'attachment':\nname: goose"
+        );
+        assert!(parts[2]["text"].as_str().unwrap().contains("audio/webm"));
+    }
+
+    #[test]
+    fn test_message_to_google_spec_image() {
+        let messages = vec![
+            Message::user()
+                .with_text("What is in this screenshot?")
+                .with_image("iVBORw0KGgo=", "image/png"),
+            set_up_tool_response_message(
+                "screen_capture",
+                vec![
+                    Content::text("Screenshot taken"),
+                    Content::image("UklGRg==", "image/webp"),
+                ],
+            ),
+        ];
+        let payload = format_messages(&messages);
+
+        assert_eq!(
+            payload[0]["parts"][1],
+            json!({"inline_data": {"mime_type": "image/png", "data": "iVBORw0KGgo="}})
+        );
+        assert_eq!(
+            payload[1]["parts"][1]["inline_data"]["mime_type"],
+            "image/webp"
+        );
+    }
+
+    #[test]
+    fn test_create_request_with_settings() -> Result<()> {
+        let model_config = ModelConfig::new("gemini-2.0-flash".to_string());
+        let settings = GoogleSettings {
+            safety_settings: vec![SafetySetting {
+                category: "HARM_CATEGORY_DANGEROUS_CONTENT".to_string(),
+                threshold: "BLOCK_ONLY_HIGH".to_string(),
+            }],
+            response_mime_type: Some("application/json".to_string()),
+            candidate_count: Some(1),
+        };
+
+        let payload = create_request(&model_config, &settings, "system", &[], &[])?;
+        assert_eq!(
+            payload["safetySettings"],
+            json!([{"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_ONLY_HIGH"}])
+        );
+        assert_eq!(
+            payload["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert_eq!(payload["generationConfig"]["candidateCount"], 1);
+
+        let payload = create_request(
+            &model_config,
+            &GoogleSettings::default(),
+            "system",
+            &[],
+            &[],
+        )?;
+        assert!(payload.get("safetySettings").is_none());
+        assert!(payload.get("generationConfig").is_none());
+
+        let model_config = model_config
+            .with_top_k(Some(40))
+            .with_stop_sequences(vec!["END".to_string()])
+            .with_seed(Some(7));
+        let payload = create_request(
+            &model_config,
+            &GoogleSettings::default(),
+            "system",
+            &[],
+            &[],
+        )?;
+        assert_eq!(payload["generationConfig"]["topK"], 40);
+        assert_eq!(payload["generationConfig"]["stopSequences"], json!(["END"]));
+        assert_eq!(payload["generationConfig"]["seed"], 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_tool_choice() -> Result<()> {
+        let model_config = ModelConfig::new("gemini-2.0-flash".to_string());
+        let settings = GoogleSettings::default();
+        let tools = [Tool::new(
+            "get_weather",
+            "Get the weather",
+            json!({"type": "object"}),
+        )];
+        let request = |choice: ToolChoice| -> Result<Value> {
+            let mut payload = create_request(&model_config, &settings, "system", &[], &tools)?;
+            add_tool_choice(&mut payload, &choice, &tools)?;
+            Ok(payload)
+        };
+
+        assert!(request(ToolChoice::Auto)?.get("toolConfig").is_none());
+        assert_eq!(
+            request(ToolChoice::None)?["toolConfig"],
+            json!({"functionCallingConfig": {"mode": "NONE"}})
+        );
+        assert_eq!(
+            request(ToolChoice::Required)?["toolConfig"],
+            json!({"functionCallingConfig": {"mode": "ANY"}})
+        );
+        assert_eq!(
+            request(ToolChoice::Tool("get_weather".to_string()))?["toolConfig"],
+            json!({"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["get_weather"]}})
+        );
+        assert!(request(ToolChoice::Tool("search".to_string())).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_error() {
+        let blocked = json!({
+            "candidates": [{
+                "finishReason": "SAFETY",
+                "safetyRatings": [
+                    {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH", "blocked": true},
+                    {"category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE"}
+                ]
+            }]
+        });
+        match response_error(&blocked) {
+            Some(ProviderError::ContentFiltered(msg)) => {
+                assert!(msg.contains("(HARM_CATEGORY_DANGEROUS_CONTENT)"))
+            }
+            other => panic!("Expected a content filter error, got {:?}", other),
+        }
+
+        let prompt_blocked = json!({"promptFeedback": {"blockReason": "PROHIBITED_CONTENT"}});
+        assert!(matches!(
+            response_error(&prompt_blocked),
+            Some(ProviderError::ContentFiltered(_))
+        ));
+
+        let recitation = json!({"candidates": [{"finishReason": "RECITATION"}]});
+        assert!(matches!(
+            response_error(&recitation),
+            Some(ProviderError::ContentFiltered(_))
+        ));
+
+        let no_output =
+            json!({"candidates": [{"content": {"role": "model"}, "finishReason": "MAX_TOKENS"}]});
+        assert!(matches!(
+            response_error(&no_output),
+            Some(ProviderError::ExecutionError(_))
+        ));
+
+        // Partial output cut off at the token limit is kept
+        let partial = json!({
+            "candidates": [{"content": {"parts": [{"text": "The answer is"}]}, "finishReason": "MAX_TOKENS"}]
+        });
+        assert!(response_error(&partial).is_none());
+
+        let complete = json!({
+            "candidates": [{"content": {"parts": [{"text": "Hi"}]}, "finishReason": "STOP"}]
+        });
+        assert!(response_error(&complete).is_none());
+        assert!(response_error(&json!({})).is_none());
+    }
+
     #[test]
     fn test_tools_to_google_spec_with_valid_tools() {
         let params1 = json!({
@@ -536,4 +1003,25 @@ mod tests {
             panic!("Expected valid tool request");
         }
     }
+
+    #[test]
+    fn test_embeddings() -> Result<()> {
+        let model = EmbeddingModel::new("text-embedding-004").with_dimensions(Some(256));
+        let payload = create_embedding_request(&model, &["a".to_string(), "b".to_string()]);
+        assert_eq!(
+            payload["requests"][1],
+            json!({
+                "model": "models/text-embedding-004",
+                "content": {"parts": [{"text": "b"}]},
+                "outputDimensionality": 256
+            })
+        );
+
+        let response = json!({"embeddings": [{"values": [0.5, 1.0]}, {"values": [0.0, -1.0]}]});
+        assert_eq!(
+            embeddings_from_response(&response)?,
+            vec![vec![0.5, 1.0], vec![0.0, -1.0]]
+        );
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 pub mod anthropic;
 pub mod bedrock;
 pub mod google;
+pub mod ollama;
 pub mod openai;
@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+
+use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::providers::base::{ToolChoice, Usage};
+use crate::providers::embedding::{vector_from_json, EmbeddingModel};
+use crate::providers::errors::ProviderError;
+use crate::providers::formats::openai::{format_tools, validate_tool_schemas};
+use crate::providers::utils::{
+    audio_to_text, detect_image_path, document_to_text, is_valid_function_name, load_image_file,
+    merge_extra_body, sanitize_function_name,
+};
+use anyhow::Error;
+use mcp_core::ToolError;
+use mcp_core::{Content, Role, Tool, ToolCall};
+use serde_json::{json, Value};
+
+/// Convert internal Message format to the message specification of Ollama's native chat API
+///   https://github.com/ollama/ollama/blob/main/docs/api.md#generate-a-chat-completion
+///   images are sent as a list of base64 strings on the message instead of content parts,
+///   and tool call arguments are objects rather than encoded strings
+pub fn format_messages(messages: &[Message]) -> Vec<Value> {
+    let mut messages_spec = Vec::new();
+    // Ollama identifies tool results by the name of the tool rather than the call id
+    let mut tool_names: HashMap<&str, String> = HashMap::new();
+
+    for message in messages {
+        let mut text = Vec::new();
+        let mut images = Vec::new();
+        let mut tool_calls = Vec::new();
+        let mut output = Vec::new();
+
+        for content in &message.content {
+            match content {
+                MessageContent::Text(content) => {
+                    if content.text.is_empty() {
+                        continue;
+                    }
+                    if let Some(image) =
+                        detect_image_path(&content.text).and_then(|path| load_image_file(path).ok())
+                    {
+                        images.push(image.data);
+                    }
+                    text.push(content.text.clone());
+                }
+                MessageContent::Image(image) => images.push(image.data.clone()),
+                MessageContent::Document(document) => text.push(document_to_text(document)),
+                MessageContent::Audio(audio) => text.push(audio_to_text(audio)),
+                MessageContent::ToolRequest(request) => match &request.tool_call {
+                    Ok(tool_call) => {
+                        let sanitized_name = sanitize_function_name(&tool_call.name);
+                        tool_names.insert(&request.id, sanitized_name.clone());
+                        tool_calls.push(json!({
+                            "function": {
+                                "name": sanitized_name,
+                                "arguments": tool_call.arguments,
+                            }
+                        }));
+                    }
+                    Err(e) => {
+                        output.push(json!({
+                            "role": "tool",
+                            "content": format!("Error: {}", e),
+                        }));
+                    }
+                },
+                MessageContent::ToolResponse(response) => {
+                    let mut tool_message = match &response.tool_result {
+                        Ok(contents) => {
+                            let mut tool_text = Vec::new();
+                            let mut tool_images = Vec::new();
+                            // Send only contents with no audience or with Assistant in the audience
+                            for content in contents.iter().filter(|content| {
+                                content
+                                    .audience()
+                                    .is_none_or(|audience| audience.contains(&Role::Assistant))
+                            }) {
+                                match content.unannotated() {
+                                    Content::Text(text) => tool_text.push(text.text),
+                                    Content::Image(image) => tool_images.push(image.data),
+                                    Content::Resource(resource) => {
+                                        tool_text.push(resource.get_text())
+                                    }
+                                    Content::Document(document) => {
+                                        tool_text.push(document_to_text(&document))
+                                    }
+                                    Content::Audio(audio) => tool_text.push(audio_to_text(&audio)),
+                                }
+                            }
+
+                            let mut tool_message = json!({
+                                "role": "tool",
+                                "content": tool_text.join(" "),
+                            });
+                            if !tool_images.is_empty() {
+                                tool_message["images"] = json!(tool_images);
+                            }
+                            tool_message
+                        }
+                        Err(e) => {
+                            // A tool result error is shown as output so the model can interpret the error message
+                            json!({
+                                "role": "tool",
+                                "content": format!("The tool call returned the following error:\n{}", e),
+                            })
+                        }
+                    };
+                    if let Some(name) = tool_names.get(response.id.as_str()) {
+                        tool_message["tool_name"] = json!(name);
+                    }
+                    output.push(tool_message);
+                }
+            }
+        }
+
+        if !text.is_empty() || !images.is_empty() || !tool_calls.is_empty() {
+            let mut converted = json!({
+                "role": message.role,
+                "content": text.join("\n"),
+            });
+            if !images.is_empty() {
+                converted["images"] = json!(images);
+            }
+            if !tool_calls.is_empty() {
+                converted["tool_calls"] = json!(tool_calls);
+            }
+            output.insert(0, converted);
+        }
+        messages_spec.extend(output);
+    }
+
+    messages_spec
+}
+
+/// Convert a response of Ollama's native chat API to internal Message format
+pub fn response_to_message(response: &Value) -> anyhow::Result<Message> {
+    let original = &response["message"];
+    let mut content = Vec::new();
+
+    if let Some(text) = original.get("content").and_then(|t| t.as_str()) {
+        if !text.is_empty() {
+            content.push(MessageContent::text(text));
+        }
+    }
+
+    if let Some(tool_calls) = original.get("tool_calls").and_then(|t| t.as_array()) {
+        for tool_call in tool_calls {
+            // Ollama does not assign ids to tool calls
+            let id = format!("call_{}", uuid::Uuid::new_v4().simple());
+            let function_name = tool_call["function"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+
+            if !is_valid_function_name(&function_name) {
+                let error = ToolError::NotFound(format!(
+                    "The provided function name '{}' had invalid characters, it must match this regex [a-zA-Z0-9_-]+",
+                    function_name
+                ));
+                content.push(MessageContent::tool_request(id, Err(error)));
+                continue;
+            }
+
+            // Arguments are an object, but some models produce them as an encoded string
+            let arguments = match &tool_call["function"]["arguments"] {
+                Value::String(arguments) if arguments.is_empty() => Ok(json!({})),
+                Value::String(arguments) => serde_json::from_str::<Value>(arguments),
+                Value::Null => Ok(json!({})),
+                arguments => Ok(arguments.clone()),
+            };
+
+            match arguments {
+                Ok(params) => content.push(MessageContent::tool_request(
+                    id,
+                    Ok(ToolCall::new(&function_name, params)),
+                )),
+                Err(e) => {
+                    let error = ToolError::InvalidParameters(format!(
+                        "Could not interpret tool use parameters for id {}: {}",
+                        id, e
+                    ));
+                    content.push(MessageContent::tool_request(id, Err(error)));
+                }
+            }
+        }
+    }
+
+    Ok(Message {
+        role: Role::Assistant,
+        created: chrono::Utc::now().timestamp(),
+        content,
+    })
+}
+
+/// Token counts of a native chat response, reported as prompt_eval_count and eval_count
+pub fn get_usage(data: &Value) -> Usage {
+    let input_tokens = data
+        .get("prompt_eval_count")
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+    let output_tokens = data
+        .get("eval_count")
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+    let total_tokens = match (input_tokens, output_tokens) {
+        (Some(input), Some(output)) => Some(input + output),
+        _ => None,
+    };
+
+    Usage::new(input_tokens, output_tokens, total_tokens)
+}
+
+/// Create a request for Ollama's native chat API
+///
+/// The context window is set through `num_ctx` so that Ollama does not silently truncate
+/// the conversation at its own default, which is much smaller than most models support.
+/// The tools to send for a tool choice, as the native API has none
+///
+/// Leaving out the tools keeps the model from calling them, but it can't be made to call one.
+pub fn tools_for_choice(tool_choice: &ToolChoice, tools: &[Tool]) -> anyhow::Result<Vec<Tool>> {
+    tool_choice.validate(tools)?;
+    match tool_choice {
+        ToolChoice::Auto => Ok(tools.to_vec()),
+        ToolChoice::None => Ok(vec![]),
+        ToolChoice::Required | ToolChoice::Tool(_) => Err(anyhow::anyhow!(
+            "Ollama's native API can't require a tool call, set OLLAMA_API to openai instead"
+        )),
+    }
+}
+
+pub fn create_request(
+    model_config: &ModelConfig,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> anyhow::Result<Value, Error> {
+    let mut messages_array = vec![json!({"role": "system", "content": system})];
+    messages_array.extend(format_messages(messages));
+
+    let mut options = json!({ "num_ctx": model_config.context_limit() });
+    if let Some(temp) = model_config.temperature {
+        options["temperature"] = json!(temp);
+    }
+    if let Some(tokens) = model_config.max_tokens {
+        options["num_predict"] = json!(tokens);
+    }
+    if let Some(top_p) = model_config.top_p {
+        options["top_p"] = json!(top_p);
+    }
+    if let Some(top_k) = model_config.top_k {
+        options["top_k"] = json!(top_k);
+    }
+    if !model_config.stop_sequences.is_empty() {
+        options["stop"] = json!(model_config.stop_sequences);
+    }
+    if let Some(seed) = model_config.seed {
+        options["seed"] = json!(seed);
+    }
+    if let Some(penalty) = model_config.frequency_penalty {
+        options["frequency_penalty"] = json!(penalty);
+    }
+    if let Some(penalty) = model_config.presence_penalty {
+        options["presence_penalty"] = json!(penalty);
+    }
+
+    let mut payload = json!({
+        "model": model_config.model_name,
+        "messages": messages_array,
+        "options": options,
+        "stream": false,
+    });
+
+    if !tools.is_empty() {
+        let mut tools_spec = format_tools(tools)?;
+        validate_tool_schemas(&mut tools_spec);
+        payload["tools"] = json!(tools_spec);
+    }
+    merge_extra_body(&mut payload, &model_config.extra_body);
+
+    Ok(payload)
+}
+
+/// Create a request for Ollama's api/embed endpoint
+pub fn create_embedding_request(model: &EmbeddingModel, texts: &[String]) -> Value {
+    let mut payload = json!({
+        "model": model.model_name,
+        "input": texts,
+    });
+    if let Some(dimensions) = model.dimensions {
+        payload["dimensions"] = json!(dimensions);
+    }
+    payload
+}
+
+/// Read the vectors of an api/embed response, in the order of the input texts
+pub fn embeddings_from_response(response: &Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+    response["embeddings"]
+        .as_array()
+        .ok_or_else(|| {
+            ProviderError::RequestFailed("No embeddings in Ollama response".to_string())
+        })?
+        .iter()
+        .map(|embedding| {
+            vector_from_json(embedding).ok_or_else(|| {
+                ProviderError::RequestFailed("Invalid embedding in response".to_string())
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tools_for_choice() -> anyhow::Result<()> {
+        let tools = vec![Tool::new("read", "Read a file", json!({"type": "object"}))];
+
+        assert_eq!(tools_for_choice(&ToolChoice::Auto, &tools)?.len(), 1);
+        assert!(tools_for_choice(&ToolChoice::None, &tools)?.is_empty());
+        assert!(tools_for_choice(&ToolChoice::Required, &tools).is_err());
+        assert!(tools_for_choice(&ToolChoice::Tool("read".to_string()), &tools).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("qwen2.5".to_string())
+            .with_context_limit(Some(16_384))
+            .with_temperature(Some(0.2));
+        let messages = vec![
+            Message::user()
+                .with_text("What is in this image?")
+                .with_image("aGVsbG8=", "image/png"),
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ),
+            Message::user().with_tool_response("call_1", Ok(vec![Content::text("README.md")])),
+        ];
+        let tool = Tool::new(
+            "developer__shell",
+            "Run a shell command",
+            json!({"type": "object", "properties": {"command": {"type": "string"}}}),
+        );
+
+        let payload = create_request(&model_config, "system prompt", &messages, &[tool])?;
+
+        assert_eq!(payload["model"], "qwen2.5");
+        assert_eq!(payload["stream"], false);
+        assert_eq!(payload["options"]["num_ctx"], 16_384);
+        assert_eq!(payload["options"]["temperature"], json!(0.2f32));
+        assert_eq!(payload["tools"][0]["function"]["name"], "developer__shell");
+
+        let spec = payload["messages"].as_array().unwrap();
+        assert_eq!(
+            spec[0],
+            json!({"role": "system", "content": "system prompt"})
+        );
+        assert_eq!(
+            spec[1],
+            json!({"role": "user", "content": "What is in this image?", "images": ["aGVsbG8="]})
+        );
+        assert_eq!(
+            spec[2]["tool_calls"][0]["function"],
+            json!({"name": "developer__shell", "arguments": {"command": "ls"}})
+        );
+        assert_eq!(
+            spec[3],
+            json!({"role": "tool", "content": "README.md", "tool_name": "developer__shell"})
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_to_message() -> anyhow::Result<()> {
+        let response = json!({
+            "model": "qwen2.5",
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [
+                    {"function": {"name": "developer__shell", "arguments": {"command": "ls"}}},
+                    {"function": {"name": "developer__shell", "arguments": "{\"command\": \"pwd\"}"}},
+                    {"function": {"name": "invalid name", "arguments": {}}}
+                ]
+            },
+            "done": true,
+            "prompt_eval_count": 120,
+            "eval_count": 15
+        });
+
+        let message = response_to_message(&response)?;
+        assert_eq!(message.content.len(), 3);
+
+        let requests: Vec<_> = message
+            .content
+            .iter()
+            .filter_map(|c| c.as_tool_request())
+            .collect();
+        let first = requests[0].tool_call.as_ref().unwrap();
+        assert_eq!(first.arguments, json!({"command": "ls"}));
+        let second = requests[1].tool_call.as_ref().unwrap();
+        assert_eq!(second.arguments, json!({"command": "pwd"}));
+        assert!(matches!(requests[2].tool_call, Err(ToolError::NotFound(_))));
+        assert_ne!(requests[0].id, requests[1].id);
+
+        let usage = get_usage(&response);
+        assert_eq!(usage.input_tokens, Some(120));
+        assert_eq!(usage.output_tokens, Some(15));
+        assert_eq!(usage.total_tokens, Some(135));
+        Ok(())
+    }
+
+    #[test]
+    fn test_embeddings() -> anyhow::Result<()> {
+        let model = EmbeddingModel::new("nomic-embed-text");
+        let payload = create_embedding_request(&model, &["a".to_string()]);
+        assert_eq!(
+            payload,
+            json!({"model": "nomic-embed-text", "input": ["a"]})
+        );
+
+        let response = json!({
+            "model": "nomic-embed-text",
+            "embeddings": [[0.5, -0.5], [1.0, 0.0]],
+            "prompt_eval_count": 4
+        });
+        assert_eq!(
+            embeddings_from_response(&response)?,
+            vec![vec![0.5, -0.5], vec![1.0, 0.0]]
+        );
+        assert_eq!(get_usage(&response).input_tokens, Some(4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_sampling() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("qwen2.5".to_string())
+            .with_top_k(Some(40))
+            .with_seed(Some(7))
+            .with_stop_sequences(vec!["END".to_string()])
+            .with_extra_body(
+                json!({"options": {"mirostat": 2}, "keep_alive": "5m"})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            );
+
+        let payload = create_request(&model_config, "system", &[], &[])?;
+        assert_eq!(payload["options"]["top_k"], 40);
+        assert_eq!(payload["options"]["seed"], 7);
+        assert_eq!(payload["options"]["stop"], json!(["END"]));
+        assert_eq!(payload["options"]["mirostat"], 2);
+        assert_eq!(payload["keep_alive"], "5m");
+        Ok(())
+    }
+}
@@ -1,14 +1,15 @@
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
-use crate::providers::base::Usage;
+use crate::providers::base::{ToolChoice, Usage};
+use crate::providers::embedding::{vector_from_json, EmbeddingModel};
 use crate::providers::errors::ProviderError;
 use crate::providers::utils::{
-    convert_image, detect_image_path, is_valid_function_name, load_image_file,
-    sanitize_function_name, ImageFormat,
+    audio_to_text, convert_image, detect_image_path, document_to_text, is_valid_function_name,
+    load_image_file, merge_extra_body, sanitize_function_name, ImageFormat,
 };
 use anyhow::{anyhow, Error};
 use mcp_core::ToolError;
-use mcp_core::{Content, Role, Tool, ToolCall};
+use mcp_core::{AudioContent, Content, DocumentContent, Role, Tool, ToolCall};
 use serde_json::{json, Value};
 
 /// Convert internal Message format to OpenAI's API message specification
@@ -31,16 +32,20 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                         if let Some(image_path) = detect_image_path(&text.text) {
                             // Try to load and convert the image
                             if let Ok(image) = load_image_file(image_path) {
-                                converted["content"] = json!([
-                                    {"type": "text", "text": text.text},
-                                    convert_image(&image, image_format)
-                                ]);
+                                push_content_part(
+                                    &mut converted,
+                                    json!({"type": "text", "text": text.text}),
+                                );
+                                push_content_part(
+                                    &mut converted,
+                                    convert_image(&image, image_format),
+                                );
                             } else {
                                 // If image loading fails, just use the text
-                                converted["content"] = json!(text.text);
+                                push_text(&mut converted, &text.text);
                             }
                         } else {
-                            converted["content"] = json!(text.text);
+                            push_text(&mut converted, &text.text);
                         }
                     }
                 }
@@ -103,6 +108,13 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                                     Content::Resource(resource) => {
                                         tool_content.push(Content::text(resource.get_text()));
                                     }
+                                    Content::Document(document) => {
+                                        tool_content
+                                            .push(Content::text(document_to_text(&document)));
+                                    }
+                                    Content::Audio(audio) => {
+                                        tool_content.push(Content::text(audio_to_text(&audio)));
+                                    }
                                     _ => {
                                         tool_content.push(content);
                                     }
@@ -138,7 +150,13 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 }
                 MessageContent::Image(image) => {
                     // Handle direct image content
-                    converted["content"] = json!([convert_image(image, image_format)]);
+                    push_content_part(&mut converted, convert_image(image, image_format));
+                }
+                MessageContent::Document(document) => {
+                    push_content_part(&mut converted, convert_document(document, image_format));
+                }
+                MessageContent::Audio(audio) => {
+                    push_content_part(&mut converted, convert_audio(audio, image_format));
                 }
             }
         }
@@ -152,6 +170,73 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
     messages_spec
 }
 
+/// Append text to a message, which stays plain string content while it is the only part
+fn push_text(message: &mut Value, text: &str) {
+    if message.get("content").is_none() {
+        message["content"] = json!(text);
+    } else {
+        push_content_part(message, json!({"type": "text", "text": text}));
+    }
+}
+
+/// Append a content part to a message, turning plain string content into a parts array
+fn push_content_part(message: &mut Value, part: Value) {
+    let parts = match message.get("content") {
+        Some(Value::String(text)) => vec![json!({"type": "text", "text": text})],
+        Some(Value::Array(parts)) => parts.clone(),
+        _ => vec![],
+    };
+    message["content"] = json!(parts);
+    message["content"].as_array_mut().unwrap().push(part);
+}
+
+/// Convert a document into a content part
+///   PDFs use the file input (or the anthropic document block for compatible endpoints),
+///   everything else falls back to text
+fn convert_document(document: &DocumentContent, image_format: &ImageFormat) -> Value {
+    if document.mime_type != "application/pdf" {
+        return json!({"type": "text", "text": document_to_text(document)});
+    }
+
+    match image_format {
+        ImageFormat::OpenAi => json!({
+            "type": "file",
+            "file": {
+                "filename": document.name.as_deref().unwrap_or("document.pdf"),
+                "file_data": format!("data:{};base64,{}", document.mime_type, document.data),
+            }
+        }),
+        ImageFormat::Anthropic => json!({
+            "type": "document",
+            "source": {
+                "type": "base64",
+                "media_type": document.mime_type,
+                "data": document.data,
+            }
+        }),
+    }
+}
+
+/// Convert audio into an input_audio content part, OpenAI only accepts wav and mp3
+fn convert_audio(audio: &AudioContent, image_format: &ImageFormat) -> Value {
+    let format = match audio.mime_type.as_str() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => Some("wav"),
+        "audio/mpeg" | "audio/mp3" => Some("mp3"),
+        _ => None,
+    };
+
+    match (image_format, format) {
+        (ImageFormat::OpenAi, Some(format)) => json!({
+            "type": "input_audio",
+            "input_audio": {
+                "data": audio.data,
+                "format": format,
+            }
+        }),
+        _ => json!({"type": "text", "text": audio_to_text(audio)}),
+    }
+}
+
 /// Convert internal Tool format to OpenAI's API tool specification
 pub fn format_tools(tools: &[Tool]) -> anyhow::Result<Vec<Value>> {
     let mut tool_names = std::collections::HashSet::new();
@@ -321,12 +406,6 @@ pub fn create_request(
     tools: &[Tool],
     image_format: &ImageFormat,
 ) -> anyhow::Result<Value, Error> {
-    if model_config.model_name.starts_with("o1-mini") {
-        return Err(anyhow!(
-            "o1-mini model is not currently supported since Goose uses tool calling and o1-mini does not support it. Please use o1 or o3 models instead."
-        ));
-    }
-
     let is_o1 = model_config.model_name.starts_with("o1");
     let is_o3 = model_config.model_name.starts_with("o3");
 
@@ -345,7 +424,12 @@ pub fn create_request(
     // Validate tool schemas
     validate_tool_schemas(&mut tools_spec);
 
-    let mut messages_array = vec![system_message];
+    // Models without a system prompt get it merged into the first user message by the agent
+    let mut messages_array = if system.is_empty() {
+        vec![]
+    } else {
+        vec![system_message]
+    };
     messages_array.extend(messages_spec);
 
     let mut payload = json!({
@@ -358,6 +442,12 @@ pub fn create_request(
             .as_object_mut()
             .unwrap()
             .insert("tools".to_string(), json!(tools_spec));
+        if !model_config.capabilities.parallel_tool_calls {
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("parallel_tool_calls".to_string(), json!(false));
+        }
     }
     // o1, o3 models currently don't support temperature
     if !is_o1 && !is_o3 {
@@ -381,9 +471,110 @@ pub fn create_request(
             .unwrap()
             .insert(key.to_string(), json!(tokens));
     }
+
+    if model_config.top_k.is_some() {
+        return Err(anyhow!(
+            "top_k is not supported by the OpenAI API, servers that accept it can be sent top_k with GOOSE_EXTRA_BODY"
+        ));
+    }
+    let sampling = [
+        ("top_p", model_config.top_p.map(|v| json!(v))),
+        ("seed", model_config.seed.map(|v| json!(v))),
+        (
+            "frequency_penalty",
+            model_config.frequency_penalty.map(|v| json!(v)),
+        ),
+        (
+            "presence_penalty",
+            model_config.presence_penalty.map(|v| json!(v)),
+        ),
+        (
+            "stop",
+            (!model_config.stop_sequences.is_empty()).then(|| json!(model_config.stop_sequences)),
+        ),
+    ];
+    for (key, value) in sampling {
+        if let Some(value) = value {
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert(key.to_string(), value);
+        }
+    }
+    merge_extra_body(&mut payload, &model_config.extra_body);
+
     Ok(payload)
 }
 
+/// Add the tool choice to a request made with [`create_request`]
+pub fn add_tool_choice(
+    payload: &mut Value,
+    tool_choice: &ToolChoice,
+    tools: &[Tool],
+) -> anyhow::Result<()> {
+    tool_choice.validate(tools)?;
+    // The API rejects a tool choice without tools
+    if payload.get("tools").is_none() {
+        return Ok(());
+    }
+    let choice = match tool_choice {
+        ToolChoice::Auto => return Ok(()),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Required => json!("required"),
+        ToolChoice::Tool(name) => json!({"type": "function", "function": {"name": name}}),
+    };
+    payload
+        .as_object_mut()
+        .unwrap()
+        .insert("tool_choice".to_string(), choice);
+    Ok(())
+}
+
+/// Constrain the response of a request made with [`create_request`] to a JSON schema
+pub fn add_response_format(payload: &mut Value, response_schema: Option<&Value>) {
+    if let Some(schema) = response_schema {
+        payload["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": {"name": "final_output", "schema": schema}
+        });
+    }
+}
+
+/// How many texts to embed per request; the API accepts up to 2048 inputs, smaller batches
+/// keep requests reasonably sized
+pub const EMBEDDING_BATCH_SIZE: usize = 512;
+
+/// Create a request for the embeddings API
+pub fn create_embedding_request(model: &EmbeddingModel, texts: &[String]) -> Value {
+    let mut payload = json!({
+        "model": model.model_name,
+        "input": texts,
+        "encoding_format": "float",
+    });
+    if let Some(dimensions) = model.dimensions {
+        payload["dimensions"] = json!(dimensions);
+    }
+    payload
+}
+
+/// Read the vectors of an embeddings response, in the order of the input texts
+pub fn embeddings_from_response(response: &Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+    let mut data: Vec<&Value> = response["data"]
+        .as_array()
+        .ok_or_else(|| ProviderError::RequestFailed("No data in embeddings response".to_string()))?
+        .iter()
+        .collect();
+    data.sort_by_key(|item| item["index"].as_u64().unwrap_or_default());
+
+    data.iter()
+        .map(|item| {
+            vector_from_json(&item["embedding"]).ok_or_else(|| {
+                ProviderError::RequestFailed("Invalid embedding in response".to_string())
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -630,6 +821,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_add_tool_choice() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("gpt-4o".to_string());
+        let tools = [Tool::new(
+            "read_file",
+            "Read a file",
+            json!({"type": "object"}),
+        )];
+        let request = |choice: ToolChoice| -> anyhow::Result<Value> {
+            let mut payload =
+                create_request(&model_config, "system", &[], &tools, &ImageFormat::OpenAi)?;
+            add_tool_choice(&mut payload, &choice, &tools)?;
+            Ok(payload)
+        };
+
+        assert!(request(ToolChoice::Auto)?.get("tool_choice").is_none());
+        assert_eq!(request(ToolChoice::None)?["tool_choice"], "none");
+        assert_eq!(request(ToolChoice::Required)?["tool_choice"], "required");
+        assert_eq!(
+            request(ToolChoice::Tool("read_file".to_string()))?["tool_choice"],
+            json!({"type": "function", "function": {"name": "read_file"}})
+        );
+        assert!(request(ToolChoice::Tool("write_file".to_string())).is_err());
+
+        // No tool choice is sent without tools
+        let mut payload = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        add_tool_choice(&mut payload, &ToolChoice::None, &[])?;
+        assert!(payload.get("tool_choice").is_none());
+        Ok(())
+    }
+
     #[test]
     fn test_format_tools_empty() -> anyhow::Result<()> {
         let spec = format_tools(&[])?;
@@ -671,6 +893,63 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_format_messages_with_document_and_audio() -> anyhow::Result<()> {
+        let message = Message::user()
+            .with_text("Summarize these")
+            .with_document("JVBERi0=", "application/pdf", Some("spec.pdf".to_string()))
+            .with_audio("UklGRg==", "audio/wav")
+            .with_audio("T2dnUw==", "audio/ogg");
+        let spec = format_messages(&[message], &ImageFormat::OpenAi);
+
+        assert_eq!(spec.len(), 1);
+        let content = spec[0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 4);
+        assert_eq!(content[0]["type"], "text");
+        assert_eq!(content[0]["text"], "Summarize these");
+        assert_eq!(content[1]["type"], "file");
+        assert_eq!(content[1]["file"]["filename"], "spec.pdf");
+        assert_eq!(
+            content[1]["file"]["file_data"],
+            "data:application/pdf;base64,JVBERi0="
+        );
+        assert_eq!(content[2]["type"], "input_audio");
+        assert_eq!(content[2]["input_audio"]["format"], "wav");
+        assert_eq!(content[3]["type"], "text");
+        assert!(content[3]["text"].as_str().unwrap().contains("audio/ogg"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_tool_response_with_document() -> anyhow::Result<()> {
+        let messages = vec![
+            Message::assistant().with_tool_request(
+                "tool1",
+                Ok(ToolCall::new("example", json!({"param1": "value1"}))),
+            ),
+            Message::user().with_tool_response(
+                "tool1",
+                Ok(vec![Content::document(
+                    "aGVsbG8=",
+                    "text/plain",
+                    Some("hello.txt".to_string()),
+                )]),
+            ),
+        ];
+
+        let spec = format_messages(&messages, &ImageFormat::OpenAi);
+
+        assert_eq!(spec.len(), 2);
+        assert_eq!(spec[1]["role"], "tool");
+        assert_eq!(
+            spec[1]["content"],
+            "Contents of document 'hello.txt':\nhello"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_response_to_message_text() -> anyhow::Result<()> {
         let response = json!({
@@ -778,4 +1057,65 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_embeddings() -> anyhow::Result<()> {
+        let model = EmbeddingModel::new("text-embedding-3-small").with_dimensions(Some(2));
+        let payload = create_embedding_request(&model, &["a".to_string(), "b".to_string()]);
+        assert_eq!(payload["input"], json!(["a", "b"]));
+        assert_eq!(payload["dimensions"], 2);
+
+        let response = json!({
+            "data": [
+                {"object": "embedding", "index": 1, "embedding": [0.5, 0.25]},
+                {"object": "embedding", "index": 0, "embedding": [1.0, -1.0]}
+            ],
+            "usage": {"prompt_tokens": 2, "total_tokens": 2}
+        });
+        assert_eq!(
+            embeddings_from_response(&response)?,
+            vec![vec![1.0, -1.0], vec![0.5, 0.25]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_sampling() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("gpt-4o".to_string())
+            .with_top_p(Some(0.5))
+            .with_seed(Some(7))
+            .with_stop_sequences(vec!["END".to_string()])
+            .with_extra_body(
+                json!({"logit_bias": {"50256": -100}})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            );
+
+        let payload = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        assert_eq!(payload["top_p"], json!(0.5f32));
+        assert_eq!(payload["seed"], 7);
+        assert_eq!(payload["stop"], json!(["END"]));
+        assert_eq!(payload["logit_bias"], json!({"50256": -100}));
+        assert!(payload.get("frequency_penalty").is_none());
+
+        let model_config = model_config.with_top_k(Some(40));
+        assert!(create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_response_format() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("gpt-4o".to_string());
+        let schema = json!({"type": "object", "properties": {"answer": {"type": "string"}}});
+
+        let mut payload = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        add_response_format(&mut payload, None);
+        assert!(payload.get("response_format").is_none());
+
+        add_response_format(&mut payload, Some(&schema));
+        assert_eq!(payload["response_format"]["type"], "json_schema");
+        assert_eq!(payload["response_format"]["json_schema"]["schema"], schema);
+        Ok(())
+    }
 }
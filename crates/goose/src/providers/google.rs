@@ -1,15 +1,27 @@
 use super::errors::ProviderError;
+use super::http::provider_client;
+use super::rate_limit::{rate_limiter, RateLimiter};
+use crate::config::Config;
 use crate::message::Message;
 use crate::model::ModelConfig;
-use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
-use crate::providers::formats::google::{create_request, get_usage, response_to_message};
-use crate::providers::utils::{emit_debug_trace, unescape_json_values};
-use anyhow::Result;
+use crate::providers::base::{
+    CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage,
+};
+use crate::providers::embedding::{EmbeddingModel, EmbeddingProvider};
+use crate::providers::formats::google::{
+    add_tool_choice, create_embedding_request, create_request, embeddings_from_response, get_usage,
+    response_error, response_to_message, GoogleSettings, SafetySetting, HARM_BLOCK_THRESHOLDS,
+    HARM_CATEGORIES,
+};
+use crate::providers::utils::{
+    emit_debug_trace, get_model_list, handle_response_openai_compat, unescape_json_values,
+};
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use mcp_core::tool::Tool;
 use reqwest::{Client, StatusCode};
 use serde_json::Value;
-use std::time::Duration;
+use std::sync::Arc;
 use url::Url;
 
 pub const GOOGLE_API_HOST: &str = "https://generativelanguage.googleapis.com";
@@ -25,6 +37,8 @@ pub const GOOGLE_KNOWN_MODELS: &[&str] = &[
     "models/gemini-2.0-pro-exp-02-05",
 ];
 
+pub const GOOGLE_DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-004";
+
 pub const GOOGLE_DOC_URL: &str = "https://ai.google/get-started/our-models/";
 
 #[derive(Debug, serde::Serialize)]
@@ -34,6 +48,9 @@ pub struct GoogleProvider {
     host: String,
     api_key: String,
     model: ModelConfig,
+    #[serde(skip)]
+    settings: GoogleSettings,
+    embedding: EmbeddingModel,
 }
 
 impl Default for GoogleProvider {
@@ -51,26 +68,43 @@ impl GoogleProvider {
             .get("GOOGLE_HOST")
             .unwrap_or_else(|_| GOOGLE_API_HOST.to_string());
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let settings = read_settings(config)?;
+
+        let client = provider_client("GOOGLE")?;
 
         Ok(Self {
             client,
             host,
             api_key,
             model,
+            settings,
+            embedding: EmbeddingModel::new(GOOGLE_DEFAULT_EMBEDDING_MODEL),
         })
     }
 
+    pub fn with_embedding_model(mut self, embedding: EmbeddingModel) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
     async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+        self.post_to_model(&self.model.model_name, "generateContent", payload)
+            .await
+    }
+
+    async fn post_to_model(
+        &self,
+        model_name: &str,
+        method: &str,
+        payload: Value,
+    ) -> Result<Value, ProviderError> {
         let base_url = Url::parse(&self.host)
             .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
 
         let url = base_url
             .join(&format!(
-                "v1beta/models/{}:generateContent?key={}",
-                self.model.model_name, self.api_key
+                "v1beta/models/{}:{}?key={}",
+                model_name, method, self.api_key
             ))
             .map_err(|e| {
                 ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
@@ -125,6 +159,106 @@ impl GoogleProvider {
     }
 }
 
+#[async_trait]
+impl EmbeddingProvider for GoogleProvider {
+    fn embedding_model(&self) -> EmbeddingModel {
+        self.embedding.clone()
+    }
+
+    fn max_batch_size(&self) -> usize {
+        // The limit of batchEmbedContents
+        100
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError> {
+        let payload = create_embedding_request(&self.embedding, texts);
+        let response = self
+            .post_to_model(&self.embedding.model_name, "batchEmbedContents", payload)
+            .await?;
+
+        // Gemini does not report token counts for embeddings
+        let embeddings = embeddings_from_response(&response)?;
+        Ok((
+            embeddings,
+            ProviderUsage::new(self.embedding.model_name.clone(), Usage::default()),
+        ))
+    }
+}
+
+/// Read the Gemini request settings
+///
+/// - GOOGLE_SAFETY_SETTINGS: a threshold for all harm categories, e.g. `BLOCK_ONLY_HIGH`,
+///   or a map of category to threshold, e.g. `{"dangerous_content": "BLOCK_NONE"}`
+/// - GOOGLE_RESPONSE_MIME_TYPE: e.g. `application/json`
+/// - GOOGLE_CANDIDATE_COUNT
+fn read_settings(config: &Config) -> Result<GoogleSettings> {
+    let safety_settings = match config.get::<Value>("GOOGLE_SAFETY_SETTINGS") {
+        Ok(Value::String(threshold)) => {
+            let threshold = safety_threshold(&threshold)?;
+            HARM_CATEGORIES
+                .iter()
+                .map(|category| SafetySetting {
+                    category: category.to_string(),
+                    threshold: threshold.clone(),
+                })
+                .collect()
+        }
+        Ok(Value::Object(thresholds)) => thresholds
+            .iter()
+            .map(|(category, threshold)| {
+                Ok(SafetySetting {
+                    category: harm_category(category)?,
+                    threshold: safety_threshold(threshold.as_str().unwrap_or_default())?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        Ok(other) => bail!(
+            "GOOGLE_SAFETY_SETTINGS must be a threshold or a map of category to threshold, got {}",
+            other
+        ),
+        Err(_) => Vec::new(),
+    };
+
+    Ok(GoogleSettings {
+        safety_settings,
+        response_mime_type: config.get("GOOGLE_RESPONSE_MIME_TYPE").ok(),
+        candidate_count: config.get("GOOGLE_CANDIDATE_COUNT").ok(),
+    })
+}
+
+/// Accept categories with or without the HARM_CATEGORY_ prefix, in any case
+fn harm_category(name: &str) -> Result<String> {
+    let name = name.to_uppercase();
+    let category = if name.starts_with("HARM_CATEGORY_") {
+        name
+    } else {
+        format!("HARM_CATEGORY_{}", name)
+    };
+    if !HARM_CATEGORIES.contains(&category.as_str()) {
+        bail!(
+            "Unknown harm category '{}' in GOOGLE_SAFETY_SETTINGS, expected one of {}",
+            category,
+            HARM_CATEGORIES.join(", ")
+        );
+    }
+    Ok(category)
+}
+
+fn safety_threshold(name: &str) -> Result<String> {
+    let threshold = name.to_uppercase();
+    if !HARM_BLOCK_THRESHOLDS.contains(&threshold.as_str()) {
+        bail!(
+            "Unknown threshold '{}' in GOOGLE_SAFETY_SETTINGS, expected one of {}",
+            name,
+            HARM_BLOCK_THRESHOLDS.join(", ")
+        );
+    }
+    Ok(threshold)
+}
+
 #[async_trait]
 impl Provider for GoogleProvider {
     fn metadata() -> ProviderMetadata {
@@ -146,21 +280,71 @@ impl Provider for GoogleProvider {
         self.model.clone()
     }
 
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        rate_limiter("GOOGLE", &self.api_key)
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        Some(self.host.clone())
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let url = Url::parse(&self.host)
+            .and_then(|base_url| {
+                base_url.join(&format!("v1beta/models?pageSize=1000&key={}", self.api_key))
+            })
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let response = self.client.get(url).send().await?;
+        let payload = handle_response_openai_compat(response).await?;
+
+        // Only models that can generate content, names are listed as models/<name>
+        let mut models: Vec<String> = get_model_list(&payload, "models")?
+            .iter()
+            .filter(|model| {
+                model["supportedGenerationMethods"]
+                    .as_array()
+                    .is_some_and(|methods| methods.iter().any(|m| m == "generateContent"))
+            })
+            .filter_map(|model| model["name"].as_str())
+            .map(|name| name.trim_start_matches("models/").to_string())
+            .collect();
+        models.sort();
+        Ok(Some(models))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools)?;
+        let mut payload = create_request(&self.model, &self.settings, system, messages, tools)?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
 
         // Make request
         let response = self.post(payload.clone()).await?;
 
+        // Blocked and truncated responses have no usable content
+        if let Some(error) = response_error(&response) {
+            return Err(error);
+        }
+
         // Parse response
         let message = response_to_message(unescape_json_values(&response))?;
         let usage = get_usage(&response)?;
@@ -173,3 +357,58 @@ impl Provider for GoogleProvider {
         Ok((message, provider_usage))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> (tempfile::NamedTempFile, Config) {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = Config::new(temp_file.path(), "goose-test-google").unwrap();
+        (temp_file, config)
+    }
+
+    #[test]
+    fn test_read_settings() -> Result<()> {
+        let (_file, config) = config();
+        assert!(read_settings(&config)?.safety_settings.is_empty());
+
+        config.set("GOOGLE_SAFETY_SETTINGS", json!("block_only_high"))?;
+        config.set("GOOGLE_RESPONSE_MIME_TYPE", json!("application/json"))?;
+        let settings = read_settings(&config)?;
+        assert_eq!(settings.safety_settings.len(), HARM_CATEGORIES.len());
+        assert!(settings
+            .safety_settings
+            .iter()
+            .all(|setting| setting.threshold == "BLOCK_ONLY_HIGH"));
+        assert_eq!(
+            settings.response_mime_type.as_deref(),
+            Some("application/json")
+        );
+
+        config.set(
+            "GOOGLE_SAFETY_SETTINGS",
+            json!({"dangerous_content": "BLOCK_NONE", "HARM_CATEGORY_HARASSMENT": "OFF"}),
+        )?;
+        let settings = read_settings(&config)?;
+        assert!(settings.safety_settings.contains(&SafetySetting {
+            category: "HARM_CATEGORY_DANGEROUS_CONTENT".to_string(),
+            threshold: "BLOCK_NONE".to_string(),
+        }));
+        assert_eq!(settings.safety_settings.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_invalid_settings() -> Result<()> {
+        let (_file, config) = config();
+        config.set("GOOGLE_SAFETY_SETTINGS", json!({"violence": "BLOCK_NONE"}))?;
+        let err = read_settings(&config).unwrap_err();
+        assert!(err.to_string().contains("HARM_CATEGORY_VIOLENCE"));
+
+        config.set("GOOGLE_SAFETY_SETTINGS", json!("BLOCK_SOME"))?;
+        assert!(read_settings(&config).is_err());
+        Ok(())
+    }
+}
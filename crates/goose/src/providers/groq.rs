@@ -1,15 +1,21 @@
 use super::errors::ProviderError;
+use super::http::provider_client;
+use super::rate_limit::{rate_limiter, RateLimiter};
 use crate::message::Message;
 use crate::model::ModelConfig;
-use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
-use crate::providers::formats::openai::{create_request, get_usage, response_to_message};
-use crate::providers::utils::get_model;
+use crate::providers::base::{
+    CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage,
+};
+use crate::providers::formats::openai::{
+    add_tool_choice, create_request, get_usage, response_to_message,
+};
+use crate::providers::utils::{get_model, get_model_ids, handle_response_openai_compat};
 use anyhow::Result;
 use async_trait::async_trait;
 use mcp_core::Tool;
 use reqwest::{Client, StatusCode};
 use serde_json::Value;
-use std::time::Duration;
+use std::sync::Arc;
 use url::Url;
 
 pub const GROQ_API_HOST: &str = "https://api.groq.com";
@@ -42,9 +48,7 @@ impl GroqProvider {
             .get("GROQ_HOST")
             .unwrap_or_else(|_| GROQ_API_HOST.to_string());
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let client = provider_client("GROQ")?;
 
         Ok(Self {
             client,
@@ -118,23 +122,64 @@ impl Provider for GroqProvider {
         self.model.clone()
     }
 
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        rate_limiter("GROQ", &self.api_key)
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        Some(self.host.clone())
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let url = Url::parse(&self.host)
+            .and_then(|base_url| base_url.join("openai/v1/models"))
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let response = self
+            .client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+        let payload = handle_response_openai_compat(response).await?;
+
+        // Whisper models only transcribe audio
+        let models = get_model_ids(&payload)?
+            .into_iter()
+            .filter(|id| !id.contains("whisper"))
+            .collect();
+        Ok(Some(models))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> anyhow::Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> anyhow::Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(
+        let mut payload = create_request(
             &self.model,
             system,
             messages,
             tools,
             &super::utils::ImageFormat::OpenAi,
         )?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
 
         let response = self.post(payload.clone()).await?;
 
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+
+use crate::config::Config;
+
+/// Timeout of a whole request, long enough for slow models producing long responses
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Settings of the HTTP client providers use to reach their APIs
+///
+/// Network settings are shared by all providers and read from
+/// - GOOSE_HTTP_PROXY: proxy URL for all requests, by default the HTTP(S)_PROXY
+///   environment variables are used
+/// - GOOSE_NO_PROXY: comma separated hosts that bypass the proxy, e.g. `localhost,.corp.com`
+/// - GOOSE_CA_FILE: PEM file with additional root certificates to trust
+/// - GOOSE_REQUEST_TIMEOUT and GOOSE_CONNECT_TIMEOUT: timeouts in seconds
+///
+/// Each provider can override the request timeout and add headers with its own keys,
+/// e.g. OPENAI_TIMEOUT and OPENAI_HEADERS.
+#[derive(Debug, Clone)]
+pub struct HttpClientConfig {
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub ca_file: Option<PathBuf>,
+    pub timeout: Duration,
+    pub connect_timeout: Option<Duration>,
+    pub headers: HashMap<String, String>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: None,
+            ca_file: None,
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            connect_timeout: None,
+            headers: HashMap::new(),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    /// Read the shared settings, with the overrides of the provider whose config keys
+    /// start with `key_prefix` if given
+    pub fn from_config(config: &Config, key_prefix: Option<&str>) -> Self {
+        let seconds = |key: &str| config.get::<u64>(key).ok().map(Duration::from_secs);
+
+        let mut timeout = seconds("GOOSE_REQUEST_TIMEOUT").unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        let mut headers = HashMap::new();
+        if let Some(prefix) = key_prefix {
+            timeout = seconds(&format!("{prefix}_TIMEOUT")).unwrap_or(timeout);
+            headers = config.get(&format!("{prefix}_HEADERS")).unwrap_or_default();
+        }
+
+        Self {
+            proxy: config.get("GOOSE_HTTP_PROXY").ok(),
+            no_proxy: config.get("GOOSE_NO_PROXY").ok(),
+            ca_file: config
+                .get::<String>("GOOSE_CA_FILE")
+                .ok()
+                .map(PathBuf::from),
+            timeout,
+            connect_timeout: seconds("GOOSE_CONNECT_TIMEOUT"),
+            headers,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Add headers sent with every request, replacing configured headers of the same name
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    pub fn build(&self) -> Result<Client> {
+        let mut builder = Client::builder().timeout(self.timeout);
+
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        if let Some(proxy_url) = &self.proxy {
+            let proxy = Proxy::all(proxy_url)
+                .with_context(|| format!("Invalid GOOSE_HTTP_PROXY '{}'", proxy_url))?
+                .no_proxy(self.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(ca_file) = &self.ca_file {
+            let pem = std::fs::read(ca_file)
+                .with_context(|| format!("Failed to read GOOSE_CA_FILE {}", ca_file.display()))?;
+            let certificates = Certificate::from_pem_bundle(&pem).with_context(|| {
+                format!("GOOSE_CA_FILE {} is not a PEM bundle", ca_file.display())
+            })?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if !self.headers.is_empty() {
+            let mut headers = HeaderMap::new();
+            for (name, value) in &self.headers {
+                headers.insert(
+                    HeaderName::from_bytes(name.as_bytes())
+                        .with_context(|| format!("Invalid header name '{}'", name))?,
+                    HeaderValue::from_str(value)
+                        .with_context(|| format!("Invalid value for header '{}'", name))?,
+                );
+            }
+            builder = builder.default_headers(headers);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Build the HTTP client of a provider from the global config
+///
+/// `key_prefix` is the prefix of the provider's config keys, e.g. "OPENAI".
+pub fn provider_client(key_prefix: &str) -> Result<Client> {
+    HttpClientConfig::from_config(Config::global(), Some(key_prefix)).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[test]
+    fn test_from_config() -> Result<()> {
+        let temp_file = tempfile::NamedTempFile::new()?;
+        let config = Config::new(temp_file.path(), "goose-test-http")?;
+        config.set("GOOSE_HTTP_PROXY", json!("http://proxy.corp.com:3128"))?;
+        config.set("GOOSE_NO_PROXY", json!("localhost,.corp.com"))?;
+        config.set("GOOSE_REQUEST_TIMEOUT", json!(120))?;
+        config.set("ANTHROPIC_TIMEOUT", json!(900))?;
+        config.set("ANTHROPIC_HEADERS", json!({"X-Team": "tools"}))?;
+
+        let shared = HttpClientConfig::from_config(&config, None);
+        assert_eq!(shared.proxy.as_deref(), Some("http://proxy.corp.com:3128"));
+        assert_eq!(shared.no_proxy.as_deref(), Some("localhost,.corp.com"));
+        assert_eq!(shared.timeout, Duration::from_secs(120));
+        assert!(shared.headers.is_empty());
+
+        let openai = HttpClientConfig::from_config(&config, Some("OPENAI"));
+        assert_eq!(openai.timeout, Duration::from_secs(120));
+
+        let anthropic = HttpClientConfig::from_config(&config, Some("ANTHROPIC"));
+        assert_eq!(anthropic.timeout, Duration::from_secs(900));
+        assert_eq!(anthropic.headers.get("X-Team"), Some(&"tools".to_string()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_requests_use_proxy_and_headers() -> Result<()> {
+        let proxy = MockServer::start().await;
+
+        // The proxy receives requests for any host
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("X-Team", "tools"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("proxied"))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let client = HttpClientConfig {
+            proxy: Some(proxy.uri()),
+            headers: HashMap::from([("X-Team".to_string(), "tools".to_string())]),
+            ..Default::default()
+        }
+        .build()?;
+
+        let response = client
+            .get("http://api.example.invalid/v1/models")
+            .send()
+            .await?;
+        assert_eq!(response.text().await?, "proxied");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let missing_ca = HttpClientConfig {
+            ca_file: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        let err = missing_ca.build().unwrap_err();
+        assert!(err.to_string().contains("GOOSE_CA_FILE"));
+
+        let invalid_header = HttpClientConfig::default()
+            .with_headers(HashMap::from([("bad header".to_string(), "x".to_string())]));
+        assert!(invalid_header.build().is_err());
+    }
+}
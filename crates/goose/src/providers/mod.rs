@@ -3,15 +3,23 @@ pub mod azure;
 pub mod base;
 pub mod bedrock;
 pub mod databricks;
+pub mod embedding;
 pub mod errors;
 mod factory;
 pub mod formats;
 pub mod google;
 pub mod groq;
+pub mod http;
+pub mod model_cache;
 pub mod oauth;
 pub mod ollama;
 pub mod openai;
+pub mod openai_compatible;
 pub mod openrouter;
+pub mod rate_limit;
 pub mod utils;
 
-pub use factory::{create, providers};
+pub use factory::{
+    create, create_embedding, create_embedding_from_config, default_embedding_model, providers,
+    supported_models,
+};
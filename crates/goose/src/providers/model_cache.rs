@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use etcetera::{choose_app_strategy, AppStrategy};
+use serde::{Deserialize, Serialize};
+
+use super::base::Provider;
+use crate::config::Config;
+
+/// How long fetched model lists are used before asking the provider again
+pub const DEFAULT_MODEL_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Listing models should never hold up configuration for long
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedModels {
+    fetched_at: u64,
+    /// The [`Provider::supported_models_key`] the models were fetched with
+    #[serde(default)]
+    key: String,
+    models: Vec<String>,
+}
+
+/// A disk cache of the models each provider reports through
+/// [`Provider::fetch_supported_models`]
+///
+/// Lists are stored per provider as json files and refreshed once they are older than the
+/// TTL, or were fetched with another configuration such as a different host. When a refresh
+/// fails the stale list is still returned, so that goose keeps working offline.
+#[derive(Debug, Clone)]
+pub struct ModelCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ModelCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// The cache in the goose cache directory, with the TTL in seconds from GOOSE_MODEL_CACHE_TTL
+    ///
+    /// - macOS/Linux: ~/.cache/goose/models/
+    /// - Windows:     ~\AppData\Local\Block\goose\cache\models\
+    pub fn from_env() -> Result<Self> {
+        let dir = choose_app_strategy(crate::config::APP_STRATEGY.clone())?.in_cache_dir("models");
+        let ttl = Config::global()
+            .get::<u64>("GOOSE_MODEL_CACHE_TTL")
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_MODEL_CACHE_TTL);
+        Ok(Self::new(dir, ttl))
+    }
+
+    fn path(&self, provider_name: &str) -> PathBuf {
+        // Named instances such as openai_compatible:vllm need a valid file name
+        let file_name: String = provider_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    fn read(&self, provider_name: &str, key: &str) -> Option<CachedModels> {
+        let contents = std::fs::read_to_string(self.path(provider_name)).ok()?;
+        serde_json::from_str::<CachedModels>(&contents)
+            .ok()
+            .filter(|cached| cached.key == key)
+    }
+
+    /// The cached models of a provider if they were fetched with `key` and are younger than
+    /// the TTL
+    pub fn get(&self, provider_name: &str, key: &str) -> Option<Vec<String>> {
+        self.read(provider_name, key)
+            .filter(|cached| now().saturating_sub(cached.fetched_at) < self.ttl.as_secs())
+            .map(|cached| cached.models)
+    }
+
+    /// Store the models of a provider fetched with `key`
+    pub fn put(&self, provider_name: &str, key: &str, models: &[String]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let cached = CachedModels {
+            fetched_at: now(),
+            key: key.to_string(),
+            models: models.to_vec(),
+        };
+        std::fs::write(self.path(provider_name), serde_json::to_string(&cached)?)?;
+        Ok(())
+    }
+
+    /// The models a provider supports, from the cache or by asking the provider
+    ///
+    /// Returns `None` when the provider can't list its models and nothing is cached.
+    pub async fn supported_models(
+        &self,
+        provider_name: &str,
+        provider: &dyn Provider,
+    ) -> Option<Vec<String>> {
+        let Some(key) = provider.supported_models_key() else {
+            return provider.fetch_supported_models().await.ok().flatten();
+        };
+        if let Some(models) = self.get(provider_name, &key) {
+            return Some(models);
+        }
+
+        match tokio::time::timeout(FETCH_TIMEOUT, provider.fetch_supported_models()).await {
+            Ok(Ok(Some(models))) => {
+                if let Err(e) = self.put(provider_name, &key, &models) {
+                    tracing::warn!("Failed to cache models for {}: {}", provider_name, e);
+                }
+                Some(models)
+            }
+            Ok(Ok(None)) => None,
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to fetch models for {}: {}", provider_name, e);
+                self.read(provider_name, &key).map(|cached| cached.models)
+            }
+            Err(_) => {
+                tracing::warn!("Timed out fetching models for {}", provider_name);
+                self.read(provider_name, &key).map(|cached| cached.models)
+            }
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+    use crate::model::ModelConfig;
+    use crate::providers::base::{ProviderMetadata, ProviderUsage};
+    use crate::providers::errors::ProviderError;
+    use async_trait::async_trait;
+    use mcp_core::tool::Tool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct ListingProvider {
+        models: Result<Vec<String>, String>,
+        key: Option<String>,
+        fetches: AtomicUsize,
+    }
+
+    impl ListingProvider {
+        fn new(models: Result<Vec<String>, String>) -> Self {
+            Self {
+                models,
+                key: Some("http://localhost:8000".to_string()),
+                fetches: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for ListingProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            Err(ProviderError::ExecutionError(
+                "listing provider can't complete".to_string(),
+            ))
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("test".to_string())
+        }
+
+        fn supported_models_key(&self) -> Option<String> {
+            self.key.clone()
+        }
+
+        async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            self.models
+                .clone()
+                .map(Some)
+                .map_err(ProviderError::RequestFailed)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_models_are_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModelCache::new(dir.path().to_path_buf(), DEFAULT_MODEL_CACHE_TTL);
+        let provider = ListingProvider::new(Ok(vec!["model-a".to_string(), "model-b".to_string()]));
+
+        for _ in 0..2 {
+            let models = cache
+                .supported_models("openai_compatible:vllm", &provider)
+                .await;
+            assert_eq!(
+                models,
+                Some(vec!["model-a".to_string(), "model-b".to_string()])
+            );
+        }
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+        assert!(dir.path().join("openai_compatible_vllm.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_stale_models_are_used_when_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModelCache::new(dir.path().to_path_buf(), Duration::ZERO);
+        let provider = ListingProvider::new(Err("offline".to_string()));
+        let key = provider.supported_models_key().unwrap();
+        cache.put("openai", &key, &["gpt-4o".to_string()]).unwrap();
+        assert!(cache.get("openai", &key).is_none());
+
+        let models = cache.supported_models("openai", &provider).await;
+        assert_eq!(models, Some(vec!["gpt-4o".to_string()]));
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+
+        assert!(cache.supported_models("groq", &provider).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_models_of_another_configuration_are_refetched() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModelCache::new(dir.path().to_path_buf(), DEFAULT_MODEL_CACHE_TTL);
+        cache
+            .put("ollama", "http://old-host:11434", &["llama3".to_string()])
+            .unwrap();
+
+        let provider = ListingProvider::new(Ok(vec!["qwen2.5".to_string()]));
+        let models = cache.supported_models("ollama", &provider).await;
+        assert_eq!(models, Some(vec!["qwen2.5".to_string()]));
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+
+        // Lists read from the config are never cached
+        let provider = ListingProvider {
+            key: None,
+            ..ListingProvider::new(Ok(vec!["configured".to_string()]))
+        };
+        for _ in 0..2 {
+            let models = cache.supported_models("openai_compatible", &provider).await;
+            assert_eq!(models, Some(vec!["configured".to_string()]));
+        }
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+        assert!(!dir.path().join("openai_compatible.json").exists());
+    }
+}
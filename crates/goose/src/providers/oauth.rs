@@ -4,88 +4,195 @@ use base64::Engine;
 use chrono::{DateTime, Utc};
 use etcetera::{choose_app_strategy, AppStrategy};
 use lazy_static::lazy_static;
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use sha2::Digest;
-use std::{collections::HashMap, fs, net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex, Once},
+    time::Duration,
+};
 use tokio::sync::{oneshot, Mutex as TokioMutex};
 use url::Url;
 
+use super::http::provider_client;
+use crate::config::Config;
+
+static LEGACY_TOKENS_REMOVED: Once = Once::new();
+
 lazy_static! {
     static ref OAUTH_MUTEX: TokioMutex<()> = TokioMutex::new(());
+    /// Tokens of this process by cache key, so they are reused when the keyring is unavailable
+    static ref SESSION_TOKENS: Mutex<HashMap<String, TokenData>> = Mutex::new(HashMap::new());
 }
 
+/// Tokens are refreshed this long before they expire, so that they don't expire mid-request
+const EXPIRY_MARGIN: chrono::Duration = chrono::Duration::minutes(5);
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
 #[derive(Debug, Clone)]
 struct OidcEndpoints {
     authorization_endpoint: String,
     token_endpoint: String,
+    device_authorization_endpoint: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TokenData {
     access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
     expires_at: Option<DateTime<Utc>>,
 }
 
-struct TokenCache {
-    cache_path: PathBuf,
+impl TokenData {
+    fn is_fresh(&self) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| expires_at > Utc::now() + EXPIRY_MARGIN)
+    }
+}
+
+/// How the user logs in when there is no token that can be refreshed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LoginMethod {
+    /// Open the browser and receive the code on the local redirect URL
+    Browser,
+    /// Show a code to enter on any device, for machines without a browser
+    Device,
+    /// Print the authorization URL and read the redirected URL back from the terminal
+    Paste,
+}
+
+impl LoginMethod {
+    /// The method set with DATABRICKS_OAUTH_LOGIN (browser, device or paste), by default the
+    /// browser unless goose runs over SSH or without a display
+    fn from_config(config: &Config) -> Self {
+        match config
+            .get::<String>("DATABRICKS_OAUTH_LOGIN")
+            .map(|method| method.to_lowercase())
+            .as_deref()
+        {
+            Ok("browser") => Self::Browser,
+            Ok("device") => Self::Device,
+            Ok("paste") => Self::Paste,
+            _ if is_headless() => Self::Device,
+            _ => Self::Browser,
+        }
+    }
+}
+
+fn is_headless() -> bool {
+    let var = |name: &str| std::env::var_os(name).is_some();
+    if var("SSH_CONNECTION") || var("SSH_TTY") {
+        return true;
+    }
+    cfg!(target_os = "linux") && !var("DISPLAY") && !var("WAYLAND_DISPLAY")
+}
+
+/// Tokens stored in the keyring through [`Config`], one entry per workspace, client and scopes
+///
+/// Tokens are also kept in memory for the rest of the process, and tokens from the JSON files
+/// that older versions cached in the config directory are moved into the keyring.
+struct TokenCache<'a> {
+    config: &'a Config,
+    key: String,
+    legacy_path: Option<PathBuf>,
 }
 
-fn get_base_path() -> PathBuf {
-    // choose_app_strategy().config_dir()
-    // - macOS/Linux: ~/.config/goose/databricks/oauth
-    // - Windows:     ~\AppData\Roaming\Block\goose\config\databricks\oauth\
+/// The directory older versions cached tokens in
+// - macOS/Linux: ~/.config/goose/databricks/oauth
+// - Windows:     ~\AppData\Roaming\Block\goose\config\databricks\oauth\
+fn legacy_token_dir() -> Option<PathBuf> {
     choose_app_strategy(crate::config::APP_STRATEGY.clone())
-        .expect("goose requires a home dir")
-        .in_config_dir("databricks/oauth")
+        .ok()
+        .map(|strategy| strategy.in_config_dir("databricks/oauth"))
+}
+
+/// Delete the token files older versions left for other workspaces, clients or scopes
+fn remove_legacy_tokens() {
+    let Some(dir) = legacy_token_dir() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+    let _ = fs::remove_dir(&dir);
 }
 
-impl TokenCache {
-    fn new(host: &str, client_id: &str, scopes: &[String]) -> Self {
+impl<'a> TokenCache<'a> {
+    fn new(config: &'a Config, host: &str, client_id: &str, scopes: &[String]) -> Self {
         let mut hasher = sha2::Sha256::new();
         hasher.update(host.as_bytes());
         hasher.update(client_id.as_bytes());
         hasher.update(scopes.join(",").as_bytes());
         let hash = format!("{:x}", hasher.finalize());
 
-        fs::create_dir_all(get_base_path()).unwrap();
-        let cache_path = get_base_path().join(format!("{}.json", hash));
-
-        Self { cache_path }
+        Self {
+            config,
+            key: format!("DATABRICKS_OAUTH_TOKEN_{}", hash[..16].to_uppercase()),
+            legacy_path: legacy_token_dir().map(|dir| dir.join(format!("{}.json", hash))),
+        }
     }
 
     fn load_token(&self) -> Option<TokenData> {
-        if let Ok(contents) = fs::read_to_string(&self.cache_path) {
-            if let Ok(token_data) = serde_json::from_str::<TokenData>(&contents) {
-                if let Some(expires_at) = token_data.expires_at {
-                    if expires_at > Utc::now() {
-                        return Some(token_data);
-                    }
-                } else {
-                    return Some(token_data);
-                }
-            }
+        if let Some(token) = SESSION_TOKENS.lock().unwrap().get(&self.key) {
+            return Some(token.clone());
+        }
+        if let Ok(token) = self.config.get_secret::<TokenData>(&self.key) {
+            SESSION_TOKENS
+                .lock()
+                .unwrap()
+                .insert(self.key.clone(), token.clone());
+            return Some(token);
         }
-        None
+        self.migrate_legacy_token()
     }
 
-    fn save_token(&self, token_data: &TokenData) -> Result<()> {
-        if let Some(parent) = self.cache_path.parent() {
-            fs::create_dir_all(parent)?;
+    /// Store a token, when the keyring is unavailable it is only kept for this session
+    fn save_token(&self, token_data: &TokenData) {
+        SESSION_TOKENS
+            .lock()
+            .unwrap()
+            .insert(self.key.clone(), token_data.clone());
+        if let Err(e) = self.config.set_secret(&self.key, json!(token_data)) {
+            tracing::warn!("Failed to store the Databricks token in the keyring: {}", e);
         }
-        let contents = serde_json::to_string(token_data)?;
-        fs::write(&self.cache_path, contents)?;
-        Ok(())
+    }
+
+    /// Move the token file of older versions into the keyring and delete it, as it holds the
+    /// access token in plain text
+    fn migrate_legacy_token(&self) -> Option<TokenData> {
+        let path = self.legacy_path.as_ref()?;
+        let contents = fs::read_to_string(path).ok()?;
+        if let Err(e) = fs::remove_file(path) {
+            tracing::warn!("Failed to delete the old Databricks token file: {}", e);
+        }
+
+        let token = serde_json::from_str::<TokenData>(&contents).ok()?;
+        self.save_token(&token);
+        Some(token)
     }
 }
 
-async fn get_workspace_endpoints(host: &str) -> Result<OidcEndpoints> {
+async fn get_workspace_endpoints(client: &Client, host: &str) -> Result<OidcEndpoints> {
     let base_url = Url::parse(host).expect("Invalid host URL");
     let oidc_url = base_url
         .join("oidc/.well-known/oauth-authorization-server")
         .expect("Invalid OIDC URL");
 
-    let client = reqwest::Client::new();
     let resp = client.get(oidc_url.clone()).send().await?;
 
     if !resp.status().is_success() {
@@ -109,13 +216,50 @@ async fn get_workspace_endpoints(host: &str) -> Result<OidcEndpoints> {
         .ok_or_else(|| anyhow::anyhow!("token_endpoint not found in OIDC configuration"))?
         .to_string();
 
+    let device_authorization_endpoint = oidc_config
+        .get("device_authorization_endpoint")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+
     Ok(OidcEndpoints {
         authorization_endpoint,
         token_endpoint,
+        device_authorization_endpoint,
+    })
+}
+
+/// Read the tokens from a token endpoint response
+///
+/// `refresh_token` is kept when the response doesn't include a new one.
+fn parse_token_response(token_response: &Value, refresh_token: Option<&str>) -> Result<TokenData> {
+    let access_token = token_response
+        .get("access_token")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("access_token not found in token response"))?
+        .to_string();
+
+    let refresh_token = token_response
+        .get("refresh_token")
+        .and_then(|v| v.as_str())
+        .or(refresh_token)
+        .map(String::from);
+
+    let expires_in = token_response
+        .get("expires_in")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(3600);
+
+    let expires_at = Utc::now() + chrono::Duration::seconds(expires_in as i64);
+
+    Ok(TokenData {
+        access_token,
+        refresh_token,
+        expires_at: Some(expires_at),
     })
 }
 
 struct OAuthFlow {
+    client: Client,
     endpoints: OidcEndpoints,
     client_id: String,
     redirect_url: String,
@@ -126,12 +270,14 @@ struct OAuthFlow {
 
 impl OAuthFlow {
     fn new(
+        client: Client,
         endpoints: OidcEndpoints,
         client_id: String,
         redirect_url: String,
         scopes: Vec<String>,
     ) -> Self {
         Self {
+            client,
             endpoints,
             client_id,
             redirect_url,
@@ -173,8 +319,8 @@ impl OAuthFlow {
             ("client_id", &self.client_id),
         ];
 
-        let client = reqwest::Client::new();
-        let resp = client
+        let resp = self
+            .client
             .post(&self.endpoints.token_endpoint)
             .header("Content-Type", "application/x-www-form-urlencoded")
             .form(&params)
@@ -190,23 +336,169 @@ impl OAuthFlow {
         }
 
         let token_response: Value = resp.json().await?;
-        let access_token = token_response
-            .get("access_token")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("access_token not found in token response"))?
-            .to_string();
+        parse_token_response(&token_response, None)
+    }
 
-        let expires_in = token_response
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenData> {
+        let params = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &self.client_id),
+        ];
+
+        let resp = self
+            .client
+            .post(&self.endpoints.token_endpoint)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .form(&params)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err_text = resp.text().await?;
+            return Err(anyhow::anyhow!("Failed to refresh token: {}", err_text));
+        }
+
+        let token_response: Value = resp.json().await?;
+        parse_token_response(&token_response, Some(refresh_token))
+    }
+
+    async fn login(&self, method: LoginMethod) -> Result<TokenData> {
+        match method {
+            LoginMethod::Browser => self.execute().await,
+            LoginMethod::Device if self.endpoints.device_authorization_endpoint.is_some() => {
+                self.device_login().await
+            }
+            // Workspaces without device authorization still allow copying the code by hand
+            LoginMethod::Device | LoginMethod::Paste => self.paste_login().await,
+        }
+    }
+
+    /// The device authorization grant (RFC 8628): the user enters a code on another device
+    /// while we poll the token endpoint
+    async fn device_login(&self) -> Result<TokenData> {
+        let endpoint = self
+            .endpoints
+            .device_authorization_endpoint
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Device authorization is not supported"))?;
+
+        let resp = self
+            .client
+            .post(endpoint)
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("scope", &self.scopes.join(" ")),
+            ])
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err_text = resp.text().await?;
+            return Err(anyhow::anyhow!(
+                "Failed to start device authorization: {}",
+                err_text
+            ));
+        }
+
+        let device: Value = resp.json().await?;
+        let device_code = device
+            .get("device_code")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("device_code not found in device authorization"))?;
+        let user_code = device
+            .get("user_code")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let verification_uri = device
+            .get("verification_uri_complete")
+            .or_else(|| device.get("verification_uri"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let expires_in = device
             .get("expires_in")
             .and_then(|v| v.as_u64())
-            .unwrap_or(3600);
+            .unwrap_or(600);
+        let mut interval =
+            Duration::from_secs(device.get("interval").and_then(|v| v.as_u64()).unwrap_or(5));
 
-        let expires_at = Utc::now() + chrono::Duration::seconds(expires_in as i64);
+        println!(
+            "To log in to Databricks, open {} on any device and enter the code {}",
+            verification_uri, user_code
+        );
 
-        Ok(TokenData {
-            access_token,
-            expires_at: Some(expires_at),
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(expires_in);
+        loop {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("Authentication timed out"));
+            }
+            tokio::time::sleep(interval).await;
+
+            let resp = self
+                .client
+                .post(&self.endpoints.token_endpoint)
+                .form(&[
+                    ("grant_type", DEVICE_CODE_GRANT),
+                    ("device_code", device_code),
+                    ("client_id", &self.client_id),
+                ])
+                .send()
+                .await?;
+
+            let success = resp.status().is_success();
+            let token_response: Value = resp.json().await?;
+            if success {
+                return parse_token_response(&token_response, None);
+            }
+
+            match token_response.get("error").and_then(|e| e.as_str()) {
+                Some("authorization_pending") => {}
+                Some("slow_down") => interval += Duration::from_secs(5),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Device authorization failed: {}",
+                        token_response
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Log in without a local browser: the user opens the URL anywhere and pastes back the URL
+    /// the browser was redirected to, which fails to load but carries the code
+    async fn paste_login(&self) -> Result<TokenData> {
+        println!(
+            "Open this URL in a browser to log in to Databricks:\n{}\n\n\
+            After logging in the browser is redirected to {}, which may fail to load. \
+            Paste the full URL from the address bar here:",
+            self.get_authorization_url(),
+            self.redirect_url
+        );
+
+        let input = tokio::task::spawn_blocking(|| {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).map(|_| line)
         })
+        .await??;
+
+        let code = self.code_from_redirect(input.trim())?;
+        self.exchange_code_for_token(&code).await
+    }
+
+    /// The authorization code from a pasted redirect URL, or the code itself
+    fn code_from_redirect(&self, input: &str) -> Result<String> {
+        let Ok(url) = Url::parse(input) else {
+            return Ok(input.to_string());
+        };
+
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        if params.get("state") != Some(&self.state) {
+            return Err(anyhow::anyhow!("State mismatch in the pasted URL"));
+        }
+        params
+            .get("code")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No authorization code in the pasted URL"))
     }
 
     async fn execute(&self) -> Result<TokenData> {
@@ -294,38 +586,125 @@ pub(crate) async fn get_oauth_token_async(
     // Acquire the global mutex to ensure only one OAuth flow runs at a time
     let _guard = OAUTH_MUTEX.lock().await;
 
-    let token_cache = TokenCache::new(host, client_id, scopes);
+    let config = Config::global();
+    let client = provider_client("DATABRICKS")?;
+    let token_cache = TokenCache::new(config, host, client_id, scopes);
+
+    let token = get_token(
+        &client,
+        &token_cache,
+        host,
+        client_id,
+        redirect_url,
+        scopes,
+        LoginMethod::from_config(config),
+    )
+    .await;
+
+    // The token file of this workspace is migrated by now, the others hold stale tokens
+    LEGACY_TOKENS_REMOVED.call_once(remove_legacy_tokens);
+    token
+}
+
+/// Whether a cached token can be used without refreshing it or logging in
+pub(crate) fn has_fresh_token(host: &str, client_id: &str, scopes: &[String]) -> bool {
+    TokenCache::new(Config::global(), host, client_id, scopes)
+        .load_token()
+        .is_some_and(|token| token.is_fresh())
+}
 
+/// A valid access token from the cache, by refreshing the cached token or by logging in
+async fn get_token(
+    client: &Client,
+    token_cache: &TokenCache<'_>,
+    host: &str,
+    client_id: &str,
+    redirect_url: &str,
+    scopes: &[String],
+    method: LoginMethod,
+) -> Result<String> {
     // Try cache first
-    if let Some(token) = token_cache.load_token() {
-        return Ok(token.access_token);
+    let cached = token_cache.load_token();
+    if let Some(token) = cached.as_ref().filter(|token| token.is_fresh()) {
+        return Ok(token.access_token.clone());
     }
 
     // Get endpoints and execute flow
-    let endpoints = get_workspace_endpoints(host).await?;
+    let endpoints = get_workspace_endpoints(client, host).await?;
     let flow = OAuthFlow::new(
+        client.clone(),
         endpoints,
         client_id.to_string(),
         redirect_url.to_string(),
         scopes.to_vec(),
     );
 
-    // Execute the OAuth flow and get token
-    let token = flow.execute().await?;
+    // Refresh silently if we can, otherwise the user has to log in again
+    let refreshed = match cached.and_then(|token| token.refresh_token) {
+        Some(refresh_token) => match flow.refresh(&refresh_token).await {
+            Ok(token) => Some(token),
+            Err(e) => {
+                tracing::info!("Failed to refresh the Databricks token, logging in: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    let token = match refreshed {
+        Some(token) => token,
+        None => flow.login(method).await?,
+    };
 
     // Cache and return
-    token_cache.save_token(&token)?;
+    token_cache.save_token(&token);
     Ok(token.access_token)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serial_test::serial;
     use wiremock::{
-        matchers::{method, path},
+        matchers::{body_string_contains, method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
+    async fn mock_oidc_server() -> MockServer {
+        let mock_server = MockServer::start().await;
+
+        let mock_response = serde_json::json!({
+            "authorization_endpoint": format!("{}/oidc/v1/authorize", mock_server.uri()),
+            "token_endpoint": format!("{}/oidc/v1/token", mock_server.uri()),
+            "device_authorization_endpoint": format!("{}/oidc/v1/device", mock_server.uri()),
+        });
+
+        Mock::given(method("GET"))
+            .and(path("/oidc/.well-known/oauth-authorization-server"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&mock_response))
+            .mount(&mock_server)
+            .await;
+
+        mock_server
+    }
+
+    fn test_config() -> (tempfile::NamedTempFile, Config) {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = Config::new(temp_file.path(), "goose-test-oauth").unwrap();
+        (temp_file, config)
+    }
+
+    async fn test_flow(mock_server: &MockServer) -> Result<OAuthFlow> {
+        let client = Client::new();
+        let endpoints = get_workspace_endpoints(&client, &mock_server.uri()).await?;
+        Ok(OAuthFlow::new(
+            client,
+            endpoints,
+            "test-client".to_string(),
+            "http://localhost:8020".to_string(),
+            vec!["all-apis".to_string(), "offline_access".to_string()],
+        ))
+    }
+
     #[tokio::test]
     async fn test_get_workspace_endpoints() -> Result<()> {
         let mock_server = MockServer::start().await;
@@ -341,35 +720,264 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let endpoints = get_workspace_endpoints(&mock_server.uri()).await?;
+        let endpoints = get_workspace_endpoints(&Client::new(), &mock_server.uri()).await?;
 
         assert_eq!(
             endpoints.authorization_endpoint,
             "https://example.com/oauth2/authorize"
         );
         assert_eq!(endpoints.token_endpoint, "https://example.com/oauth2/token");
+        assert!(endpoints.device_authorization_endpoint.is_none());
 
         Ok(())
     }
 
-    #[test]
-    fn test_token_cache() -> Result<()> {
-        let cache = TokenCache::new(
-            "https://example.com",
+    #[tokio::test]
+    #[serial]
+    async fn test_expired_token_is_refreshed() -> Result<()> {
+        let mock_server = mock_oidc_server().await;
+
+        Mock::given(method("POST"))
+            .and(path("/oidc/v1/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=old-refresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "new-access",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let (_file, config) = test_config();
+        let scopes = vec!["all-apis".to_string()];
+        let cache = TokenCache::new(&config, &mock_server.uri(), "test-client", &scopes);
+
+        // Secrets are read from the environment before the keyring
+        let expired = TokenData {
+            access_token: "old-access".to_string(),
+            refresh_token: Some("old-refresh".to_string()),
+            expires_at: Some(Utc::now() + chrono::Duration::minutes(1)),
+        };
+        std::env::set_var(&cache.key, serde_json::to_string(&expired)?);
+
+        let token = get_token(
+            &Client::new(),
+            &cache,
+            &mock_server.uri(),
             "test-client",
-            &["scope1".to_string()],
-        );
+            "http://localhost:8020",
+            &scopes,
+            LoginMethod::Browser,
+        )
+        .await;
+
+        std::env::remove_var(&cache.key);
+        let _ = config.delete_secret(&cache.key);
+        assert_eq!(token?, "new-access");
+        Ok(())
+    }
 
-        let token_data = TokenData {
-            access_token: "test-token".to_string(),
+    #[tokio::test]
+    #[serial]
+    async fn test_fresh_token_is_used() -> Result<()> {
+        let (_file, config) = test_config();
+        let cache = TokenCache::new(&config, "https://example.com", "test-client", &[]);
+
+        let fresh = TokenData {
+            access_token: "cached-access".to_string(),
+            refresh_token: None,
             expires_at: Some(Utc::now() + chrono::Duration::hours(1)),
         };
+        std::env::set_var(&cache.key, serde_json::to_string(&fresh)?);
 
-        cache.save_token(&token_data)?;
+        // No request is made, the host doesn't exist
+        let token = get_token(
+            &Client::new(),
+            &cache,
+            "https://example.invalid",
+            "test-client",
+            "http://localhost:8020",
+            &[],
+            LoginMethod::Browser,
+        )
+        .await;
+
+        std::env::remove_var(&cache.key);
+        assert_eq!(token?, "cached-access");
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_token_is_kept_for_the_session() -> Result<()> {
+        let (_file, config) = test_config();
+        let cache = TokenCache::new(&config, "https://session.example.com", "test-client", &[]);
+        let token = TokenData {
+            access_token: "session-access".to_string(),
+            refresh_token: Some("session-refresh".to_string()),
+            expires_at: Some(Utc::now() + chrono::Duration::hours(1)),
+        };
+
+        // Also without a keyring, the token is loaded from memory
+        cache.save_token(&token);
+        let _ = config.delete_secret(&cache.key);
+        let loaded = cache.load_token();
+
+        SESSION_TOKENS.lock().unwrap().remove(&cache.key);
+        assert_eq!(loaded.unwrap().access_token, "session-access");
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_has_fresh_token() {
+        let host = "https://fresh.example.com";
+        let key = TokenCache::new(Config::global(), host, "test-client", &[]).key;
+        let token = |expires_in: chrono::Duration| TokenData {
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: Some(Utc::now() + expires_in),
+        };
+
+        SESSION_TOKENS
+            .lock()
+            .unwrap()
+            .insert(key.clone(), token(chrono::Duration::hours(1)));
+        let fresh = has_fresh_token(host, "test-client", &[]);
+
+        // An expiring token would have to be refreshed, which may end in a login
+        SESSION_TOKENS
+            .lock()
+            .unwrap()
+            .insert(key.clone(), token(chrono::Duration::minutes(1)));
+        let expiring = has_fresh_token(host, "test-client", &[]);
+
+        SESSION_TOKENS.lock().unwrap().remove(&key);
+        assert!(fresh);
+        assert!(!expiring);
+    }
 
-        let loaded_token = cache.load_token().unwrap();
-        assert_eq!(loaded_token.access_token, token_data.access_token);
+    #[test]
+    #[serial]
+    fn test_legacy_token_file_is_migrated() -> Result<()> {
+        let (_file, config) = test_config();
+        let dir = tempfile::tempdir()?;
+        let legacy_path = dir.path().join("token.json");
+        std::fs::write(
+            &legacy_path,
+            r#"{"access_token": "legacy-access", "expires_at": null}"#,
+        )?;
+        let mut cache = TokenCache::new(&config, "https://legacy.example.com", "test-client", &[]);
+        cache.legacy_path = Some(legacy_path.clone());
+
+        let loaded = cache.load_token();
+
+        SESSION_TOKENS.lock().unwrap().remove(&cache.key);
+        let _ = config.delete_secret(&cache.key);
+        assert_eq!(loaded.unwrap().access_token, "legacy-access");
+        assert!(!legacy_path.exists());
+        Ok(())
+    }
 
+    #[tokio::test]
+    async fn test_device_login() -> Result<()> {
+        let mock_server = mock_oidc_server().await;
+
+        Mock::given(method("POST"))
+            .and(path("/oidc/v1/device"))
+            .and(body_string_contains("client_id=test-client"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "device_code": "device-123",
+                "user_code": "ABCD-EFGH",
+                "verification_uri": "https://example.com/device",
+                "expires_in": 60,
+                "interval": 0
+            })))
+            .mount(&mock_server)
+            .await;
+
+        // The user approves the login on the second poll
+        Mock::given(method("POST"))
+            .and(path("/oidc/v1/token"))
+            .and(body_string_contains("device_code=device-123"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": "authorization_pending"
+            })))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oidc/v1/token"))
+            .and(body_string_contains("device_code=device-123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "device-access",
+                "refresh_token": "device-refresh",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let flow = test_flow(&mock_server).await?;
+        let token = flow.login(LoginMethod::Device).await?;
+        assert_eq!(token.access_token, "device-access");
+        assert_eq!(token.refresh_token.as_deref(), Some("device-refresh"));
+        assert!(token.is_fresh());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_denied_device_login() -> Result<()> {
+        let mock_server = mock_oidc_server().await;
+
+        Mock::given(method("POST"))
+            .and(path("/oidc/v1/device"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "device_code": "device-123",
+                "user_code": "ABCD-EFGH",
+                "verification_uri": "https://example.com/device",
+                "interval": 0
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oidc/v1/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": "access_denied"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let flow = test_flow(&mock_server).await?;
+        let err = flow.device_login().await.unwrap_err();
+        assert!(err.to_string().contains("access_denied"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_code_from_redirect() -> Result<()> {
+        let mock_server = mock_oidc_server().await;
+        let flow = test_flow(&mock_server).await?;
+
+        let redirect = format!("http://localhost:8020/?code=abc&state={}", flow.state);
+        assert_eq!(flow.code_from_redirect(&redirect)?, "abc");
+        assert_eq!(flow.code_from_redirect("abc")?, "abc");
+        assert!(flow
+            .code_from_redirect("http://localhost:8020/?code=abc&state=other")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_token_response() -> Result<()> {
+        let token = parse_token_response(
+            &serde_json::json!({"access_token": "a", "expires_in": 60}),
+            Some("kept-refresh"),
+        )?;
+        assert_eq!(token.refresh_token.as_deref(), Some("kept-refresh"));
+        // Expires within the margin, so it is refreshed before use
+        assert!(!token.is_fresh());
         Ok(())
     }
 }
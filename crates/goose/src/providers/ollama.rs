@@ -1,14 +1,20 @@
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::embedding::{EmbeddingModel, EmbeddingProvider};
 use super::errors::ProviderError;
-use super::utils::{get_model, handle_response_openai_compat};
+use super::formats::ollama as native;
+use super::http::{provider_client, HttpClientConfig};
+use super::utils::{get_model, get_model_list, handle_response_openai_compat};
+use crate::config::Config;
 use crate::message::Message;
 use crate::model::ModelConfig;
-use crate::providers::formats::openai::{create_request, get_usage, response_to_message};
+use crate::providers::formats::openai::{
+    add_tool_choice, create_request, get_usage, response_to_message,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use indoc::formatdoc;
 use mcp_core::tool::Tool;
-use reqwest::Client;
+use reqwest::{Client, Response, StatusCode};
 use serde_json::Value;
 use std::time::Duration;
 use url::Url;
@@ -18,14 +24,47 @@ pub const OLLAMA_DEFAULT_PORT: u16 = 11434;
 pub const OLLAMA_DEFAULT_MODEL: &str = "qwen2.5";
 // Ollama can run many models, we only provide the default
 pub const OLLAMA_KNOWN_MODELS: &[&str] = &[OLLAMA_DEFAULT_MODEL];
+pub const OLLAMA_DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
 pub const OLLAMA_DOC_URL: &str = "https://ollama.com/library";
 
+/// The API used to talk to Ollama, configured with OLLAMA_API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OllamaApi {
+    /// The OpenAI compatible v1/chat/completions endpoint
+    OpenAi,
+    /// Ollama's own api/chat endpoint, which accepts the context window size
+    Native,
+}
+
+impl OllamaApi {
+    fn from_config(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "openai" => Ok(OllamaApi::OpenAi),
+            "native" => Ok(OllamaApi::Native),
+            other => Err(anyhow::anyhow!(
+                "Unknown OLLAMA_API '{}', expected 'openai' or 'native'",
+                other
+            )),
+        }
+    }
+
+    fn path(&self) -> &'static str {
+        match self {
+            OllamaApi::OpenAi => "v1/chat/completions",
+            OllamaApi::Native => "api/chat",
+        }
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct OllamaProvider {
     #[serde(skip)]
     client: Client,
     host: String,
+    api: OllamaApi,
     model: ModelConfig,
+    embedding: EmbeddingModel,
 }
 
 impl Default for OllamaProvider {
@@ -37,52 +76,109 @@ impl Default for OllamaProvider {
 
 impl OllamaProvider {
     pub fn from_env(model: ModelConfig) -> Result<Self> {
-        let config = crate::config::Config::global();
+        let config = Config::global();
         let host: String = config
             .get("OLLAMA_HOST")
             .unwrap_or_else(|_| OLLAMA_HOST.to_string());
+        let api = match config.get::<String>("OLLAMA_API") {
+            Ok(api) => OllamaApi::from_config(&api)?,
+            Err(_) => OllamaApi::OpenAi,
+        };
+
+        Self::new(host, api, model)
+    }
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+    pub fn new(host: String, api: OllamaApi, model: ModelConfig) -> Result<Self> {
+        let client = provider_client("OLLAMA")?;
 
         Ok(Self {
             client,
             host,
+            api,
             model,
+            embedding: EmbeddingModel::new(OLLAMA_DEFAULT_EMBEDDING_MODEL),
         })
     }
 
-    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
-        // TODO: remove this later when the UI handles provider config refresh
-        // OLLAMA_HOST is sometimes just the 'host' or 'host:port' without a scheme
-        let base = if self.host.starts_with("http://") || self.host.starts_with("https://") {
-            self.host.clone()
-        } else {
-            format!("http://{}", self.host)
-        };
+    pub fn with_embedding_model(mut self, embedding: EmbeddingModel) -> Self {
+        self.embedding = embedding;
+        self
+    }
 
-        let mut base_url = Url::parse(&base)
-            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+    /// Models available on the Ollama server at `host`, as listed by api/tags
+    pub async fn fetch_models(host: &str) -> Result<Vec<String>, ProviderError> {
+        let url = base_url(host)?
+            .join("api/tags")
+            .map_err(|e| ProviderError::RequestFailed(format!("Failed to construct URL: {e}")))?;
 
-        // Set the default port if missing
-        let explicit_default_port = self.host.ends_with(":80") || self.host.ends_with(":443");
-        if base_url.port().is_none() && !explicit_default_port {
-            base_url.set_port(Some(OLLAMA_DEFAULT_PORT)).map_err(|_| {
-                ProviderError::RequestFailed("Failed to set default port".to_string())
-            })?;
-        }
+        let response = HttpClientConfig::from_config(Config::global(), Some("OLLAMA"))
+            .with_timeout(Duration::from_secs(5))
+            .build()?
+            .get(url)
+            .send()
+            .await?;
+        let payload = handle_response_openai_compat(response).await?;
+
+        let mut models: Vec<String> = get_model_list(&payload, "models")?
+            .iter()
+            .filter_map(|model| model["name"].as_str().map(String::from))
+            .collect();
+        models.sort();
+        Ok(models)
+    }
 
-        let url = base_url.join("v1/chat/completions").map_err(|e| {
+    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+        let url = base_url(&self.host)?.join(self.api.path()).map_err(|e| {
             ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
         })?;
 
         let response = self.client.post(url).json(&payload).send().await?;
 
+        self.handle_response(response, &self.model.model_name).await
+    }
+
+    async fn handle_response(
+        &self,
+        response: Response,
+        model: &str,
+    ) -> Result<Value, ProviderError> {
+        // Ollama answers with 404 when the model has not been pulled yet
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ProviderError::RequestFailed(format!(
+                "Model '{}' is not available on the Ollama server at {}. Download it with `ollama pull {}`",
+                model, self.host, model
+            )));
+        }
         handle_response_openai_compat(response).await
     }
 }
 
+/// The base URL of the Ollama server
+///
+/// OLLAMA_HOST is sometimes just the 'host' or 'host:port' without a scheme, and uses
+/// Ollama's default port unless one is given.
+fn base_url(host: &str) -> Result<Url, ProviderError> {
+    // TODO: remove this later when the UI handles provider config refresh
+    let base = if host.starts_with("http://") || host.starts_with("https://") {
+        host.to_string()
+    } else {
+        format!("http://{}", host)
+    };
+
+    let mut base_url = Url::parse(&base)
+        .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+    // Set the default port if missing
+    let explicit_default_port = host.ends_with(":80") || host.ends_with(":443");
+    if base_url.port().is_none() && !explicit_default_port {
+        base_url
+            .set_port(Some(OLLAMA_DEFAULT_PORT))
+            .map_err(|_| ProviderError::RequestFailed("Failed to set default port".to_string()))?;
+    }
+
+    Ok(base_url)
+}
+
 #[async_trait]
 impl Provider for OllamaProvider {
     fn metadata() -> ProviderMetadata {
@@ -93,12 +189,10 @@ impl Provider for OllamaProvider {
             OLLAMA_DEFAULT_MODEL,
             OLLAMA_KNOWN_MODELS.iter().map(|&s| s.to_string()).collect(),
             OLLAMA_DOC_URL,
-            vec![ConfigKey::new(
-                "OLLAMA_HOST",
-                true,
-                false,
-                Some(OLLAMA_HOST),
-            )],
+            vec![
+                ConfigKey::new("OLLAMA_HOST", true, false, Some(OLLAMA_HOST)),
+                ConfigKey::new("OLLAMA_API", false, false, Some("openai")),
+            ],
         )
     }
 
@@ -106,16 +200,41 @@ impl Provider for OllamaProvider {
         self.model.clone()
     }
 
+    fn supported_models_key(&self) -> Option<String> {
+        Some(self.host.clone())
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        Self::fetch_models(&self.host).await.map(Some)
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
+        if options.response_schema.is_some() {
+            return Err(ProviderError::RequestFailed(
+                "This provider can't constrain responses to a JSON schema".to_string(),
+            ));
+        }
+
         // Transform the system message to replace developer instructions
         let modified_system = if let Some(dev_section) = system.split("## developer").nth(1) {
             if let (Some(start_idx), Some(end_idx)) = (
@@ -185,13 +304,26 @@ impl Provider for OllamaProvider {
             system.to_string()
         };
 
-        let payload = create_request(
+        if self.api == OllamaApi::Native {
+            let tools = native::tools_for_choice(&options.tool_choice, tools)?;
+            let payload = native::create_request(&self.model, &modified_system, messages, &tools)?;
+            let response = self.post(payload.clone()).await?;
+
+            let message = native::response_to_message(&response)?;
+            let usage = native::get_usage(&response);
+            let model = get_model(&response);
+            super::utils::emit_debug_trace(self, &payload, &response, &usage);
+            return Ok((message, ProviderUsage::new(model, usage)));
+        }
+
+        let mut payload = create_request(
             &self.model,
             &modified_system,
             messages,
             tools,
             &super::utils::ImageFormat::OpenAi,
         )?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
         let response = self.post(payload.clone()).await?;
 
         // Parse response
@@ -209,3 +341,218 @@ impl Provider for OllamaProvider {
         Ok((message, ProviderUsage::new(model, usage)))
     }
 }
+
+#[async_trait]
+impl EmbeddingProvider for OllamaProvider {
+    fn embedding_model(&self) -> EmbeddingModel {
+        self.embedding.clone()
+    }
+
+    fn max_batch_size(&self) -> usize {
+        // Texts are embedded locally one after another, so small batches lose nothing
+        64
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError> {
+        // Embeddings are only served by the native API
+        let url = base_url(&self.host)?.join("api/embed").map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+        })?;
+
+        let payload = native::create_embedding_request(&self.embedding, texts);
+        let response = self.client.post(url).json(&payload).send().await?;
+        let response = self
+            .handle_response(response, &self.embedding.model_name)
+            .await?;
+
+        let embeddings = native::embeddings_from_response(&response)?;
+        Ok((
+            embeddings,
+            ProviderUsage::new(
+                self.embedding.model_name.clone(),
+                native::get_usage(&response),
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::ToolChoice;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_partial_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn test_native_complete_sets_context_window() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({
+                "model": "qwen2.5",
+                "stream": false,
+                "options": {"num_ctx": 32768}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "qwen2.5",
+                "message": {"role": "assistant", "content": "Hello!"},
+                "done": true,
+                "prompt_eval_count": 20,
+                "eval_count": 2
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let model = ModelConfig::new("qwen2.5".to_string()).with_context_limit(Some(32768));
+        let provider = OllamaProvider::new(mock_server.uri(), OllamaApi::Native, model)?;
+
+        let (message, usage) = provider
+            .complete("You are helpful", &[Message::user().with_text("Hi")], &[])
+            .await?;
+        assert_eq!(message.content[0].as_text(), Some("Hello!"));
+        assert_eq!(usage.model, "qwen2.5");
+        assert_eq!(usage.usage.total_tokens, Some(22));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tool_choice() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({"tool_choice": "required"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "qwen2.5",
+                "choices": [{"message": {"role": "assistant", "content": "Hello!"}}]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let tools = [Tool::new(
+            "developer__shell",
+            "Run a command",
+            json!({"type": "object"}),
+        )];
+        let messages = [Message::user().with_text("Hi")];
+        let options = CompletionOptions::default().with_tool_choice(ToolChoice::Required);
+
+        let model = ModelConfig::new("qwen2.5".to_string());
+        let provider = OllamaProvider::new(mock_server.uri(), OllamaApi::OpenAi, model.clone())?;
+        provider
+            .complete_with_options("You are helpful", &messages, &tools, &options)
+            .await?;
+
+        // The native API can't require a tool call, so no request is made
+        let provider = OllamaProvider::new(mock_server.uri(), OllamaApi::Native, model)?;
+        let err = provider
+            .complete_with_options("You are helpful", &messages, &tools, &options)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("OLLAMA_API"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_model_suggests_pull() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "error": {"message": "model \"llama3.3\" not found, try pulling it first"}
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OllamaProvider::new(
+            mock_server.uri(),
+            OllamaApi::OpenAi,
+            ModelConfig::new("llama3.3".to_string()),
+        )?;
+
+        let err = provider
+            .complete("You are helpful", &[Message::user().with_text("Hi")], &[])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("`ollama pull llama3.3`"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_models() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "models": [
+                    {"name": "qwen2.5:latest", "size": 4683087332u64},
+                    {"name": "llama3.2:latest", "size": 2019393189u64}
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let models = OllamaProvider::fetch_models(&mock_server.uri()).await?;
+        assert_eq!(models, vec!["llama3.2:latest", "qwen2.5:latest"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_embed() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .and(body_partial_json(json!({"model": "nomic-embed-text"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "nomic-embed-text",
+                "embeddings": [[0.1, 0.2], [0.3, 0.4]],
+                "prompt_eval_count": 6
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Embeddings use the native API whichever API is used for chat
+        let provider = OllamaProvider::new(
+            mock_server.uri(),
+            OllamaApi::OpenAi,
+            ModelConfig::new("qwen2.5".to_string()),
+        )?;
+
+        let (embeddings, usage) = provider
+            .embed(&["first".to_string(), "second".to_string()])
+            .await?;
+        assert_eq!(embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        assert_eq!(usage.model, "nomic-embed-text");
+        assert_eq!(usage.usage.input_tokens, Some(6));
+        Ok(())
+    }
+
+    #[test]
+    fn test_base_url() {
+        assert_eq!(
+            base_url("localhost").unwrap().as_str(),
+            "http://localhost:11434/"
+        );
+        assert_eq!(
+            base_url("https://ollama.example.com:443").unwrap().as_str(),
+            "https://ollama.example.com/"
+        );
+        assert_eq!(
+            base_url("http://gpu-box:8080").unwrap().as_str(),
+            "http://gpu-box:8080/"
+        );
+    }
+}
@@ -1,13 +1,22 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::Value;
-use std::time::Duration;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::embedding::{EmbeddingModel, EmbeddingProvider};
 use super::errors::ProviderError;
-use super::formats::openai::{create_request, get_usage, response_to_message};
-use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
+use super::formats::openai::{
+    add_response_format, add_tool_choice, create_embedding_request, create_request,
+    embeddings_from_response, get_usage, response_to_message, EMBEDDING_BATCH_SIZE,
+};
+use super::http::provider_client;
+use super::rate_limit::{rate_limiter, RateLimiter};
+use super::utils::{
+    emit_debug_trace, get_model, get_model_ids, handle_response_openai_compat, ImageFormat,
+};
 use crate::message::Message;
 use crate::model::ModelConfig;
 use mcp_core::tool::Tool;
@@ -21,6 +30,8 @@ pub const OPEN_AI_KNOWN_MODELS: &[&str] = &[
     "o1",
 ];
 
+pub const OPEN_AI_DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
 pub const OPEN_AI_DOC_URL: &str = "https://platform.openai.com/docs/models";
 
 #[derive(Debug, serde::Serialize)]
@@ -30,6 +41,7 @@ pub struct OpenAiProvider {
     host: String,
     api_key: String,
     model: ModelConfig,
+    embedding: EmbeddingModel,
 }
 
 impl Default for OpenAiProvider {
@@ -46,18 +58,22 @@ impl OpenAiProvider {
         let host: String = config
             .get("OPENAI_HOST")
             .unwrap_or_else(|_| "https://api.openai.com".to_string());
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let client = provider_client("OPENAI")?;
 
         Ok(Self {
             client,
             host,
             api_key,
             model,
+            embedding: EmbeddingModel::new(OPEN_AI_DEFAULT_EMBEDDING_MODEL),
         })
     }
 
+    pub fn with_embedding_model(mut self, embedding: EmbeddingModel) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
     async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
         let base_url = url::Url::parse(&self.host)
             .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
@@ -101,17 +117,71 @@ impl Provider for OpenAiProvider {
         self.model.clone()
     }
 
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        rate_limiter("OPENAI", &self.api_key)
+    }
+
+    fn supports_response_schema(&self) -> bool {
+        true
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        Some(self.host.clone())
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let url = url::Url::parse(&self.host)
+            .and_then(|base_url| base_url.join("v1/models"))
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let response = self
+            .client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+        let payload = handle_response_openai_compat(response).await?;
+
+        // The list also has embedding, image and audio models, which can't be used for chat
+        let models = get_model_ids(&payload)?
+            .into_iter()
+            .filter(|id| {
+                ["gpt-", "o1", "o3", "chatgpt-"]
+                    .iter()
+                    .any(|prefix| id.starts_with(prefix))
+                    && !["audio", "realtime", "tts", "transcribe"]
+                        .iter()
+                        .any(|kind| id.contains(kind))
+            })
+            .collect();
+        Ok(Some(models))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        let mut payload =
+            create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
+        add_response_format(&mut payload, options.response_schema.as_ref());
 
         // Make request
         let response = self.post(payload.clone()).await?;
@@ -131,3 +201,40 @@ impl Provider for OpenAiProvider {
         Ok((message, ProviderUsage::new(model, usage)))
     }
 }
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiProvider {
+    fn embedding_model(&self) -> EmbeddingModel {
+        self.embedding.clone()
+    }
+
+    fn max_batch_size(&self) -> usize {
+        EMBEDDING_BATCH_SIZE
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, ProviderUsage), ProviderError> {
+        let url = url::Url::parse(&self.host)
+            .and_then(|base_url| base_url.join("v1/embeddings"))
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let payload = create_embedding_request(&self.embedding, texts);
+        let response = self
+            .client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&payload)
+            .send()
+            .await?;
+        let response = handle_response_openai_compat(response).await?;
+
+        let embeddings = embeddings_from_response(&response)?;
+        let usage = get_usage(&response).unwrap_or_default();
+        Ok((
+            embeddings,
+            ProviderUsage::new(self.embedding.model_name.clone(), usage),
+        ))
+    }
+}
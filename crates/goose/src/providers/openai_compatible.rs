@@ -0,0 +1,494 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::base::{CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use super::formats::openai::{add_tool_choice, create_request, get_usage, response_to_message};
+use super::http::HttpClientConfig;
+use super::rate_limit::{shared_rate_limiter, RateLimiter, RateLimits};
+use super::utils::{
+    emit_debug_trace, get_model, get_model_ids, handle_response_openai_compat, ImageFormat,
+};
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use mcp_core::tool::Tool;
+
+pub const OPENAI_COMPATIBLE_PROVIDER: &str = "openai_compatible";
+pub const OPENAI_COMPATIBLE_DEFAULT_MODEL: &str = "default";
+pub const OPENAI_COMPATIBLE_DEFAULT_PATH: &str = "v1/chat/completions";
+pub const OPENAI_COMPATIBLE_DOC_URL: &str =
+    "https://platform.openai.com/docs/api-reference/chat/create";
+
+/// Settings of one OpenAI compatible endpoint, such as a vLLM, llama.cpp or LM Studio server
+///
+/// The unnamed instance reads OPENAI_COMPATIBLE_HOST, OPENAI_COMPATIBLE_PATH, etc. A named
+/// instance, selected with the provider name `openai_compatible:<name>`, reads the same keys
+/// with the name added, e.g. OPENAI_COMPATIBLE_VLLM_HOST for `openai_compatible:vllm`.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct OpenAiCompatibleSettings {
+    /// Base URL of the server, e.g. http://localhost:8000
+    pub host: String,
+    /// Path of the chat completions endpoint relative to the host
+    pub path: String,
+    /// Sent as a bearer token when set
+    #[serde(skip)]
+    pub api_key: Option<String>,
+    /// Additional headers sent with every request, on top of the shared HTTP settings
+    #[serde(skip)]
+    pub headers: HashMap<String, String>,
+    /// Models served by this endpoint
+    pub models: Vec<String>,
+    /// Context limit of the served models, overriding the model registry
+    pub context_limit: Option<usize>,
+    /// Client side limits of the requests sent to this endpoint
+    #[serde(skip)]
+    pub rate_limits: RateLimits,
+    /// The config key prefix these settings were read from, also used for the HTTP settings
+    /// of the instance such as OPENAI_COMPATIBLE_<NAME>_TIMEOUT
+    #[serde(skip)]
+    pub key_prefix: Option<String>,
+}
+
+impl OpenAiCompatibleSettings {
+    /// The config key prefix for an instance, OPENAI_COMPATIBLE or OPENAI_COMPATIBLE_<NAME>
+    pub fn key_prefix(instance: Option<&str>) -> String {
+        match instance {
+            Some(name) => format!(
+                "OPENAI_COMPATIBLE_{}",
+                name.chars()
+                    .map(|c| if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    })
+                    .collect::<String>()
+            ),
+            None => "OPENAI_COMPATIBLE".to_string(),
+        }
+    }
+
+    /// Load the settings of an instance from the config, or of the unnamed instance if `None`
+    pub fn from_config(config: &Config, instance: Option<&str>) -> Result<Self> {
+        let prefix = Self::key_prefix(instance);
+        let host: String = config.get(&format!("{prefix}_HOST")).map_err(|_| {
+            anyhow::anyhow!(
+                "{prefix}_HOST must be set to the base URL of the OpenAI compatible server"
+            )
+        })?;
+
+        Ok(Self {
+            host,
+            path: config
+                .get(&format!("{prefix}_PATH"))
+                .unwrap_or_else(|_| OPENAI_COMPATIBLE_DEFAULT_PATH.to_string()),
+            api_key: config.get_secret(&format!("{prefix}_API_KEY")).ok(),
+            headers: config.get(&format!("{prefix}_HEADERS")).unwrap_or_default(),
+            models: config.get(&format!("{prefix}_MODELS")).unwrap_or_default(),
+            context_limit: config.get(&format!("{prefix}_CONTEXT_LIMIT")).ok(),
+            rate_limits: RateLimits::from_config(config, &prefix),
+            key_prefix: Some(prefix),
+        })
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct OpenAiCompatibleProvider {
+    #[serde(skip)]
+    client: Client,
+    settings: OpenAiCompatibleSettings,
+    model: ModelConfig,
+}
+
+impl Default for OpenAiCompatibleProvider {
+    fn default() -> Self {
+        let model = ModelConfig::new(OpenAiCompatibleProvider::metadata().default_model);
+        OpenAiCompatibleProvider::from_env(model)
+            .expect("Failed to initialize OpenAI compatible provider")
+    }
+}
+
+impl OpenAiCompatibleProvider {
+    /// Create the provider for the unnamed instance
+    pub fn from_env(model: ModelConfig) -> Result<Self> {
+        Self::from_instance(None, model)
+    }
+
+    /// Create the provider for a named instance, or the unnamed one if `None`
+    pub fn from_instance(instance: Option<&str>, model: ModelConfig) -> Result<Self> {
+        let settings = OpenAiCompatibleSettings::from_config(Config::global(), instance)?;
+        Self::new(settings, model)
+    }
+
+    pub fn new(settings: OpenAiCompatibleSettings, model: ModelConfig) -> Result<Self> {
+        Self::with_config(Config::global(), settings, model)
+    }
+
+    /// Create the provider with the HTTP settings of `config` instead of the global config
+    pub fn with_config(
+        config: &Config,
+        settings: OpenAiCompatibleSettings,
+        model: ModelConfig,
+    ) -> Result<Self> {
+        let client = HttpClientConfig::from_config(config, settings.key_prefix.as_deref())
+            .with_headers(settings.headers.clone())
+            .build()?;
+        let model = model.with_context_limit(settings.context_limit);
+
+        Ok(Self {
+            client,
+            settings,
+            model,
+        })
+    }
+
+    /// The models configured for this endpoint
+    pub fn models(&self) -> &[String] {
+        &self.settings.models
+    }
+
+    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+        let base_url = url::Url::parse(&self.settings.host)
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+        let url = base_url.join(&self.settings.path).map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+        })?;
+
+        let mut request = self.client.post(url);
+        if let Some(api_key) = &self.settings.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = request.json(&payload).send().await?;
+
+        handle_response_openai_compat(response).await
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAiCompatibleProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            OPENAI_COMPATIBLE_PROVIDER,
+            "OpenAI Compatible",
+            "Self-hosted servers with an OpenAI compatible API, such as vLLM, llama.cpp or LM Studio",
+            OPENAI_COMPATIBLE_DEFAULT_MODEL,
+            vec![],
+            OPENAI_COMPATIBLE_DOC_URL,
+            vec![
+                ConfigKey::new("OPENAI_COMPATIBLE_HOST", true, false, None),
+                ConfigKey::new(
+                    "OPENAI_COMPATIBLE_PATH",
+                    false,
+                    false,
+                    Some(OPENAI_COMPATIBLE_DEFAULT_PATH),
+                ),
+                ConfigKey::new("OPENAI_COMPATIBLE_API_KEY", false, true, None),
+            ],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.model.clone()
+    }
+
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        let credential = format!(
+            "{}{}",
+            self.settings.host,
+            self.settings.api_key.as_deref().unwrap_or_default()
+        );
+        shared_rate_limiter("OPENAI_COMPATIBLE", &credential, || {
+            self.settings.rate_limits.clone()
+        })
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        // Configured models are read from the config each time
+        self.settings
+            .models
+            .is_empty()
+            .then(|| format!("{}{}", self.settings.host, self.settings.path))
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        if !self.settings.models.is_empty() {
+            return Ok(Some(self.settings.models.clone()));
+        }
+
+        // Servers that follow the OpenAI layout list their models next to the chat endpoint
+        let Some(prefix) = self.settings.path.strip_suffix("chat/completions") else {
+            return Ok(None);
+        };
+        let url = url::Url::parse(&self.settings.host)
+            .and_then(|base_url| base_url.join(&format!("{prefix}models")))
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let mut request = self.client.get(url);
+        if let Some(api_key) = &self.settings.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        let payload = handle_response_openai_compat(request.send().await?).await?;
+
+        Ok(Some(get_model_ids(&payload)?))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
+    #[tracing::instrument(
+        skip(self, system, messages, tools, options),
+        fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
+    )]
+    async fn complete_with_options(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        options: &CompletionOptions,
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let mut payload =
+            create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
+
+        // Make request
+        let response = self.post(payload.clone()).await?;
+
+        // Parse response
+        let message = response_to_message(response.clone())?;
+        let usage = match get_usage(&response) {
+            Ok(usage) => usage,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage data: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        let model = get_model(&response);
+        emit_debug_trace(self, &payload, &response, &usage);
+        Ok((message, ProviderUsage::new(model, usage)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_partial_json, header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn chat_response(text: &str) -> Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "model": "qwen2.5-coder-32b",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": text},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_custom_path_and_headers() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(header("Authorization", "Bearer local-key"))
+            .and(header("X-Team", "tools"))
+            .and(body_partial_json(json!({"model": "qwen2.5-coder-32b"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("Hello!")))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let settings = OpenAiCompatibleSettings {
+            host: mock_server.uri(),
+            path: "api/chat".to_string(),
+            api_key: Some("local-key".to_string()),
+            headers: HashMap::from([("X-Team".to_string(), "tools".to_string())]),
+            models: vec!["qwen2.5-coder-32b".to_string()],
+            context_limit: Some(32_000),
+            ..Default::default()
+        };
+        let provider =
+            OpenAiCompatibleProvider::new(settings, ModelConfig::new("qwen2.5-coder-32b".into()))?;
+        assert_eq!(provider.get_model_config().context_limit(), 32_000);
+        assert_eq!(provider.models(), ["qwen2.5-coder-32b".to_string()]);
+
+        let (message, usage) = provider
+            .complete("You are helpful", &[Message::user().with_text("Hi")], &[])
+            .await?;
+
+        assert_eq!(message.content[0].as_text(), Some("Hello!"));
+        assert_eq!(usage.model, "qwen2.5-coder-32b");
+        assert_eq!(usage.usage.total_tokens, Some(15));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_instance_http_settings() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(chat_response("Too late"))
+                    .set_delay(std::time::Duration::from_secs(5)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        // Timeouts are set per instance like the other HTTP settings
+        let temp_file = tempfile::NamedTempFile::new()?;
+        let config = Config::new(temp_file.path(), "goose-test-openai-compatible")?;
+        config.set("OPENAI_COMPATIBLE_TIMEOUT_TEST_TIMEOUT", json!(1))?;
+        let settings = OpenAiCompatibleSettings {
+            host: mock_server.uri(),
+            path: "v1/chat/completions".to_string(),
+            key_prefix: Some("OPENAI_COMPATIBLE_TIMEOUT_TEST".to_string()),
+            ..Default::default()
+        };
+        let provider = OpenAiCompatibleProvider::with_config(
+            &config,
+            settings,
+            ModelConfig::new("local".into()),
+        )?;
+
+        let result = provider
+            .complete("You are helpful", &[Message::user().with_text("Hi")], &[])
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_complete_without_api_key() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("Hi there")))
+            .mount(&mock_server)
+            .await;
+
+        let settings = OpenAiCompatibleSettings {
+            host: mock_server.uri(),
+            path: OPENAI_COMPATIBLE_DEFAULT_PATH.to_string(),
+            ..Default::default()
+        };
+        let provider =
+            OpenAiCompatibleProvider::new(settings, ModelConfig::new("local-model".into()))?;
+
+        let (message, _) = provider
+            .complete("You are helpful", &[Message::user().with_text("Hi")], &[])
+            .await?;
+        assert_eq!(message.content[0].as_text(), Some("Hi there"));
+
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(requests[0].headers.get("Authorization").is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_supported_models() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("Authorization", "Bearer local-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [{"id": "qwen2.5-coder-32b"}, {"id": "llama-3.3-70b"}]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let settings = OpenAiCompatibleSettings {
+            host: mock_server.uri(),
+            path: OPENAI_COMPATIBLE_DEFAULT_PATH.to_string(),
+            api_key: Some("local-key".to_string()),
+            ..Default::default()
+        };
+        let provider =
+            OpenAiCompatibleProvider::new(settings.clone(), ModelConfig::new("local".into()))?;
+        assert_eq!(
+            provider.fetch_supported_models().await?,
+            Some(vec![
+                "llama-3.3-70b".to_string(),
+                "qwen2.5-coder-32b".to_string()
+            ])
+        );
+
+        // Configured models are used without asking the server
+        let provider = OpenAiCompatibleProvider::new(
+            OpenAiCompatibleSettings {
+                models: vec!["configured".to_string()],
+                ..settings
+            },
+            ModelConfig::new("local".into()),
+        )?;
+        assert_eq!(
+            provider.fetch_supported_models().await?,
+            Some(vec!["configured".to_string()])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_prefix() {
+        assert_eq!(
+            OpenAiCompatibleSettings::key_prefix(None),
+            "OPENAI_COMPATIBLE"
+        );
+        assert_eq!(
+            OpenAiCompatibleSettings::key_prefix(Some("lm-studio")),
+            "OPENAI_COMPATIBLE_LM_STUDIO"
+        );
+    }
+
+    #[test]
+    fn test_from_config_named_instance() -> Result<()> {
+        let temp_file = tempfile::NamedTempFile::new()?;
+        let config = Config::new(temp_file.path(), "goose-test-openai-compatible")?;
+        config.set("OPENAI_COMPATIBLE_VLLM_HOST", json!("http://gpu-box:8000"))?;
+        config.set("OPENAI_COMPATIBLE_VLLM_MODELS", json!(["llama-3.3-70b"]))?;
+        config.set("OPENAI_COMPATIBLE_VLLM_HEADERS", json!({"X-Team": "tools"}))?;
+        config.set("OPENAI_COMPATIBLE_VLLM_CONTEXT_LIMIT", json!(65536))?;
+        config.set("OPENAI_COMPATIBLE_VLLM_REQUESTS_PER_MINUTE", json!(30))?;
+
+        let settings = OpenAiCompatibleSettings::from_config(&config, Some("vllm"))?;
+        assert_eq!(settings.host, "http://gpu-box:8000");
+        assert_eq!(settings.path, OPENAI_COMPATIBLE_DEFAULT_PATH);
+        assert_eq!(settings.models, vec!["llama-3.3-70b".to_string()]);
+        assert_eq!(settings.headers.get("X-Team"), Some(&"tools".to_string()));
+        assert_eq!(settings.context_limit, Some(65536));
+        assert_eq!(settings.rate_limits.requests_per_minute, Some(30));
+        assert_eq!(settings.rate_limits.tokens_per_minute, None);
+        assert_eq!(
+            settings.key_prefix.as_deref(),
+            Some("OPENAI_COMPATIBLE_VLLM")
+        );
+
+        // The unnamed instance is configured separately
+        assert!(OpenAiCompatibleSettings::from_config(&config, None).is_err());
+        Ok(())
+    }
+}
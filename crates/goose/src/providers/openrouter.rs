@@ -1,15 +1,20 @@
+use std::sync::Arc;
+
 use anyhow::{Error, Result};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::{json, Value};
-use std::time::Duration;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{CompletionOptions, ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
-use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat};
+use super::http::provider_client;
+use super::rate_limit::{rate_limiter, RateLimiter};
+use super::utils::{emit_debug_trace, get_model, get_model_list, handle_response_openai_compat};
 use crate::message::Message;
 use crate::model::ModelConfig;
-use crate::providers::formats::openai::{create_request, get_usage, response_to_message};
+use crate::providers::formats::openai::{
+    add_tool_choice, create_request, get_usage, response_to_message,
+};
 use mcp_core::tool::Tool;
 use url::Url;
 
@@ -44,9 +49,7 @@ impl OpenRouterProvider {
             .get("OPENROUTER_HOST")
             .unwrap_or_else(|_| "https://openrouter.ai".to_string());
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let client = provider_client("OPENROUTER")?;
 
         Ok(Self {
             client,
@@ -204,18 +207,65 @@ impl Provider for OpenRouterProvider {
         self.model.clone()
     }
 
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        rate_limiter("OPENROUTER", &self.api_key)
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        Some(self.host.clone())
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let url = Url::parse(&self.host)
+            .and_then(|base_url| base_url.join("api/v1/models"))
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+
+        let response = self
+            .client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+        let payload = handle_response_openai_compat(response).await?;
+
+        // Goose needs tool calling, skip models that are known not to support it
+        let mut models: Vec<String> = get_model_list(&payload, "data")?
+            .iter()
+            .filter(|model| {
+                model["supported_parameters"]
+                    .as_array()
+                    .is_none_or(|params| params.iter().any(|p| p == "tools"))
+            })
+            .filter_map(|model| model["id"].as_str().map(String::from))
+            .collect();
+        models.sort();
+        Ok(Some(models))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.complete_with_options(system, messages, tools, &CompletionOptions::default())
+            .await
+    }
+
     #[tracing::instrument(
-        skip(self, system, messages, tools),
+        skip(self, system, messages, tools, options),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
     )]
-    async fn complete(
+    async fn complete_with_options(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
         // Create the base payload
-        let payload = create_request_based_on_model(&self.model, system, messages, tools)?;
+        let mut payload = create_request_based_on_model(&self.model, system, messages, tools)?;
+        add_tool_choice(&mut payload, &options.tool_choice, tools)?;
 
         // Make request
         let response = self.post(payload.clone()).await?;
@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use mcp_core::tool::Tool;
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+
+use super::base::{CompletionOptions, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use crate::model_registry::ModelCapabilities;
+use crate::token_counter::TokenCounter;
+
+lazy_static! {
+    /// Limiters shared by all providers in the process, keyed by provider and credential
+    static ref RATE_LIMITERS: Mutex<HashMap<String, Arc<RateLimiter>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Client side limits of the requests sent with one API key
+///
+/// Read from `<PREFIX>_REQUESTS_PER_MINUTE` and `<PREFIX>_TOKENS_PER_MINUTE`, where the prefix
+/// is the same as for the provider's other settings, e.g. OPENAI_REQUESTS_PER_MINUTE.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl RateLimits {
+    pub fn from_config(config: &Config, key_prefix: &str) -> Self {
+        let limit = |key: String| config.get::<u32>(&key).ok().filter(|limit| *limit > 0);
+        Self {
+            requests_per_minute: limit(format!("{key_prefix}_REQUESTS_PER_MINUTE")),
+            tokens_per_minute: limit(format!("{key_prefix}_TOKENS_PER_MINUTE")),
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_minute.is_none()
+    }
+}
+
+/// A token bucket holding up to one minute of its limit, refilled continuously
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    refill_per_second: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn per_minute(limit: u32, now: Instant) -> Self {
+        let capacity = f64::from(limit);
+        Self {
+            capacity,
+            available: capacity,
+            refill_per_second: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// How long until `amount` is available, requests larger than the bucket only wait
+    /// for it to be full
+    fn wait_time(&self, amount: f64) -> Duration {
+        let missing = amount.min(self.capacity) - self.available;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.refill_per_second)
+        }
+    }
+
+    /// Remove `amount` from the bucket, which may go negative to account for usage
+    /// reported after the fact
+    fn take(&mut self, amount: f64) {
+        self.available -= amount;
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+}
+
+impl Buckets {
+    fn refill(&mut self, now: Instant) {
+        self.requests.iter_mut().for_each(|b| b.refill(now));
+        self.tokens.iter_mut().for_each(|b| b.refill(now));
+    }
+
+    fn wait_time(&self, tokens: f64) -> Duration {
+        let requests = self.requests.as_ref().map(|b| b.wait_time(1.0));
+        let tokens = self.tokens.as_ref().map(|b| b.wait_time(tokens));
+        requests.into_iter().chain(tokens).max().unwrap_or_default()
+    }
+}
+
+/// Limits the requests and tokens sent to a provider with one API key
+///
+/// Requests wait in [`RateLimiter::acquire`] until both buckets have room, so that agents
+/// sharing a key slow down instead of all failing with rate limit errors.
+#[derive(Debug)]
+pub struct RateLimiter {
+    name: String,
+    limits: RateLimits,
+    buckets: Mutex<Buckets>,
+    queued: AtomicUsize,
+}
+
+/// Counts a request as queued until it is dropped, also when the waiting future is
+/// cancelled
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl<'a> QueuedGuard<'a> {
+    /// Count a request as queued, returning the guard and the new queue length
+    fn new(queued: &'a AtomicUsize) -> (Self, usize) {
+        let queue_length = queued.fetch_add(1, Ordering::SeqCst) + 1;
+        (Self(queued), queue_length)
+    }
+}
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl RateLimiter {
+    pub fn new(name: impl Into<String>, limits: &RateLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            name: name.into(),
+            limits: limits.clone(),
+            buckets: Mutex::new(Buckets {
+                requests: limits
+                    .requests_per_minute
+                    .map(|l| Bucket::per_minute(l, now)),
+                tokens: limits.tokens_per_minute.map(|l| Bucket::per_minute(l, now)),
+            }),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wait until a request of about `tokens` input tokens can be sent, and take it from the
+    /// buckets
+    pub async fn acquire(&self, tokens: usize) {
+        let tokens = tokens as f64;
+        let mut queued = None;
+
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap();
+                buckets.refill(Instant::now());
+                let wait = buckets.wait_time(tokens);
+                if wait.is_zero() {
+                    buckets.requests.iter_mut().for_each(|b| b.take(1.0));
+                    buckets.tokens.iter_mut().for_each(|b| b.take(tokens));
+                }
+                wait
+            };
+            if wait.is_zero() {
+                break;
+            }
+
+            if queued.is_none() {
+                let (guard, queue_length) = QueuedGuard::new(&self.queued);
+                queued = Some(guard);
+                tracing::info!(
+                    limiter = %self.name,
+                    tokens,
+                    queued = queue_length,
+                    wait_ms = wait.as_millis() as u64,
+                    "Rate limit reached, request queued"
+                );
+            }
+            tokio::time::sleep(wait).await;
+        }
+
+        if queued.is_some() {
+            tracing::debug!(limiter = %self.name, "Queued request released");
+        }
+    }
+
+    /// Account for the tokens a request actually used, after acquiring it with an estimate
+    /// of `estimated_tokens`
+    pub fn record(&self, estimated_tokens: usize, usage: &Usage) {
+        let input = usage
+            .input_tokens
+            .map(|t| t.max(0) as f64)
+            .unwrap_or(estimated_tokens as f64);
+        let output = usage.output_tokens.unwrap_or(0).max(0) as f64;
+        let extra = input - estimated_tokens as f64 + output;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if let Some(bucket) = buckets.tokens.as_mut() {
+            bucket.refill(Instant::now());
+            bucket.take(extra);
+        }
+    }
+
+    /// The number of requests currently waiting
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+}
+
+/// The limiter shared by all providers using `credential` with the provider whose config keys
+/// start with `key_prefix`, or `None` if no limits are configured
+pub fn rate_limiter(key_prefix: &str, credential: &str) -> Option<Arc<RateLimiter>> {
+    shared_rate_limiter(key_prefix, credential, || {
+        RateLimits::from_config(Config::global(), key_prefix)
+    })
+}
+
+/// Like [`rate_limiter`] with limits not read from the provider's config keys
+///
+/// Calls for the same provider and credential get the same limiter as long as the limits
+/// don't change, a change starts a new one.
+pub fn shared_rate_limiter(
+    name: &str,
+    credential: &str,
+    limits: impl FnOnce() -> RateLimits,
+) -> Option<Arc<RateLimiter>> {
+    let hash = format!("{:x}", Sha256::digest(credential.as_bytes()));
+    let key = format!("{name}:{}", &hash[..16]);
+    let limits = limits();
+
+    let mut limiters = RATE_LIMITERS.lock().unwrap();
+    if limits.is_unlimited() {
+        limiters.remove(&key);
+        return None;
+    }
+    let limiter = limiters
+        .entry(key)
+        .and_modify(|limiter| {
+            if limiter.limits != limits {
+                *limiter = Arc::new(RateLimiter::new(name, &limits));
+            }
+        })
+        .or_insert_with(|| Arc::new(RateLimiter::new(name, &limits)));
+    Some(limiter.clone())
+}
+
+/// Waits on the provider's [`RateLimiter`] before each completion
+///
+/// [`super::create`] wraps every provider in this, so that all requests sent with a key
+/// count towards its limits.
+pub struct RateLimitedProvider {
+    inner: Box<dyn Provider + Send + Sync>,
+    // Only loaded once limits are configured, to estimate the tokens of a request
+    token_counter: OnceLock<TokenCounter>,
+}
+
+impl RateLimitedProvider {
+    pub fn new(inner: Box<dyn Provider + Send + Sync>) -> Self {
+        Self {
+            inner,
+            token_counter: OnceLock::new(),
+        }
+    }
+
+    /// Wait for the limiter, returning it with the estimated tokens of the request
+    async fn acquire(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Option<(Arc<RateLimiter>, usize)> {
+        let limiter = self.inner.rate_limiter()?;
+        let token_counter = self
+            .token_counter
+            .get_or_init(|| TokenCounter::new(self.inner.get_model_config().tokenizer_name()));
+        let tokens = token_counter.count_chat_tokens(system, messages, tools);
+        limiter.acquire(tokens).await;
+        Some((limiter, tokens))
+    }
+}
+
+#[async_trait]
+impl Provider for RateLimitedProvider {
+    fn metadata() -> ProviderMetadata {
+        // Only called on concrete providers, use the metadata of the wrapped one
+        ProviderMetadata::empty()
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let acquired = self.acquire(system, messages, tools).await;
+        let result = self.inner.complete(system, messages, tools).await;
+        if let (Some((limiter, tokens)), Ok((_, usage))) = (&acquired, &result) {
+            limiter.record(*tokens, &usage.usage);
+        }
+        result
+    }
+
+    async fn complete_with_options(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        options: &CompletionOptions,
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let acquired = self.acquire(system, messages, tools).await;
+        let result = self
+            .inner
+            .complete_with_options(system, messages, tools, options)
+            .await;
+        if let (Some((limiter, tokens)), Ok((_, usage))) = (&acquired, &result) {
+            limiter.record(*tokens, &usage.usage);
+        }
+        result
+    }
+
+    fn supports_response_schema(&self) -> bool {
+        self.inner.supports_response_schema()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn model_capabilities(&self) -> ModelCapabilities {
+        self.inner.model_capabilities()
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models().await
+    }
+
+    fn supported_models_key(&self) -> Option<String> {
+        self.inner.supported_models_key()
+    }
+
+    fn lists_models_without_login(&self) -> bool {
+        self.inner.lists_models_without_login()
+    }
+
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.inner.rate_limiter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    fn limits(requests_per_minute: Option<u32>, tokens_per_minute: Option<u32>) -> RateLimits {
+        RateLimits {
+            requests_per_minute,
+            tokens_per_minute,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_per_minute() {
+        let limiter = RateLimiter::new("test", &limits(Some(2), None));
+        let start = Instant::now();
+
+        limiter.acquire(0).await;
+        limiter.acquire(0).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // The third request waits for one request to refill, 30 seconds at 2 per minute
+        limiter.acquire(0).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(30) && elapsed < Duration::from_secs(31));
+        assert_eq!(limiter.queued(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokens_per_minute() {
+        let limiter = RateLimiter::new("test", &limits(None, Some(600)));
+        let start = Instant::now();
+
+        limiter.acquire(500).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // 100 tokens left, 200 more refill in 20 seconds
+        limiter.acquire(300).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(20) && elapsed < Duration::from_secs(21));
+
+        // Larger requests than the limit wait for a full bucket instead of forever
+        limiter.acquire(10_000).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(80) && elapsed < Duration::from_secs(81));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_record_usage() {
+        let limiter = RateLimiter::new("test", &limits(None, Some(600)));
+        let start = Instant::now();
+
+        limiter.acquire(100).await;
+        // The request used 100 more input tokens than estimated and produced 400
+        limiter.record(100, &Usage::new(Some(200), Some(400), Some(600)));
+
+        limiter.acquire(60).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(6) && elapsed < Duration::from_secs(7));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queued_requests() {
+        let limiter = Arc::new(RateLimiter::new("test", &limits(Some(1), None)));
+        limiter.acquire(0).await;
+
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(0).await }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.queued(), 1);
+
+        waiting.await.unwrap();
+        assert_eq!(limiter.queued(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancelled_request_leaves_queue() {
+        let limiter = Arc::new(RateLimiter::new("test", &limits(Some(1), None)));
+        limiter.acquire(0).await;
+
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(0).await }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.queued(), 1);
+
+        waiting.abort();
+        assert!(waiting.await.unwrap_err().is_cancelled());
+        assert_eq!(limiter.queued(), 0);
+    }
+
+    struct LimitedProvider {
+        limiter: Arc<RateLimiter>,
+    }
+
+    #[async_trait]
+    impl Provider for LimitedProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            Ok((
+                Message::assistant().with_text("Hi"),
+                ProviderUsage::new("test-model".to_string(), Usage::default()),
+            ))
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("test-model".to_string())
+        }
+
+        fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+            Some(self.limiter.clone())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_provider() {
+        let provider = RateLimitedProvider::new(Box::new(LimitedProvider {
+            limiter: Arc::new(RateLimiter::new("test", &limits(Some(1), None))),
+        }));
+        let messages = [Message::user().with_text("Hello")];
+        let start = Instant::now();
+
+        provider.complete("system", &messages, &[]).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // Both ways of completing wait for the limiter
+        provider
+            .complete_with_options("system", &messages, &[], &CompletionOptions::default())
+            .await
+            .unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(60) && elapsed < Duration::from_secs(61));
+    }
+
+    #[test]
+    #[serial]
+    fn test_shared_by_credential() {
+        std::env::set_var("RATE_LIMIT_TEST_REQUESTS_PER_MINUTE", "10");
+
+        let first = rate_limiter("RATE_LIMIT_TEST", "key-a").unwrap();
+        let second = rate_limiter("RATE_LIMIT_TEST", "key-a").unwrap();
+        let other = rate_limiter("RATE_LIMIT_TEST", "key-b").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+
+        // Changed limits start a new limiter
+        std::env::set_var("RATE_LIMIT_TEST_REQUESTS_PER_MINUTE", "20");
+        let changed = rate_limiter("RATE_LIMIT_TEST", "key-a").unwrap();
+        assert!(!Arc::ptr_eq(&first, &changed));
+
+        std::env::remove_var("RATE_LIMIT_TEST_REQUESTS_PER_MINUTE");
+        assert!(rate_limiter("RATE_LIMIT_TEST", "key-a").is_none());
+        assert!(rate_limiter("RATE_LIMIT_UNSET_TEST", "key-a").is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_limits_configured_later() {
+        assert!(rate_limiter("RATE_LIMIT_LATER_TEST", "key-a").is_none());
+
+        std::env::set_var("RATE_LIMIT_LATER_TEST_TOKENS_PER_MINUTE", "1000");
+        let limiter = rate_limiter("RATE_LIMIT_LATER_TEST", "key-a");
+        std::env::remove_var("RATE_LIMIT_LATER_TEST_TOKENS_PER_MINUTE");
+        assert!(limiter.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn test_limits_from_config() {
+        std::env::set_var("RATE_LIMIT_CONFIG_TEST_TOKENS_PER_MINUTE", "40000");
+        std::env::set_var("RATE_LIMIT_CONFIG_TEST_REQUESTS_PER_MINUTE", "0");
+
+        let limits = RateLimits::from_config(Config::global(), "RATE_LIMIT_CONFIG_TEST");
+        assert_eq!(limits.tokens_per_minute, Some(40000));
+        assert_eq!(limits.requests_per_minute, None);
+
+        std::env::remove_var("RATE_LIMIT_CONFIG_TEST_TOKENS_PER_MINUTE");
+        std::env::remove_var("RATE_LIMIT_CONFIG_TEST_REQUESTS_PER_MINUTE");
+    }
+}
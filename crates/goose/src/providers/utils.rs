@@ -9,7 +9,7 @@ use std::io::Read;
 use std::path::Path;
 
 use crate::providers::errors::ProviderError;
-use mcp_core::content::ImageContent;
+use mcp_core::content::{AudioContent, DocumentContent, ImageContent};
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ImageFormat {
@@ -37,6 +37,44 @@ pub fn convert_image(image: &ImageContent, image_format: &ImageFormat) -> Value
     }
 }
 
+/// Whether a document mime type holds plain text that can be inlined into a prompt
+pub fn is_text_document(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/json" | "application/xml" | "application/yaml" | "application/x-yaml"
+        )
+}
+
+/// Render a document as text for providers that cannot accept it natively
+///
+/// Plain text documents are decoded and inlined, anything else is replaced by a
+/// short note so the model knows an attachment was dropped.
+pub fn document_to_text(document: &DocumentContent) -> String {
+    let name = document.name.as_deref().unwrap_or("attachment");
+    if is_text_document(&document.mime_type) {
+        if let Some(text) = base64::prelude::BASE64_STANDARD
+            .decode(&document.data)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+        {
+            return format!("Contents of document '{}':\n{}", name, text);
+        }
+    }
+    format!(
+        "[The user attached a document '{}' ({}) which this model cannot read]",
+        name, document.mime_type
+    )
+}
+
+/// Render audio as a text note for providers that cannot accept it natively
+pub fn audio_to_text(audio: &AudioContent) -> String {
+    format!(
+        "[The user attached an audio clip ({}) which this model cannot listen to]",
+        audio.mime_type
+    )
+}
+
 /// Handle response from OpenAI compatible endpoints
 /// Error codes: https://platform.openai.com/docs/guides/error-codes
 /// Context window exceeded: https://community.openai.com/t/help-needed-tackling-context-length-limits-in-openai-models/617543
@@ -113,6 +151,25 @@ pub fn get_model(data: &Value) -> String {
     }
 }
 
+/// The models listed under `key` in a response of a provider's models endpoint
+pub fn get_model_list<'a>(data: &'a Value, key: &str) -> Result<&'a [Value], ProviderError> {
+    data[key].as_array().map(Vec::as_slice).ok_or_else(|| {
+        ProviderError::RequestFailed(format!(
+            "Unexpected response listing models, `{key}` is missing"
+        ))
+    })
+}
+
+/// Extract the sorted model ids from an OpenAI style model list, `{"data": [{"id": ...}]}`
+pub fn get_model_ids(data: &Value) -> Result<Vec<String>, ProviderError> {
+    let mut ids: Vec<String> = get_model_list(data, "data")?
+        .iter()
+        .filter_map(|model| model["id"].as_str().map(String::from))
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
 /// Check if a file is actually an image by examining its magic bytes
 fn is_image_file(path: &Path) -> bool {
     if let Ok(mut file) = std::fs::File::open(path) {
@@ -227,6 +284,24 @@ pub fn unescape_json_values(value: &Value) -> Value {
     }
 }
 
+/// Merge extra fields into a request body, objects are merged recursively and other values
+/// replace what the request has
+pub fn merge_extra_body(payload: &mut Value, extra_body: &Map<String, Value>) {
+    let Some(target) = payload.as_object_mut() else {
+        return;
+    };
+    for (key, value) in extra_body {
+        match (target.get_mut(key), value) {
+            (Some(existing @ Value::Object(_)), Value::Object(extra)) => {
+                merge_extra_body(existing, extra)
+            }
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
 pub fn emit_debug_trace<T: serde::Serialize>(
     model_config: &T,
     payload: &impl serde::Serialize,
@@ -331,6 +406,30 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_document_to_text() {
+        let data = base64::prelude::BASE64_STANDARD.encode("hello world");
+        let document = DocumentContent {
+            data,
+            mime_type: "text/plain".to_string(),
+            name: Some("notes.txt".to_string()),
+            annotations: None,
+        };
+        assert_eq!(
+            document_to_text(&document),
+            "Contents of document 'notes.txt':\nhello world"
+        );
+
+        let document = DocumentContent {
+            data: "JVBERi0=".to_string(),
+            mime_type: "application/pdf".to_string(),
+            name: None,
+            annotations: None,
+        };
+        let text = document_to_text(&document);
+        assert!(text.contains("'attachment' (application/pdf)"));
+    }
+
     #[test]
     fn test_sanitize_function_name() {
         assert_eq!(sanitize_function_name("hello-world"), "hello-world");
@@ -395,4 +494,26 @@ mod tests {
         let unescaped_value = unescape_json_values(&value);
         assert_eq!(unescaped_value, json!({"text": "Hello World"}));
     }
+
+    #[test]
+    fn test_merge_extra_body() {
+        let mut payload = json!({
+            "model": "llama",
+            "options": {"temperature": 0.2, "num_ctx": 8192}
+        });
+        let extra = json!({
+            "options": {"num_ctx": 32768, "mirostat": 2},
+            "keep_alive": "5m"
+        });
+
+        merge_extra_body(&mut payload, extra.as_object().unwrap());
+        assert_eq!(
+            payload,
+            json!({
+                "model": "llama",
+                "options": {"temperature": 0.2, "num_ctx": 32768, "mirostat": 2},
+                "keep_alive": "5m"
+            })
+        );
+    }
 }
@@ -1,19 +1,60 @@
 use include_dir::{include_dir, Dir};
 use mcp_core::Tool;
-use std::error::Error;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
 use tokenizers::tokenizer::Tokenizer;
 
+use crate::config::Config;
 use crate::message::Message;
 
 // The embedded directory with all possible tokenizer files.
-// If one of them doesn’t exist, we’ll download it at startup.
+// If one of them doesn’t exist, we’ll look for it in the tokenizer directory or download it.
 static TOKENIZER_FILES: Dir = include_dir!("$CARGO_MANIFEST_DIR/../../tokenizer_files");
 
-/// The `TokenCounter` now stores exactly one `Tokenizer`.
+// <|im_start|>ROLE<|im_sep|>MESSAGE<|im_end|>
+const TOKENS_PER_MESSAGE: usize = 4;
+
+// Every reply is primed with <|start|>assistant<|message|>
+const REPLY_PRIMING_TOKENS: usize = 3;
+
+// Upper bound on memoized message counts, the cache is reset once it is exceeded
+const MAX_CACHED_MESSAGES: usize = 10_000;
+
+// Rough average for english text and code, used when no tokenizer is available
+const APPROXIMATE_CHARS_PER_TOKEN: usize = 4;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Error, Debug)]
+pub enum TokenizerError {
+    #[error("Failed to parse tokenizer '{0}': {1}")]
+    Parse(String, String),
+
+    #[error("Failed to download tokenizer '{0}': {1}")]
+    Download(String, String),
+
+    #[error("Failed to read tokenizer '{0}': {1}")]
+    Io(String, #[source] std::io::Error),
+
+    #[error("Tokenizer '{0}' is not available offline and GOOSE_TOKENIZER_DOWNLOAD is false")]
+    DownloadDisabled(String),
+}
+
+/// The `TokenCounter` now stores exactly one `Tokenizer`, or none at all in which case
+/// counts are approximated from the number of characters.
+///
+/// Counts are memoized per message (keyed by a hash of its role and content) and for the most
+/// recently seen tool set, so recounting a long session only tokenizes what changed.
 pub struct TokenCounter {
-    tokenizer: Tokenizer,
+    tokenizer: Option<Tokenizer>,
+    message_cache: Mutex<HashMap<u64, usize>>,
+    tools_cache: Mutex<Option<(u64, usize)>>,
 }
 
 impl TokenCounter {
@@ -21,98 +62,191 @@ impl TokenCounter {
     ///
     /// * `tokenizer_name` might look like "Xenova--gpt-4o"
     ///   or "Qwen--Qwen2.5-Coder-32B-Instruct", etc.
+    ///
+    /// If the tokenizer cannot be loaded, this logs a warning and falls back to an
+    /// approximate character based counter. Use [`TokenCounter::try_new`] to handle
+    /// the error instead.
     pub fn new(tokenizer_name: &str) -> Self {
-        match Self::load_from_embedded(tokenizer_name) {
-            Ok(tokenizer) => Self { tokenizer },
-            Err(e) => {
-                println!(
-                    "Tokenizer '{}' not found in embedded dir: {}",
-                    tokenizer_name, e
-                );
-                println!("Attempting to download tokenizer and load...");
-                // Fallback to download tokenizer and load from disk
-                match Self::download_and_load(tokenizer_name) {
-                    Ok(counter) => counter,
-                    Err(e) => panic!("Failed to initialize tokenizer: {}", e),
-                }
-            }
+        Self::try_new(tokenizer_name).unwrap_or_else(|e| {
+            tracing::warn!("{}, falling back to approximate token counts", e);
+            Self::approximate()
+        })
+    }
+
+    /// Creates a new `TokenCounter`, looking for the tokenizer in order:
+    /// 1. The tokenizers embedded in the binary
+    /// 2. `<tokenizer dir>/<tokenizer_name>/tokenizer.json` on disk
+    /// 3. A download from Hugging Face into the tokenizer dir
+    ///
+    /// The tokenizer dir is set by GOOSE_TOKENIZER_DIR and defaults to the temp directory.
+    /// Setting GOOSE_TOKENIZER_DOWNLOAD to false disables the download.
+    pub fn try_new(tokenizer_name: &str) -> Result<Self, TokenizerError> {
+        if let Some(tokenizer) = Self::load_from_embedded(tokenizer_name)? {
+            return Ok(Self::from_tokenizer(Some(tokenizer)));
         }
+
+        tracing::debug!(
+            "Tokenizer '{}' not embedded, loading from {}",
+            tokenizer_name,
+            Self::tokenizer_dir().display()
+        );
+        let tokenizer = Self::load_from_dir(tokenizer_name, &Self::tokenizer_dir())?;
+        Ok(Self::from_tokenizer(Some(tokenizer)))
+    }
+
+    /// A `TokenCounter` that estimates counts from the number of characters
+    pub fn approximate() -> Self {
+        Self::from_tokenizer(None)
+    }
+
+    /// Whether counts are estimated rather than computed with a real tokenizer
+    pub fn is_approximate(&self) -> bool {
+        self.tokenizer.is_none()
+    }
+
+    fn from_tokenizer(mut tokenizer: Option<Tokenizer>) -> Self {
+        // Batches are padded to their longest text, which would count the padding too
+        if let Some(tokenizer) = tokenizer.as_mut() {
+            tokenizer.with_padding(None);
+        }
+        Self {
+            tokenizer,
+            message_cache: Mutex::new(HashMap::new()),
+            tools_cache: Mutex::new(None),
+        }
+    }
+
+    /// The directory tokenizers are loaded from and downloaded to when they aren't embedded
+    pub fn tokenizer_dir() -> PathBuf {
+        Config::global()
+            .get::<String>("GOOSE_TOKENIZER_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir())
+    }
+
+    /// Whether missing tokenizers may be downloaded from Hugging Face
+    fn download_enabled() -> bool {
+        Config::global()
+            .get::<bool>("GOOSE_TOKENIZER_DOWNLOAD")
+            .unwrap_or(true)
     }
 
     /// Load tokenizer bytes from the embedded directory (via `include_dir!`).
-    fn load_from_embedded(tokenizer_name: &str) -> Result<Tokenizer, Box<dyn Error>> {
+    fn load_from_embedded(tokenizer_name: &str) -> Result<Option<Tokenizer>, TokenizerError> {
         let tokenizer_file_path = format!("{}/tokenizer.json", tokenizer_name);
-        let file = TOKENIZER_FILES
-            .get_file(&tokenizer_file_path)
-            .ok_or_else(|| {
-                format!(
-                    "Tokenizer file not found in embedded: {}",
-                    tokenizer_file_path
-                )
-            })?;
-        let contents = file.contents();
-        let tokenizer = Tokenizer::from_bytes(contents)
-            .map_err(|e| format!("Failed to parse tokenizer bytes: {}", e))?;
-        Ok(tokenizer)
+        match TOKENIZER_FILES.get_file(&tokenizer_file_path) {
+            Some(file) => Tokenizer::from_bytes(file.contents())
+                .map(Some)
+                .map_err(|e| TokenizerError::Parse(tokenizer_name.to_string(), e.to_string())),
+            None => Ok(None),
+        }
     }
 
     /// Fallback: If not found in embedded, we look in `base_dir` on disk.
     /// If not on disk, we download from Hugging Face, then load from disk.
-    fn download_and_load(tokenizer_name: &str) -> Result<Self, Box<dyn Error>> {
-        let local_dir = std::env::temp_dir().join(tokenizer_name);
+    fn load_from_dir(tokenizer_name: &str, base_dir: &Path) -> Result<Tokenizer, TokenizerError> {
+        let local_dir = base_dir.join(tokenizer_name);
         let local_json_path = local_dir.join("tokenizer.json");
 
         // If the file doesn't already exist, we download from HF
-        if !Path::new(&local_json_path).exists() {
-            eprintln!("Tokenizer file not on disk, downloading…");
-            let repo_id = tokenizer_name.replace("--", "/");
+        if !local_json_path.exists() {
+            if !Self::download_enabled() {
+                return Err(TokenizerError::DownloadDisabled(tokenizer_name.to_string()));
+            }
+            tracing::info!("Tokenizer '{}' not on disk, downloading", tokenizer_name);
             // e.g. "Xenova--llama3-tokenizer" -> "Xenova/llama3-tokenizer"
-            Self::download_tokenizer(&repo_id, &local_dir)?;
+            let repo_id = tokenizer_name.replace("--", "/");
+            Self::download_tokenizer(&repo_id, &local_dir)
+                .map_err(|e| TokenizerError::Download(tokenizer_name.to_string(), e))?;
         }
 
         // Load from disk
-        let file_content = fs::read(&local_json_path)?;
-        let tokenizer = Tokenizer::from_bytes(&file_content)
-            .map_err(|e| format!("Failed to parse tokenizer after download: {}", e))?;
-
-        Ok(Self { tokenizer })
+        let file_content = fs::read(&local_json_path)
+            .map_err(|e| TokenizerError::Io(tokenizer_name.to_string(), e))?;
+        Tokenizer::from_bytes(&file_content)
+            .map_err(|e| TokenizerError::Parse(tokenizer_name.to_string(), e.to_string()))
     }
 
-    /// Download from Hugging Face into the local directory if not already present.
-    /// Synchronous version using a blocking runtime for simplicity.
-    fn download_tokenizer(repo_id: &str, download_dir: &Path) -> Result<(), Box<dyn Error>> {
-        fs::create_dir_all(download_dir)?;
-
+    /// Download from Hugging Face into the local directory.
+    ///
+    /// The download runs on its own thread with a short-lived runtime, so this is safe to call
+    /// from within an async context.
+    fn download_tokenizer(repo_id: &str, download_dir: &Path) -> Result<(), String> {
         let file_url = format!(
             "https://huggingface.co/{}/resolve/main/tokenizer.json",
             repo_id
         );
-        let file_path = download_dir.join("tokenizer.json");
-
-        // Blocking for example: just spawn a short-lived runtime
-        let content = tokio::runtime::Runtime::new()?.block_on(async {
-            let response = reqwest::get(&file_url).await?;
-            if !response.status().is_success() {
-                let error_msg =
-                    format!("Failed to download tokenizer: status {}", response.status());
-                return Err(Box::<dyn Error>::from(error_msg));
-            }
-            let bytes = response.bytes().await?;
-            Ok(bytes)
-        })?;
 
-        fs::write(&file_path, content)?;
+        let content = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+            runtime.block_on(async {
+                let client = reqwest::Client::builder()
+                    .timeout(DOWNLOAD_TIMEOUT)
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                let response = client
+                    .get(&file_url)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    return Err(format!("status {}", response.status()));
+                }
+                response.bytes().await.map_err(|e| e.to_string())
+            })
+        })
+        .join()
+        .map_err(|_| "download thread panicked".to_string())??;
+
+        fs::create_dir_all(download_dir).map_err(|e| e.to_string())?;
+        fs::write(download_dir.join("tokenizer.json"), content).map_err(|e| e.to_string())?;
 
         Ok(())
     }
 
     /// Count tokens for a piece of text using our single tokenizer.
     pub fn count_tokens(&self, text: &str) -> usize {
-        let encoding = self.tokenizer.encode(text, false).unwrap();
-        encoding.len()
+        match &self.tokenizer {
+            Some(tokenizer) => tokenizer.encode(text, false).unwrap().len(),
+            None => approximate_token_count(text),
+        }
+    }
+
+    /// Count tokens for several pieces of text in a single batched tokenizer call.
+    pub fn count_tokens_batch(&self, texts: &[&str]) -> Vec<usize> {
+        let Some(tokenizer) = &self.tokenizer else {
+            return texts.iter().map(|t| approximate_token_count(t)).collect();
+        };
+        match texts.len() {
+            0 => Vec::new(),
+            1 => vec![self.count_tokens(texts[0])],
+            _ => tokenizer
+                .encode_batch(texts.to_vec(), false)
+                .unwrap()
+                .iter()
+                .map(|encoding| encoding.len())
+                .collect(),
+        }
     }
 
     pub fn count_tokens_for_tools(&self, tools: &[Tool]) -> usize {
+        if tools.is_empty() {
+            return 0;
+        }
+
+        let key = hash_tools(tools);
+        if let Some((cached_key, count)) = *self.tools_cache.lock().unwrap() {
+            if cached_key == key {
+                return count;
+            }
+        }
+
+        let count = self.count_tokens_for_tools_uncached(tools);
+        *self.tools_cache.lock().unwrap() = Some((key, count));
+        count
+    }
+
+    fn count_tokens_for_tools_uncached(&self, tools: &[Tool]) -> usize {
         // Token counts for different function components
         let func_init = 7; // Tokens for function initialization
         let prop_init = 3; // Tokens for properties initialization
@@ -163,52 +297,82 @@ impl TokenCounter {
         func_token_count
     }
 
+    /// Count the tokens a single message contributes to a chat, including its framing tokens.
+    pub fn count_message_tokens(&self, message: &Message) -> usize {
+        self.count_tokens_per_message(std::slice::from_ref(message))[0]
+    }
+
+    /// Count the tokens each message contributes to a chat, including framing tokens.
+    ///
+    /// Messages seen before are served from the cache; the remaining ones are tokenized
+    /// together in one batch.
+    pub fn count_tokens_per_message(&self, messages: &[Message]) -> Vec<usize> {
+        let keys: Vec<u64> = messages.iter().map(hash_message).collect();
+        let cached: Vec<Option<usize>> = {
+            let cache = self.message_cache.lock().unwrap();
+            keys.iter().map(|key| cache.get(key).copied()).collect()
+        };
+
+        // Gather the text of every message we haven't seen yet, remembering which message
+        // each piece of text belongs to
+        let mut counts = vec![TOKENS_PER_MESSAGE; messages.len()];
+        let mut texts = Vec::new();
+        let mut owners = Vec::new();
+        for (idx, message) in messages.iter().enumerate() {
+            match cached[idx] {
+                Some(count) => counts[idx] = count,
+                None => {
+                    for text in message_texts(message) {
+                        texts.push(text);
+                        owners.push(idx);
+                    }
+                }
+            }
+        }
+
+        let text_refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+        for (owner, count) in owners.into_iter().zip(self.count_tokens_batch(&text_refs)) {
+            counts[owner] += count;
+        }
+
+        let mut cache = self.message_cache.lock().unwrap();
+        if cache.len() + messages.len() > MAX_CACHED_MESSAGES {
+            cache.clear();
+        }
+        for (key, count) in keys.into_iter().zip(counts.iter()) {
+            cache.insert(key, *count);
+        }
+
+        counts
+    }
+
+    /// Forget all memoized message and tool counts.
+    pub fn clear_cache(&self) {
+        self.message_cache.lock().unwrap().clear();
+        *self.tools_cache.lock().unwrap() = None;
+    }
+
     pub fn count_chat_tokens(
         &self,
         system_prompt: &str,
         messages: &[Message],
         tools: &[Tool],
     ) -> usize {
-        // <|im_start|>ROLE<|im_sep|>MESSAGE<|im_end|>
-        let tokens_per_message = 4;
-
         // Count tokens in the system prompt
         let mut num_tokens = 0;
         if !system_prompt.is_empty() {
-            num_tokens += self.count_tokens(system_prompt) + tokens_per_message;
+            num_tokens += self.count_tokens(system_prompt) + TOKENS_PER_MESSAGE;
         }
 
-        for message in messages {
-            num_tokens += tokens_per_message;
-            // Count tokens in the content
-            for content in &message.content {
-                // content can either be text response or tool request
-                if let Some(content_text) = content.as_text() {
-                    num_tokens += self.count_tokens(content_text);
-                } else if let Some(tool_request) = content.as_tool_request() {
-                    // TODO: count tokens for tool request
-                    let tool_call = tool_request.tool_call.as_ref().unwrap();
-                    let text = format!(
-                        "{}:{}:{}",
-                        tool_request.id, tool_call.name, tool_call.arguments
-                    );
-                    num_tokens += self.count_tokens(&text);
-                } else if let Some(tool_response_text) = content.as_tool_response_text() {
-                    num_tokens += self.count_tokens(&tool_response_text);
-                } else {
-                    // unsupported content type such as image - pass
-                    continue;
-                }
-            }
-        }
+        num_tokens += self
+            .count_tokens_per_message(messages)
+            .iter()
+            .sum::<usize>();
 
         // Count tokens for tools if provided
-        if !tools.is_empty() {
-            num_tokens += self.count_tokens_for_tools(tools);
-        }
+        num_tokens += self.count_tokens_for_tools(tools);
 
-        // Every reply is primed with <|start|>assistant<|message|>
-        num_tokens += 3;
+        num_tokens += REPLY_PRIMING_TOKENS;
 
         num_tokens
     }
@@ -231,6 +395,53 @@ impl TokenCounter {
     }
 }
 
+fn approximate_token_count(text: &str) -> usize {
+    text.chars().count().div_ceil(APPROXIMATE_CHARS_PER_TOKEN)
+}
+
+/// The pieces of a message that count towards its token usage.
+fn message_texts(message: &Message) -> Vec<String> {
+    let mut texts = Vec::new();
+    for content in &message.content {
+        // content can either be text response or tool request
+        if let Some(content_text) = content.as_text() {
+            texts.push(content_text.to_string());
+        } else if let Some(tool_request) = content.as_tool_request() {
+            // Invalid tool calls are sent back to the model as their error
+            match &tool_request.tool_call {
+                Ok(tool_call) => texts.push(format!(
+                    "{}:{}:{}",
+                    tool_request.id, tool_call.name, tool_call.arguments
+                )),
+                Err(e) => texts.push(format!("{}:{}", tool_request.id, e)),
+            }
+        } else if let Some(tool_response_text) = content.as_tool_response_text() {
+            texts.push(tool_response_text);
+        }
+        // unsupported content type such as image - pass
+    }
+    texts
+}
+
+fn hash_message(message: &Message) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(&message.role)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    serde_json::to_string(&message.content)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+fn hash_tools(tools: &[Tool]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(tools)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,6 +450,8 @@ mod tests {
     use mcp_core::role::Role;
     use mcp_core::tool::Tool;
     use serde_json::json;
+    use serial_test::serial;
+    use tempfile::tempdir;
 
     #[test]
     fn test_claude_tokenizer() {
@@ -324,12 +537,174 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
-    fn test_panic_if_provided_tokenizer_doesnt_exist() {
-        // This should panic because the tokenizer doesn't exist
-        // in the embedded directory and the download fails
+    fn test_count_tokens_batch_matches_single() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+
+        let texts = [
+            "Hey there!",
+            "",
+            "What's the weather like in San Francisco?",
+        ];
+        let expected: Vec<usize> = texts.iter().map(|t| counter.count_tokens(t)).collect();
+
+        assert_eq!(counter.count_tokens_batch(&texts), expected);
+        // Counts don't depend on the other texts of the batch
+        let reversed: Vec<&str> = texts.iter().rev().copied().collect();
+        let mut counts = counter.count_tokens_batch(&reversed);
+        counts.reverse();
+        assert_eq!(counts, expected);
+        assert!(counter.count_tokens_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_message_counts_are_cached() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+
+        let messages = vec![
+            Message::user().with_text("What's the weather like in San Francisco?"),
+            Message::assistant().with_text("Looks like it's 60 degrees Fahrenheit."),
+        ];
+
+        let first = counter.count_tokens_per_message(&messages);
+        assert_eq!(counter.message_cache.lock().unwrap().len(), 2);
+        assert_eq!(
+            first[0],
+            4 + counter.count_tokens("What's the weather like in San Francisco?")
+        );
+
+        // Counting again returns the same result without growing the cache
+        let second = counter.count_tokens_per_message(&messages);
+        assert_eq!(first, second);
+        assert_eq!(counter.message_cache.lock().unwrap().len(), 2);
+
+        // A new message is counted on its own, and the total matches count_chat_tokens
+        let mut longer = messages.clone();
+        longer.push(Message::user().with_text("How about New York?"));
+        let counts = counter.count_tokens_per_message(&longer);
+        assert_eq!(counter.message_cache.lock().unwrap().len(), 3);
+        assert_eq!(&counts[..2], &first[..]);
+        assert_eq!(
+            counter.count_chat_tokens("", &longer, &[]),
+            counts.iter().sum::<usize>() + 3
+        );
+
+        counter.clear_cache();
+        assert!(counter.message_cache.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_tool_call_is_counted() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+        let error = mcp_core::ToolError::InvalidParameters("Could not parse arguments".into());
+        let message = Message::assistant().with_tool_request("call_1", Err(error.clone()));
+
+        let counts = counter.count_tokens_per_message(&[message]);
+        assert_eq!(
+            counts[0],
+            4 + counter.count_tokens(&format!("call_1:{}", error))
+        );
+    }
+
+    #[test]
+    fn test_tool_counts_are_cached_per_tool_set() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+
+        let weather = Tool::new(
+            "get_current_weather",
+            "Get the current weather in a given location",
+            json!({"properties": {"location": {"type": "string"}}}),
+        );
+        let time = Tool::new(
+            "get_current_time",
+            "Get the current time in a given timezone",
+            json!({"properties": {"timezone": {"type": "string"}}}),
+        );
+
+        let one = counter.count_tokens_for_tools(std::slice::from_ref(&weather));
+        assert_eq!(
+            one,
+            counter.count_tokens_for_tools(std::slice::from_ref(&weather))
+        );
+
+        let both = counter.count_tokens_for_tools(&[weather.clone(), time.clone()]);
+        assert_eq!(
+            both,
+            counter.count_tokens_for_tools_uncached(&[weather, time])
+        );
+        assert_eq!(counter.count_tokens_for_tools(&[]), 0);
+    }
+
+    // Ignored cause this tries to download the tokenizer from Hugging Face
+    #[test]
+    #[ignore]
+    #[serial]
+    fn test_error_if_provided_tokenizer_doesnt_exist() {
+        // This should fail because the tokenizer doesn't exist in the embedded
+        // directory or the tokenizer dir, and the download fails
+        let dir = tempdir().unwrap();
+        std::env::set_var("GOOSE_TOKENIZER_DIR", dir.path());
+
+        let result = TokenCounter::try_new("nonexistent-tokenizer");
+        assert!(matches!(result, Err(TokenizerError::Download(_, _))));
+
+        // new falls back to approximate counts instead of failing
+        let counter = TokenCounter::new("nonexistent-tokenizer");
+        assert!(counter.is_approximate());
+
+        std::env::remove_var("GOOSE_TOKENIZER_DIR");
+    }
+
+    #[test]
+    #[serial]
+    fn test_error_if_tokenizer_download_disabled() {
+        let dir = tempdir().unwrap();
+        std::env::set_var("GOOSE_TOKENIZER_DIR", dir.path());
+        std::env::set_var("GOOSE_TOKENIZER_DOWNLOAD", "false");
+
+        let result = TokenCounter::try_new("nonexistent-tokenizer");
+        assert!(matches!(result, Err(TokenizerError::DownloadDisabled(_))));
+
+        // new falls back to approximate counts instead of failing
+        let counter = TokenCounter::new("nonexistent-tokenizer");
+        assert!(counter.is_approximate());
+
+        // Embedded tokenizers don't need a download
+        assert!(!TokenCounter::new(GPT_4O_TOKENIZER).is_approximate());
+
+        std::env::remove_var("GOOSE_TOKENIZER_DOWNLOAD");
+        std::env::remove_var("GOOSE_TOKENIZER_DIR");
+    }
+
+    #[test]
+    #[serial]
+    fn test_load_tokenizer_from_configured_dir() {
+        let dir = tempdir().unwrap();
+        let tokenizer_dir = dir.path().join("local--tokenizer");
+        fs::create_dir_all(&tokenizer_dir).unwrap();
+        let embedded = TOKENIZER_FILES
+            .get_file(format!("{}/tokenizer.json", GPT_4O_TOKENIZER))
+            .unwrap();
+        fs::write(tokenizer_dir.join("tokenizer.json"), embedded.contents()).unwrap();
+        std::env::set_var("GOOSE_TOKENIZER_DIR", dir.path());
+
+        let counter = TokenCounter::try_new("local--tokenizer").unwrap();
+        assert!(!counter.is_approximate());
+        assert_eq!(counter.count_tokens("Hey there!"), 3);
+
+        std::env::remove_var("GOOSE_TOKENIZER_DIR");
+    }
+
+    #[test]
+    fn test_approximate_counts() {
+        let counter = TokenCounter::approximate();
+
+        assert_eq!(counter.count_tokens(""), 0);
+        assert_eq!(counter.count_tokens("abcd"), 1);
+        assert_eq!(counter.count_tokens("Hello, how are you?"), 5);
+        assert_eq!(counter.count_tokens_batch(&["abcd", "abcde"]), vec![1, 2]);
 
-        TokenCounter::new("nonexistent-tokenizer");
+        let messages = vec![Message::user().with_text("abcdefgh")];
+        assert_eq!(counter.count_chat_tokens("", &messages, &[]), 4 + 2 + 3);
     }
 
     // Optional test to confirm that fallback download works if not found in embedded:
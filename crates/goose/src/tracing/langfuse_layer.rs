@@ -1,4 +1,5 @@
 use crate::tracing::observation_layer::{BatchManager, ObservationLayer, SpanTracker};
+use crate::tracing::redact::redactor;
 use chrono::Utc;
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
@@ -176,6 +177,9 @@ pub fn create_langfuse_observer() -> Option<ObservationLayer> {
         LangfuseBatchManager::spawn_sender(batch_manager.clone());
     }
 
+    // Load the secrets to redact now, the layer can't read the config while handling events
+    redactor();
+
     Some(ObservationLayer {
         batch_manager,
         span_tracker: Arc::new(Mutex::new(SpanTracker::new())),
@@ -1,5 +1,6 @@
 pub mod langfuse_layer;
 mod observation_layer;
+pub mod redact;
 
 pub use langfuse_layer::{create_langfuse_observer, LangfuseBatchManager};
 pub use observation_layer::{
@@ -11,6 +11,8 @@ use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::Layer;
 use uuid::Uuid;
 
+use super::redact::redact;
+
 #[derive(Debug, Clone)]
 pub struct SpanData {
     pub observation_id: String, // Langfuse requires ids to be UUID v4 strings
@@ -330,10 +332,14 @@ impl Visit for JsonVisitor {
     record_field!(record_i64, i64);
     record_field!(record_u64, u64);
     record_field!(record_bool, bool);
-    record_field!(record_str, &str);
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert_value(field, Value::from(redact(value)));
+    }
 
     fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
-        self.insert_value(field, Value::String(format!("{:?}", value)));
+        let value = format!("{:?}", value);
+        self.insert_value(field, Value::String(redact(&value).into_owned()));
     }
 }
 
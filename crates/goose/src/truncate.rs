@@ -1,9 +1,13 @@
-use crate::message::Message;
+use crate::message::{Message, MessageContent};
 use anyhow::{anyhow, Result};
-use mcp_core::Role;
+use mcp_core::{Content, Role};
 use std::collections::HashSet;
 use tracing::debug;
 
+/// Text that replaces elided tool outputs
+pub const ELIDED_TOOL_RESPONSE: &str =
+    "[This tool output was removed to save context space. Run the tool again if you need it.]";
+
 /// Trait representing a truncation strategy
 pub trait TruncationStrategy {
     /// Determines the indices of messages to remove to fit within the context limit.
@@ -19,59 +23,270 @@ pub trait TruncationStrategy {
         token_counts: &[usize],
         context_limit: usize,
     ) -> Result<HashSet<usize>>;
+
+    /// Shrinks messages in place before any are removed, e.g. by replacing old tool
+    /// outputs with placeholders. Implementations must update `token_counts` for every
+    /// message they change, using `count_tokens`. The default does nothing.
+    fn compact_messages(
+        &self,
+        _messages: &mut [Message],
+        _token_counts: &mut [usize],
+        _context_limit: usize,
+        _count_tokens: &dyn Fn(&Message) -> usize,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the final turn is kept whole, so that the truncated conversation may still
+    /// end with its tool responses. Otherwise it is cut back to the last user text message.
+    fn keeps_final_turn(&self) -> bool {
+        false
+    }
 }
 
 /// Strategy to truncate messages by removing the oldest first
 pub struct OldestFirstTruncation;
 /// Strategy to truncate messages explicitly
 pub struct ExplicitTruncation;
+/// Strategy to truncate the oldest messages while keeping the first user message,
+/// which usually holds the task statement
+pub struct PreserveFirstUserMessageTruncation;
+/// Strategy to truncate messages from the middle of the conversation outward,
+/// keeping both the start and the most recent exchanges
+pub struct MiddleOutTruncation;
+/// Strategy that first replaces the oldest tool outputs with a placeholder and only
+/// then removes whole turns, oldest first. The tool outputs of the final turn are kept,
+/// since the model is usually about to act on them.
+pub struct ToolResponseElisionTruncation;
+
+/// Create a truncation strategy from its configuration name
+///
+/// Supported names are `oldest_first` (the default), `preserve_first_user_message`,
+/// `middle_out` and `elide_tool_responses`.
+pub fn strategy_from_name(name: &str) -> Result<Box<dyn TruncationStrategy + Send + Sync>> {
+    match name {
+        "oldest_first" => Ok(Box::new(OldestFirstTruncation)),
+        "preserve_first_user_message" => Ok(Box::new(PreserveFirstUserMessageTruncation)),
+        "middle_out" => Ok(Box::new(MiddleOutTruncation)),
+        "elide_tool_responses" => Ok(Box::new(ToolResponseElisionTruncation)),
+        _ => Err(anyhow!("Unknown truncation strategy: {}", name)),
+    }
+}
 
-impl TruncationStrategy for OldestFirstTruncation {
+/// Remove messages in the given order until the total fits the context limit,
+/// then mark the other half of any tool request/response pair that was removed
+fn remove_in_order(
+    order: impl Iterator<Item = usize>,
+    messages: &[Message],
+    token_counts: &[usize],
+    context_limit: usize,
+) -> HashSet<usize> {
+    let mut indices_to_remove = HashSet::new();
+    let mut total_tokens: usize = token_counts.iter().sum();
+
+    for i in order {
+        if total_tokens <= context_limit {
+            break;
+        }
+        if indices_to_remove.insert(i) {
+            total_tokens -= token_counts[i];
+            debug!(
+                "Removing message at index {}. Tokens removed: {}",
+                i, token_counts[i]
+            );
+        }
+    }
+
+    add_tool_pairs(messages, &mut indices_to_remove);
+    indices_to_remove
+}
+
+/// Whether a message starts a turn, i.e. is a user message that isn't a tool response
+fn is_turn_start(message: &Message) -> bool {
+    message.role == Role::User && message.has_only_text_content()
+}
+
+/// The index of the first message of the final turn
+fn final_turn_start(messages: &[Message]) -> usize {
+    messages.iter().rposition(is_turn_start).unwrap_or(0)
+}
+
+/// Extend the removal set with the matching request or response of every removed tool message
+fn add_tool_pairs(messages: &[Message], indices_to_remove: &mut HashSet<usize>) {
+    let tool_ids_to_remove: HashSet<(usize, String)> = indices_to_remove
+        .iter()
+        .flat_map(|&i| {
+            messages[i]
+                .get_tool_ids()
+                .into_iter()
+                .map(move |id| (i, id.to_string()))
+        })
+        .collect();
+
+    for (i, message) in messages.iter().enumerate() {
+        let message_tool_ids = message.get_tool_ids();
+        if tool_ids_to_remove.iter().any(|(message_idx, tool_id)| {
+            *message_idx != i && message_tool_ids.contains(tool_id.as_str())
+        }) {
+            indices_to_remove.insert(i);
+        }
+    }
+}
+
+impl TruncationStrategy for PreserveFirstUserMessageTruncation {
+    fn determine_indices_to_remove(
+        &self,
+        messages: &[Message],
+        token_counts: &[usize],
+        context_limit: usize,
+    ) -> Result<HashSet<usize>> {
+        let first_user_message = messages
+            .iter()
+            .position(|msg| msg.role == Role::User && msg.has_only_text_content());
+
+        let order = (0..messages.len()).filter(|&i| Some(i) != first_user_message);
+        Ok(remove_in_order(
+            order,
+            messages,
+            token_counts,
+            context_limit,
+        ))
+    }
+}
+
+impl TruncationStrategy for MiddleOutTruncation {
     fn determine_indices_to_remove(
         &self,
         messages: &[Message],
         token_counts: &[usize],
         context_limit: usize,
     ) -> Result<HashSet<usize>> {
+        if messages.len() < 3 {
+            return OldestFirstTruncation.determine_indices_to_remove(
+                messages,
+                token_counts,
+                context_limit,
+            );
+        }
+
+        // Never remove the first or the last message, and work outward from the middle,
+        // preferring older messages when two are equally far from it
+        let middle = messages.len() / 2;
+        let mut order: Vec<usize> = (1..messages.len() - 1).collect();
+        order.sort_by_key(|&i| (i.abs_diff(middle), i > middle));
+
+        Ok(remove_in_order(
+            order.into_iter(),
+            messages,
+            token_counts,
+            context_limit,
+        ))
+    }
+}
+
+impl TruncationStrategy for ToolResponseElisionTruncation {
+    fn determine_indices_to_remove(
+        &self,
+        messages: &[Message],
+        token_counts: &[usize],
+        context_limit: usize,
+    ) -> Result<HashSet<usize>> {
+        let final_turn = final_turn_start(messages);
         let mut indices_to_remove = HashSet::new();
         let mut total_tokens: usize = token_counts.iter().sum();
-        let mut tool_ids_to_remove = HashSet::new();
 
-        for (i, message) in messages.iter().enumerate() {
-            if total_tokens <= context_limit {
-                break;
-            }
-
-            // Remove the message
-            indices_to_remove.insert(i);
-            total_tokens -= token_counts[i];
+        let mut start = 0;
+        while start < final_turn && total_tokens > context_limit {
+            let end = (start + 1..final_turn)
+                .find(|&i| is_turn_start(&messages[i]))
+                .unwrap_or(final_turn);
+            indices_to_remove.extend(start..end);
+            total_tokens -= token_counts[start..end].iter().sum::<usize>();
             debug!(
-                "OldestFirst: Removing message at index {}. Tokens removed: {}",
-                i, token_counts[i]
+                "ElideToolResponses: Removing the turn of messages {} to {}",
+                start,
+                end - 1
             );
+            start = end;
+        }
 
-            // If it's a ToolRequest or ToolResponse, mark its pair for removal
-            if message.is_tool_call() || message.is_tool_response() {
-                message.get_tool_ids().iter().for_each(|id| {
-                    tool_ids_to_remove.insert((i, id.to_string()));
-                });
-            }
+        // The final turn alone doesn't fit, so it has to be cut into as well
+        if total_tokens > context_limit {
+            return OldestFirstTruncation.determine_indices_to_remove(
+                messages,
+                token_counts,
+                context_limit,
+            );
         }
 
-        // Now, find and remove paired ToolResponses or ToolRequests
-        for (i, message) in messages.iter().enumerate() {
-            let message_tool_ids = message.get_tool_ids();
-            // Find the other part of the pair - same tool_id but different message index
-            for (message_idx, tool_id) in &tool_ids_to_remove {
-                if message_idx != &i && message_tool_ids.contains(tool_id.as_str()) {
-                    indices_to_remove.insert(i);
-                    // No need to check other tool_ids for this message since it's already marked
-                    break;
+        add_tool_pairs(messages, &mut indices_to_remove);
+        Ok(indices_to_remove)
+    }
+
+    fn keeps_final_turn(&self) -> bool {
+        true
+    }
+
+    fn compact_messages(
+        &self,
+        messages: &mut [Message],
+        token_counts: &mut [usize],
+        context_limit: usize,
+        count_tokens: &dyn Fn(&Message) -> usize,
+    ) -> Result<()> {
+        let mut total_tokens: usize = token_counts.iter().sum();
+        let final_turn = final_turn_start(messages);
+
+        for (i, message) in messages.iter_mut().enumerate().take(final_turn) {
+            if total_tokens <= context_limit {
+                break;
+            }
+            if !message.is_tool_response() {
+                continue;
+            }
+
+            // Keep the response and its id so the request/response pairing stays intact
+            let mut elided = false;
+            for content in message.content.iter_mut() {
+                if let MessageContent::ToolResponse(response) = content {
+                    if let Ok(result) = &mut response.tool_result {
+                        if result.len() != 1 || result[0].as_text() != Some(ELIDED_TOOL_RESPONSE) {
+                            *result = vec![Content::text(ELIDED_TOOL_RESPONSE)];
+                            elided = true;
+                        }
+                    }
                 }
             }
+
+            if elided {
+                let new_count = count_tokens(message);
+                debug!(
+                    "ElideToolResponses: Elided tool output at index {}. Tokens removed: {}",
+                    i,
+                    token_counts[i].saturating_sub(new_count)
+                );
+                total_tokens = total_tokens - token_counts[i] + new_count;
+                token_counts[i] = new_count;
+            }
         }
 
-        Ok(indices_to_remove)
+        Ok(())
+    }
+}
+
+impl TruncationStrategy for OldestFirstTruncation {
+    fn determine_indices_to_remove(
+        &self,
+        messages: &[Message],
+        token_counts: &[usize],
+        context_limit: usize,
+    ) -> Result<HashSet<usize>> {
+        Ok(remove_in_order(
+            0..messages.len(),
+            messages,
+            token_counts,
+            context_limit,
+        ))
     }
 }
 
@@ -81,7 +296,7 @@ impl TruncationStrategy for OldestFirstTruncation {
 /// - messages: The vector of messages in the conversation.
 /// - token_counts: A parallel vector containing the token count for each message.
 /// - context_limit: The maximum allowed context length in tokens.
-/// - strategy: The truncation strategy to use. Only option is OldestFirstTruncation.
+/// - strategy: The truncation strategy to use, see `strategy_from_name` for the options.
 pub fn truncate_messages(
     messages: &mut Vec<Message>,
     token_counts: &mut Vec<usize>,
@@ -121,6 +336,19 @@ pub fn truncate_messages(
     let indices_to_remove =
         strategy.determine_indices_to_remove(messages, token_counts, context_limit)?;
 
+    // The start of the final turn after removal, if the strategy keeps it and it fit
+    let final_turn = final_turn_start(messages);
+    let final_turn = (strategy.keeps_final_turn()
+        && is_turn_start(&messages[final_turn])
+        && !indices_to_remove.contains(&final_turn))
+    .then(|| {
+        final_turn
+            - indices_to_remove
+                .iter()
+                .filter(|&&i| i < final_turn)
+                .count()
+    });
+
     // Step 3: Remove the marked messages
     // Vectorize the set and sort in reverse order to avoid shifting indices when removing
     let mut indices_to_remove = indices_to_remove.iter().cloned().collect::<Vec<usize>>();
@@ -134,9 +362,11 @@ pub fn truncate_messages(
         }
     }
 
-    // Step 4: Ensure the last message is a user message with TextContent only
+    // Step 4: Ensure the last message is a user message with TextContent only, or a tool
+    // response of a final turn that is kept
     while let Some(last_msg) = messages.last() {
-        if last_msg.role != Role::User || !last_msg.has_only_text_content() {
+        let in_final_turn = final_turn.is_some_and(|start| messages.len() - 1 > start);
+        if last_msg.role != Role::User || !(last_msg.has_only_text_content() || in_final_turn) {
             let _ = messages.pop().ok_or(anyhow!("Failed to pop message"))?;
             let removed_tokens = token_counts
                 .pop()
@@ -440,6 +670,219 @@ mod tests {
         Ok(())
     }
 
+    // Checks that each remaining tool id appears exactly twice (request + response)
+    fn assert_tool_pairs_intact(messages: &[Message]) {
+        let tool_ids: HashSet<_> = messages.iter().flat_map(|m| m.get_tool_ids()).collect();
+        for id in tool_ids {
+            let count = messages
+                .iter()
+                .flat_map(|m| m.get_tool_ids().into_iter())
+                .filter(|&tool_id| tool_id == id)
+                .count();
+            assert_eq!(count, 2, "Tool pair was split: {}", id);
+        }
+    }
+
+    // A conversation with a task statement, two tool round trips and a follow-up
+    fn create_tool_conversation() -> (Vec<Message>, Vec<usize>) {
+        let tool_call = ToolCall::new("shell", json!({"command": "ls"}));
+        let messages = vec![
+            user_text(0, 10).0,
+            assistant_tool_request("tool1", tool_call.clone(), 10).0,
+            user_tool_response("tool1", vec![Content::text("a".repeat(100))], 40).0,
+            assistant_text(1, 10).0,
+            user_text(2, 10).0,
+            assistant_tool_request("tool2", tool_call, 10).0,
+            user_tool_response("tool2", vec![Content::text("b".repeat(100))], 40).0,
+            assistant_text(3, 10).0,
+            user_text(4, 10).0,
+        ];
+        let token_counts = vec![10, 10, 40, 10, 10, 10, 40, 10, 10];
+        (messages, token_counts)
+    }
+
+    #[test]
+    fn test_strategy_from_name() {
+        for name in [
+            "oldest_first",
+            "preserve_first_user_message",
+            "middle_out",
+            "elide_tool_responses",
+        ] {
+            assert!(strategy_from_name(name).is_ok(), "{} should exist", name);
+        }
+        assert!(strategy_from_name("newest_first").is_err());
+    }
+
+    #[test]
+    fn test_preserve_first_user_message() -> Result<()> {
+        let (mut messages, mut token_counts) = create_messages_with_counts(5, 10, false);
+        let task = messages[0].clone();
+
+        truncate_messages(
+            &mut messages,
+            &mut token_counts,
+            50,
+            &PreserveFirstUserMessageTruncation,
+        )?;
+
+        assert_eq!(messages[0], task);
+        assert_eq!(messages.last().unwrap().as_concat_text(), "User message 8");
+        assert!(token_counts.iter().sum::<usize>() <= 50);
+        Ok(())
+    }
+
+    #[test]
+    fn test_preserve_first_user_message_with_tools() -> Result<()> {
+        let (mut messages, mut token_counts) = create_tool_conversation();
+        let task = messages[0].clone();
+
+        truncate_messages(
+            &mut messages,
+            &mut token_counts,
+            80,
+            &PreserveFirstUserMessageTruncation,
+        )?;
+
+        assert_eq!(messages[0], task);
+        assert!(token_counts.iter().sum::<usize>() <= 80);
+        assert_tool_pairs_intact(&messages);
+        Ok(())
+    }
+
+    #[test]
+    fn test_middle_out() -> Result<()> {
+        let (mut messages, mut token_counts) = create_messages_with_counts(4, 10, true);
+
+        truncate_messages(&mut messages, &mut token_counts, 40, &MiddleOutTruncation)?;
+
+        let texts: Vec<String> = messages.iter().map(|m| m.as_concat_text()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "User message 0",
+                "Assistant message 1",
+                "Assistant message 5",
+                "User message 6"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_middle_out_with_tools() -> Result<()> {
+        let (mut messages, mut token_counts) = create_tool_conversation();
+        let first = messages[0].clone();
+        let last = messages.last().unwrap().clone();
+
+        truncate_messages(&mut messages, &mut token_counts, 80, &MiddleOutTruncation)?;
+
+        assert_eq!(messages[0], first);
+        assert_eq!(messages.last().unwrap(), &last);
+        assert!(token_counts.iter().sum::<usize>() <= 80);
+        assert_tool_pairs_intact(&messages);
+        Ok(())
+    }
+
+    #[test]
+    fn test_elide_tool_responses_before_removing_messages() -> Result<()> {
+        let (mut messages, mut token_counts) = create_tool_conversation();
+        let count_tokens = |msg: &Message| {
+            if msg.content[0].as_tool_response_text().as_deref() == Some(ELIDED_TOOL_RESPONSE) {
+                5
+            } else {
+                40
+            }
+        };
+
+        // Eliding the first tool output (40 -> 5 tokens) is enough to fit in 120 tokens
+        let strategy = ToolResponseElisionTruncation;
+        strategy.compact_messages(&mut messages, &mut token_counts, 120, &count_tokens)?;
+        truncate_messages(&mut messages, &mut token_counts, 120, &strategy)?;
+
+        assert_eq!(messages.len(), 9);
+        assert_eq!(token_counts[2], 5);
+        assert_eq!(
+            messages[2].content[0].as_tool_response_text().as_deref(),
+            Some(ELIDED_TOOL_RESPONSE)
+        );
+        assert_eq!(
+            messages[6].content[0].as_tool_response_text(),
+            Some("b".repeat(100))
+        );
+        assert_tool_pairs_intact(&messages);
+        Ok(())
+    }
+
+    #[test]
+    fn test_elide_tool_responses_falls_back_to_removal() -> Result<()> {
+        let (mut messages, mut token_counts) = create_tool_conversation();
+        let count_tokens = |_: &Message| 5;
+
+        let strategy = ToolResponseElisionTruncation;
+        strategy.compact_messages(&mut messages, &mut token_counts, 50, &count_tokens)?;
+        assert_eq!(token_counts.iter().sum::<usize>(), 80);
+
+        truncate_messages(&mut messages, &mut token_counts, 50, &strategy)?;
+
+        assert!(token_counts.iter().sum::<usize>() <= 50);
+        assert!(messages.len() < 9);
+        assert_tool_pairs_intact(&messages);
+        Ok(())
+    }
+
+    #[test]
+    fn test_elide_tool_responses_keeps_final_turn() -> Result<()> {
+        // A turn that is still running its tools, as when the agent asks for the next step
+        let (mut messages, mut token_counts) = create_tool_conversation();
+        messages.truncate(7);
+        token_counts.truncate(7);
+        let count_tokens = |_: &Message| 5;
+
+        let strategy = ToolResponseElisionTruncation;
+        strategy.compact_messages(&mut messages, &mut token_counts, 60, &count_tokens)?;
+        assert_eq!(
+            messages[2].content[0].as_tool_response_text().as_deref(),
+            Some(ELIDED_TOOL_RESPONSE)
+        );
+        assert_eq!(
+            messages[6].content[0].as_tool_response_text(),
+            Some("b".repeat(100))
+        );
+        assert_eq!(token_counts.iter().sum::<usize>(), 95);
+
+        // The older turn is removed as a whole instead
+        let indices = strategy.determine_indices_to_remove(&messages, &token_counts, 60)?;
+        assert_eq!(indices, HashSet::from([0, 1, 2, 3]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_elide_tool_responses_keeps_final_tool_pair() -> Result<()> {
+        // The agent truncates while a turn runs its tools, so it ends on a tool response
+        let (mut messages, mut token_counts) = create_tool_conversation();
+        messages.truncate(7);
+        token_counts.truncate(7);
+        let final_turn = messages[4..].to_vec();
+
+        truncate_messages(
+            &mut messages,
+            &mut token_counts,
+            70,
+            &ToolResponseElisionTruncation,
+        )?;
+        assert_eq!(messages, final_turn);
+        assert_eq!(token_counts, vec![10, 10, 40]);
+
+        // Other strategies end on the last user text message
+        let (mut messages, mut token_counts) = create_tool_conversation();
+        messages.truncate(7);
+        token_counts.truncate(7);
+        truncate_messages(&mut messages, &mut token_counts, 70, &OldestFirstTruncation)?;
+        assert_eq!(messages, final_turn[..1]);
+        Ok(())
+    }
+
     #[test]
     fn test_error_cases() -> Result<()> {
         // Test impossibly small context window
@@ -7,9 +7,12 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::atomic::{AtomicU64, Ordering};
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::Semaphore;
 use tower::{Service, ServiceExt}; // for Service::ready()
 
+/// How many requests a client sends to its server at once unless configured otherwise
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 8;
+
 pub type BoxError = Box<dyn std::error::Error + Sync + Send>;
 
 /// Error type for MCP client operations.
@@ -96,13 +99,18 @@ pub trait McpClientTrait: Send + Sync {
 }
 
 /// The MCP client is the interface for MCP operations.
+///
+/// Requests are multiplexed over the one transport: each request uses its own clone of the
+/// service and the transport matches responses to requests by their JSON-RPC id, so calls
+/// made concurrently are in flight at the same time, up to the configured limit.
 pub struct McpClient<S>
 where
     S: Service<JsonRpcMessage, Response = JsonRpcMessage> + Clone + Send + Sync + 'static,
     S::Error: Into<Error>,
     S::Future: Send,
 {
-    service: Mutex<S>,
+    service: S,
+    request_permits: Semaphore,
     next_id: AtomicU64,
     server_capabilities: Option<ServerCapabilities>,
     server_info: Option<Implementation>,
@@ -116,25 +124,37 @@ where
 {
     pub fn new(service: S) -> Self {
         Self {
-            service: Mutex::new(service),
+            service,
+            request_permits: Semaphore::new(DEFAULT_MAX_CONCURRENT_REQUESTS),
             next_id: AtomicU64::new(1),
             server_capabilities: None,
             server_info: None,
         }
     }
 
+    /// Limit how many requests can be in flight to the server at once
+    pub fn with_max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.request_permits = Semaphore::new(limit.max(1));
+        self
+    }
+
     /// Send a JSON-RPC request and check we don't get an error response.
     async fn send_request<R>(&self, method: &str, params: Value) -> Result<R, Error>
     where
         R: for<'de> Deserialize<'de>,
     {
-        let mut service = self.service.lock().await;
+        let _permit = self
+            .request_permits
+            .acquire()
+            .await
+            .map_err(|_| Error::NotReady)?;
+        let mut service = self.service.clone();
         service.ready().await.map_err(|_| Error::NotReady)?;
 
-        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let request_id = self.next_id.fetch_add(1, Ordering::SeqCst);
         let request = JsonRpcMessage::Request(JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
-            id: Some(id),
+            id: Some(request_id),
             method: method.to_string(),
             params: Some(params.clone()),
         });
@@ -158,7 +178,7 @@ where
                 id, result, error, ..
             }) => {
                 // Verify id matches
-                if id != Some(self.next_id.load(Ordering::SeqCst) - 1) {
+                if id != Some(request_id) {
                     return Err(Error::UnexpectedResponse(
                         "id mismatch for JsonRpcResponse".to_string(),
                     ));
@@ -175,7 +195,7 @@ where
                 }
             }
             JsonRpcMessage::Error(JsonRpcError { id, error, .. }) => {
-                if id != Some(self.next_id.load(Ordering::SeqCst) - 1) {
+                if id != Some(request_id) {
                     return Err(Error::UnexpectedResponse(
                         "id mismatch for JsonRpcError".to_string(),
                     ));
@@ -196,7 +216,7 @@ where
 
     /// Send a JSON-RPC notification.
     async fn send_notification(&self, method: &str, params: Value) -> Result<(), Error> {
-        let mut service = self.service.lock().await;
+        let mut service = self.service.clone();
         service.ready().await.map_err(|_| Error::NotReady)?;
 
         let notification = JsonRpcMessage::Notification(JsonRpcNotification {
@@ -347,3 +367,109 @@ where
         self.send_request("tools/call", params).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    /// A service that answers every request after a delay, tracking how many are in flight
+    #[derive(Clone, Default)]
+    struct SlowService {
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl Service<JsonRpcMessage> for SlowService {
+        type Response = JsonRpcMessage;
+        type Error = crate::transport::Error;
+        type Future = BoxFuture<'static, Result<JsonRpcMessage, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, message: JsonRpcMessage) -> Self::Future {
+            let in_flight = self.in_flight.clone();
+            let max_in_flight = self.max_in_flight.clone();
+            Box::pin(async move {
+                let JsonRpcMessage::Request(request) = message else {
+                    return Ok(JsonRpcMessage::Nil);
+                };
+
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                let result = match request.method.as_str() {
+                    "initialize" => serde_json::json!({
+                        "protocolVersion": "1.0.0",
+                        "capabilities": {"tools": {}},
+                        "serverInfo": {"name": "slow", "version": "1.0.0"}
+                    }),
+                    _ => serde_json::json!({
+                        "content": [{"type": "text", "text": request.id.unwrap().to_string()}]
+                    }),
+                };
+                Ok(JsonRpcMessage::Response(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(result),
+                    error: None,
+                }))
+            })
+        }
+    }
+
+    async fn initialized_client(service: SlowService, limit: usize) -> McpClient<SlowService> {
+        let mut client = McpClient::new(service).with_max_concurrent_requests(limit);
+        client
+            .initialize(
+                ClientInfo {
+                    name: "test".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_are_multiplexed() {
+        let service = SlowService::default();
+        let client = initialized_client(service.clone(), 8).await;
+
+        let calls = (0..3).map(|_| client.call_tool("shell", serde_json::json!({})));
+        let results = futures::future::join_all(calls).await;
+
+        // Every call gets the response to its own request
+        let mut ids: Vec<String> = results
+            .into_iter()
+            .map(|result| result.unwrap().content[0].as_text().unwrap().to_string())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["2", "3", "4"]);
+
+        assert_eq!(service.max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        let service = SlowService::default();
+        let client = initialized_client(service.clone(), 2).await;
+
+        let calls = (0..5).map(|_| client.call_tool("shell", serde_json::json!({})));
+        for result in futures::future::join_all(calls).await {
+            assert!(result.is_ok());
+        }
+
+        assert_eq!(service.max_in_flight.load(Ordering::SeqCst), 2);
+    }
+}
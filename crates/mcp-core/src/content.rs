@@ -50,6 +50,26 @@ pub struct ImageContent {
     pub annotations: Option<Annotations>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentContent {
+    pub data: String,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioContent {
+    pub data: String,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EmbeddedResource {
@@ -72,6 +92,8 @@ impl EmbeddedResource {
 pub enum Content {
     Text(TextContent),
     Image(ImageContent),
+    Document(DocumentContent),
+    Audio(AudioContent),
     Resource(EmbeddedResource),
 }
 
@@ -91,6 +113,27 @@ impl Content {
         })
     }
 
+    pub fn document<S: Into<String>, T: Into<String>>(
+        data: S,
+        mime_type: T,
+        name: Option<String>,
+    ) -> Self {
+        Content::Document(DocumentContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            name,
+            annotations: None,
+        })
+    }
+
+    pub fn audio<S: Into<String>, T: Into<String>>(data: S, mime_type: T) -> Self {
+        Content::Audio(AudioContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+        })
+    }
+
     pub fn resource(resource: ResourceContents) -> Self {
         Content::Resource(EmbeddedResource {
             resource,
@@ -125,11 +168,29 @@ impl Content {
         }
     }
 
+    /// Get the document content if this is a DocumentContent variant
+    pub fn as_document(&self) -> Option<&DocumentContent> {
+        match self {
+            Content::Document(document) => Some(document),
+            _ => None,
+        }
+    }
+
+    /// Get the audio content if this is an AudioContent variant
+    pub fn as_audio(&self) -> Option<&AudioContent> {
+        match self {
+            Content::Audio(audio) => Some(audio),
+            _ => None,
+        }
+    }
+
     /// Set the audience for the content
     pub fn with_audience(mut self, audience: Vec<Role>) -> Self {
         let annotations = match &mut self {
             Content::Text(text) => &mut text.annotations,
             Content::Image(image) => &mut image.annotations,
+            Content::Document(document) => &mut document.annotations,
+            Content::Audio(audio) => &mut audio.annotations,
             Content::Resource(resource) => &mut resource.annotations,
         };
         *annotations = Some(match annotations.take() {
@@ -156,6 +217,8 @@ impl Content {
         let annotations = match &mut self {
             Content::Text(text) => &mut text.annotations,
             Content::Image(image) => &mut image.annotations,
+            Content::Document(document) => &mut document.annotations,
+            Content::Audio(audio) => &mut audio.annotations,
             Content::Resource(resource) => &mut resource.annotations,
         };
         *annotations = Some(match annotations.take() {
@@ -177,6 +240,11 @@ impl Content {
         match self {
             Content::Text(text) => text.annotations.as_ref().and_then(|a| a.audience.as_ref()),
             Content::Image(image) => image.annotations.as_ref().and_then(|a| a.audience.as_ref()),
+            Content::Document(document) => document
+                .annotations
+                .as_ref()
+                .and_then(|a| a.audience.as_ref()),
+            Content::Audio(audio) => audio.annotations.as_ref().and_then(|a| a.audience.as_ref()),
             Content::Resource(resource) => resource
                 .annotations
                 .as_ref()
@@ -189,6 +257,8 @@ impl Content {
         match self {
             Content::Text(text) => text.annotations.as_ref().and_then(|a| a.priority),
             Content::Image(image) => image.annotations.as_ref().and_then(|a| a.priority),
+            Content::Document(document) => document.annotations.as_ref().and_then(|a| a.priority),
+            Content::Audio(audio) => audio.annotations.as_ref().and_then(|a| a.priority),
             Content::Resource(resource) => resource.annotations.as_ref().and_then(|a| a.priority),
         }
     }
@@ -197,6 +267,12 @@ impl Content {
         match self {
             Content::Text(text) => Content::text(text.text.clone()),
             Content::Image(image) => Content::image(image.data.clone(), image.mime_type.clone()),
+            Content::Document(document) => Content::document(
+                document.data.clone(),
+                document.mime_type.clone(),
+                document.name.clone(),
+            ),
+            Content::Audio(audio) => Content::audio(audio.data.clone(), audio.mime_type.clone()),
             Content::Resource(resource) => Content::resource(resource.resource.clone()),
         }
     }
@@ -220,6 +296,35 @@ mod tests {
         assert_eq!(content.as_image(), Some(("data", "image/png")));
     }
 
+    #[test]
+    fn test_content_document() {
+        let content = Content::document("data", "application/pdf", Some("spec.pdf".to_string()));
+        assert_eq!(content.as_text(), None);
+        let document = content.as_document().unwrap();
+        assert_eq!(document.mime_type, "application/pdf");
+        assert_eq!(document.name.as_deref(), Some("spec.pdf"));
+    }
+
+    #[test]
+    fn test_content_audio() {
+        let content = Content::audio("data", "audio/wav");
+        assert_eq!(content.as_image(), None);
+        let audio = content.as_audio().unwrap();
+        assert_eq!(audio.data, "data");
+        assert_eq!(audio.mime_type, "audio/wav");
+    }
+
+    #[test]
+    fn test_content_document_serialization() {
+        let content = Content::document("data", "text/plain", None);
+        let value = serde_json::to_value(&content).unwrap();
+        assert_eq!(value["type"], "document");
+        assert_eq!(value["mimeType"], "text/plain");
+        assert!(value.get("name").is_none());
+        let parsed: Content = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, content);
+    }
+
     #[test]
     fn test_content_annotations_basic() {
         let content = Content::text("hello")
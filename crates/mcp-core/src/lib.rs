@@ -1,5 +1,5 @@
 pub mod content;
-pub use content::{Annotations, Content, ImageContent, TextContent};
+pub use content::{Annotations, AudioContent, Content, DocumentContent, ImageContent, TextContent};
 pub mod handler;
 pub mod role;
 pub use role::Role;
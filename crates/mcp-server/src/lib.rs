@@ -25,6 +25,9 @@ pub struct ByteTransport<R, W> {
     reader: BufReader<R>,
     #[pin]
     writer: W,
+    // Bytes of the line currently being read, kept across polls so a line that
+    // arrives in several chunks isn't lost when a read returns Pending
+    line: Vec<u8>,
 }
 
 impl<R, W> ByteTransport<R, W>
@@ -38,6 +41,7 @@ where
             // allows the buffer to have the capacity to read very large calls
             reader: BufReader::with_capacity(2 * 1024 * 1024, reader),
             writer,
+            line: Vec::new(),
         }
     }
 }
@@ -51,13 +55,15 @@ where
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut this = self.project();
-        let mut buf = Vec::new();
 
         let mut reader = this.reader.as_mut();
-        let mut read_future = Box::pin(reader.read_until(b'\n', &mut buf));
-        match read_future.as_mut().poll(cx) {
-            Poll::Ready(Ok(0)) => Poll::Ready(None), // EOF
+        let mut read_future = Box::pin(reader.read_until(b'\n', this.line));
+        let result = read_future.as_mut().poll(cx);
+        drop(read_future);
+        match result {
+            Poll::Ready(Ok(0)) if this.line.is_empty() => Poll::Ready(None), // EOF
             Poll::Ready(Ok(_)) => {
+                let buf = std::mem::take(this.line);
                 // Convert to UTF-8 string
                 let line = match String::from_utf8(buf) {
                     Ok(s) => s,
@@ -132,21 +138,32 @@ where
     }
 
     // TODO transport trait instead of byte transport if we implement others
+    /// Serve requests from the transport until it closes
+    ///
+    /// Requests are handled concurrently: the next message is read while earlier requests
+    /// are still being processed, and each response is written as soon as it is ready.
     pub async fn run<R, W>(self, mut transport: ByteTransport<R, W>) -> Result<(), ServerError>
     where
         R: AsyncRead + Unpin,
         W: AsyncWrite + Unpin,
     {
-        use futures::StreamExt;
+        use futures::stream::{FuturesUnordered, StreamExt};
         let mut service = self.service;
+        let mut in_flight = FuturesUnordered::new();
 
         tracing::info!("Server started");
-        while let Some(msg_result) = transport.next().await {
-            let _span = tracing::span!(tracing::Level::INFO, "message_processing").entered();
-            match msg_result {
-                Ok(msg) => {
-                    match msg {
-                        JsonRpcMessage::Request(request) => {
+        loop {
+            tokio::select! {
+                Some(response) = in_flight.next(), if !in_flight.is_empty() => {
+                    Self::write_response(&mut transport, response).await?;
+                }
+                msg_result = transport.next() => {
+                    let Some(msg_result) = msg_result else {
+                        break;
+                    };
+                    let _span = tracing::span!(tracing::Level::INFO, "message_processing").entered();
+                    match msg_result {
+                        Ok(JsonRpcMessage::Request(request)) => {
                             // Serialize request for logging
                             let id = request.id;
                             let request_json = serde_json::to_string(&request)
@@ -160,87 +177,102 @@ where
                             );
 
                             // Process the request using our service
-                            let response = match service.call(request).await {
-                                Ok(resp) => resp,
-                                Err(e) => {
-                                    let error_msg = e.into().to_string();
-                                    tracing::error!(error = %error_msg, "Request processing failed");
-                                    JsonRpcResponse {
-                                        jsonrpc: "2.0".to_string(),
-                                        id,
-                                        result: None,
-                                        error: Some(mcp_core::protocol::ErrorData {
-                                            code: mcp_core::protocol::INTERNAL_ERROR,
-                                            message: error_msg,
-                                            data: None,
-                                        }),
+                            let response = service.call(request);
+                            in_flight.push(async move {
+                                match response.await {
+                                    Ok(resp) => resp,
+                                    Err(e) => {
+                                        let error_msg = e.into().to_string();
+                                        tracing::error!(error = %error_msg, "Request processing failed");
+                                        JsonRpcResponse {
+                                            jsonrpc: "2.0".to_string(),
+                                            id,
+                                            result: None,
+                                            error: Some(mcp_core::protocol::ErrorData {
+                                                code: mcp_core::protocol::INTERNAL_ERROR,
+                                                message: error_msg,
+                                                data: None,
+                                            }),
+                                        }
                                     }
                                 }
-                            };
-
-                            // Serialize response for logging
-                            let response_json = serde_json::to_string(&response)
-                                .unwrap_or_else(|_| "Failed to serialize response".to_string());
-
-                            tracing::info!(
-                                response_id = ?response.id,
-                                json = %response_json,
-                                "Sending response"
-                            );
-                            // Send the response back
-                            if let Err(e) = transport
-                                .write_message(JsonRpcMessage::Response(response))
-                                .await
-                            {
-                                return Err(ServerError::Transport(TransportError::Io(e)));
-                            }
+                            });
                         }
-                        JsonRpcMessage::Response(_)
-                        | JsonRpcMessage::Notification(_)
-                        | JsonRpcMessage::Nil
-                        | JsonRpcMessage::Error(_) => {
+                        Ok(JsonRpcMessage::Response(_))
+                        | Ok(JsonRpcMessage::Notification(_))
+                        | Ok(JsonRpcMessage::Nil)
+                        | Ok(JsonRpcMessage::Error(_)) => {
                             // Ignore responses, notifications and nil messages for now
                             continue;
                         }
-                    }
-                }
-                Err(e) => {
-                    // Convert transport error to JSON-RPC error response
-                    let error = match e {
-                        TransportError::Json(_) | TransportError::InvalidMessage(_) => {
-                            mcp_core::protocol::ErrorData {
-                                code: mcp_core::protocol::PARSE_ERROR,
-                                message: e.to_string(),
-                                data: None,
-                            }
-                        }
-                        TransportError::Protocol(_) => mcp_core::protocol::ErrorData {
-                            code: mcp_core::protocol::INVALID_REQUEST,
-                            message: e.to_string(),
-                            data: None,
-                        },
-                        _ => mcp_core::protocol::ErrorData {
-                            code: mcp_core::protocol::INTERNAL_ERROR,
-                            message: e.to_string(),
-                            data: None,
-                        },
-                    };
+                        Err(e) => {
+                            // Convert transport error to JSON-RPC error response
+                            let error = match e {
+                                TransportError::Json(_) | TransportError::InvalidMessage(_) => {
+                                    mcp_core::protocol::ErrorData {
+                                        code: mcp_core::protocol::PARSE_ERROR,
+                                        message: e.to_string(),
+                                        data: None,
+                                    }
+                                }
+                                TransportError::Protocol(_) => mcp_core::protocol::ErrorData {
+                                    code: mcp_core::protocol::INVALID_REQUEST,
+                                    message: e.to_string(),
+                                    data: None,
+                                },
+                                _ => mcp_core::protocol::ErrorData {
+                                    code: mcp_core::protocol::INTERNAL_ERROR,
+                                    message: e.to_string(),
+                                    data: None,
+                                },
+                            };
 
-                    let error_response = JsonRpcMessage::Error(JsonRpcError {
-                        jsonrpc: "2.0".to_string(),
-                        id: None,
-                        error,
-                    });
+                            let error_response = JsonRpcMessage::Error(JsonRpcError {
+                                jsonrpc: "2.0".to_string(),
+                                id: None,
+                                error,
+                            });
 
-                    if let Err(e) = transport.write_message(error_response).await {
-                        return Err(ServerError::Transport(TransportError::Io(e)));
+                            if let Err(e) = transport.write_message(error_response).await {
+                                return Err(ServerError::Transport(TransportError::Io(e)));
+                            }
+                        }
                     }
                 }
             }
         }
 
+        // Finish any requests still being processed once the input is closed
+        while let Some(response) = in_flight.next().await {
+            Self::write_response(&mut transport, response).await?;
+        }
+
         Ok(())
     }
+
+    async fn write_response<R, W>(
+        transport: &mut ByteTransport<R, W>,
+        response: JsonRpcResponse,
+    ) -> Result<(), ServerError>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        // Serialize response for logging
+        let response_json = serde_json::to_string(&response)
+            .unwrap_or_else(|_| "Failed to serialize response".to_string());
+
+        tracing::info!(
+            response_id = ?response.id,
+            json = %response_json,
+            "Sending response"
+        );
+        // Send the response back
+        transport
+            .write_message(JsonRpcMessage::Response(response))
+            .await
+            .map_err(|e| ServerError::Transport(TransportError::Io(e)))
+    }
 }
 
 // Define a specific service implementation that we need for any
@@ -267,3 +299,125 @@ impl<T> BoundedService for T where
         + 'static
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::time::Duration;
+    use tokio::io::{DuplexStream, Lines};
+
+    /// A service that answers `slow` requests after a delay and any other request right away
+    struct DelayService;
+
+    impl Service<JsonRpcRequest> for DelayService {
+        type Response = JsonRpcResponse;
+        type Error = BoxError;
+        type Future = Pin<Box<dyn Future<Output = Result<JsonRpcResponse, BoxError>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: JsonRpcRequest) -> Self::Future {
+            Box::pin(async move {
+                if request.method == "slow" {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                Ok(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(serde_json::json!({"method": request.method})),
+                    error: None,
+                })
+            })
+        }
+    }
+
+    fn request_line(id: u64, method: &str) -> String {
+        format!("{{\"jsonrpc\":\"2.0\",\"id\":{id},\"method\":\"{method}\"}}\n")
+    }
+
+    /// A server on in-memory pipes, with its input and output
+    fn server() -> (
+        impl Future<Output = Result<(), ServerError>>,
+        DuplexStream,
+        Lines<BufReader<DuplexStream>>,
+    ) {
+        let (input, server_input) = tokio::io::duplex(4096);
+        let (server_output, output) = tokio::io::duplex(4096);
+        let transport = ByteTransport::new(server_input, server_output);
+        let server = Server::new(DelayService).run(transport);
+        (server, input, BufReader::new(output).lines())
+    }
+
+    fn response_id(line: &str) -> Option<u64> {
+        let response: JsonRpcResponse = serde_json::from_str(line).unwrap();
+        response.id
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_written_as_they_finish() {
+        let (server, mut input, mut output) = server();
+        let client = async {
+            input
+                .write_all(request_line(1, "slow").as_bytes())
+                .await
+                .unwrap();
+            input
+                .write_all(request_line(2, "fast").as_bytes())
+                .await
+                .unwrap();
+
+            // The fast request is answered while the slow one is still running
+            let first = output.next_line().await.unwrap().unwrap();
+            let second = output.next_line().await.unwrap().unwrap();
+            assert_eq!(response_id(&first), Some(2));
+            assert_eq!(response_id(&second), Some(1));
+        };
+
+        tokio::select! {
+            _ = client => {}
+            result = server => panic!("server stopped early: {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_requests_finish_after_eof() {
+        let (server, mut input, mut output) = server();
+        input
+            .write_all(request_line(1, "slow").as_bytes())
+            .await
+            .unwrap();
+        drop(input);
+
+        server.await.unwrap();
+        let line = output.next_line().await.unwrap().unwrap();
+        assert_eq!(response_id(&line), Some(1));
+        assert!(output.next_line().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_line_split_across_reads() {
+        let (mut input, server_input) = tokio::io::duplex(4096);
+        let mut transport = ByteTransport::new(server_input, tokio::io::sink());
+        let line = request_line(7, "split");
+        let (start, rest) = line.split_at(10);
+        let (middle, end) = rest.split_at(10);
+
+        // Each partial read leaves the transport waiting for the rest of the line
+        for chunk in [start, middle] {
+            input.write_all(chunk.as_bytes()).await.unwrap();
+            assert!(futures::poll!(transport.next()).is_pending());
+        }
+        input.write_all(end.as_bytes()).await.unwrap();
+
+        match transport.next().await {
+            Some(Ok(JsonRpcMessage::Request(request))) => {
+                assert_eq!(request.id, Some(7));
+                assert_eq!(request.method, "split");
+            }
+            other => panic!("expected a request, got {other:?}"),
+        }
+    }
+}